@@ -0,0 +1,842 @@
+use crate::backend::GitBackend;
+use crate::changelog;
+use crate::cli;
+use crate::convention;
+use crate::diff;
+use crate::error::GustError;
+use crate::ignore;
+use crate::menus;
+use crate::settings::{self, Config};
+use crate::Commands;
+
+use clap::{Parser, Subcommand};
+use colored::Colorize;
+use std::path::PathBuf;
+
+/// Gust - Git, with rust. A simple git helper for the average user.
+///
+/// Run without a command to open the menus. Commands open their menu when
+/// the inputs they need aren't given on the command line.
+#[derive(Parser)]
+#[clap(version)]
+pub struct CliArguments {
+    /// Print the git commands that would change the repository instead of running them
+    #[clap(long, global = true)]
+    pub dry_run: bool,
+
+    /// Open the full-screen mode instead of the menus
+    #[clap(long)]
+    pub tui: bool,
+
+    #[clap(subcommand)]
+    pub command: Option<GustCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum GustCommand {
+    /// Stage files
+    Add {
+        /// Files to stage
+        paths: Vec<String>,
+        /// Stage every change in the repository
+        #[clap(short, long)]
+        all: bool,
+    },
+    /// Unstage files
+    Reset {
+        /// Files to unstage
+        paths: Vec<String>,
+    },
+    /// Commit the staged changes
+    Commit {
+        /// The commit message, given twice the second is used as the description
+        #[clap(short, long)]
+        message: Vec<String>,
+        /// Commit even if the message doesn't follow the commit convention
+        #[clap(long)]
+        no_lint: bool,
+    },
+    /// Write release notes from the conventional commits between two refs
+    Changelog {
+        /// Start after this ref, the latest tag when not given
+        #[clap(long)]
+        from: Option<String>,
+        /// End at this ref
+        #[clap(long, default_value = "HEAD")]
+        to: String,
+        /// The heading of the release, "Unreleased" when not given
+        #[clap(long)]
+        title: Option<String>,
+        /// Print JSON instead of Markdown
+        #[clap(long, conflicts_with = "write")]
+        json: bool,
+        /// Add the release notes to the top of CHANGELOG.md instead of printing them
+        #[clap(long)]
+        write: bool,
+    },
+    /// Reorder, squash, reword, edit or drop recent commits
+    Rebase,
+    /// Bump the version, commit it and tag the release with its notes
+    Release,
+    /// Check a commit message against the commit convention, for use as a `commit-msg` hook
+    LintMsg {
+        /// The file holding the commit message, git passes it to the hook
+        #[clap(required_unless_present = "install-hook")]
+        file: Option<PathBuf>,
+        /// Install `gust lint-msg` as the repository's commit-msg hook
+        #[clap(long, conflicts_with = "file")]
+        install_hook: bool,
+    },
+    /// Show changes
+    Diff {
+        /// Show staged changes instead of unstaged ones
+        #[clap(long)]
+        staged: bool,
+        /// Highlight changed words instead of lines
+        #[clap(long, conflicts_with = "side-by-side")]
+        word: bool,
+        /// Show the old and new file next to each other
+        #[clap(long)]
+        side_by_side: bool,
+        /// Compare against this commit instead of the index
+        #[clap(long)]
+        from: Option<String>,
+        /// Compare up to this commit, requires --from
+        #[clap(long, requires = "from")]
+        to: Option<String>,
+        /// Only show these files
+        paths: Vec<String>,
+    },
+    /// Revert a commit
+    UndoCommit {
+        /// The hash of the commit to revert
+        commit: Option<String>,
+    },
+    /// Switch, create, delete and rename branches
+    Branches {
+        #[clap(subcommand)]
+        action: Option<BranchAction>,
+    },
+    /// Resolve merge conflicts
+    Conflicts {
+        /// Resolve the given files with our version
+        #[clap(long, conflicts_with = "theirs", requires = "paths")]
+        ours: bool,
+        /// Resolve the given files with their version
+        #[clap(long, requires = "paths")]
+        theirs: bool,
+        /// The conflicted files to resolve
+        paths: Vec<String>,
+    },
+    /// Add patterns or templates to the .gitignore
+    Ignore {
+        /// Patterns to add to the .gitignore at the repository root
+        patterns: Vec<String>,
+        /// Merge a bundled template (Rust, Node, Python...) into the .gitignore
+        #[clap(short, long)]
+        template: Option<String>,
+        /// Write to .git/info/exclude instead, which isn't shared
+        #[clap(long)]
+        exclude: bool,
+    },
+    /// Push the current branch
+    Push,
+    /// Pull into the current branch
+    Pull,
+    /// Add, rename, remove and configure remotes
+    Remotes {
+        #[clap(subcommand)]
+        action: Option<RemoteAction>,
+    },
+    /// Stop tracking files
+    Remove {
+        /// Files to stop tracking
+        paths: Vec<String>,
+        /// Keep the files on disk
+        #[clap(long)]
+        keep: bool,
+    },
+    /// Save, apply and manage stashes
+    Stash {
+        #[clap(subcommand)]
+        action: Option<StashAction>,
+    },
+    /// List, create, delete and push tags
+    Tags {
+        #[clap(subcommand)]
+        action: Option<TagAction>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BranchAction {
+    /// Switch to a branch
+    Switch { name: String },
+    /// Create a branch
+    Create {
+        name: String,
+        /// The commit to start the branch on
+        #[clap(long)]
+        from: Option<String>,
+    },
+    /// Delete a branch
+    Delete { name: String },
+    /// Rename a local branch
+    Rename { name: String, new_name: String },
+    /// Track a remote branch, e.g. `origin/main`
+    SetUpstream {
+        upstream: String,
+        /// The branch to set it for, the current one by default
+        #[clap(long)]
+        branch: Option<String>,
+    },
+    /// Stop tracking the upstream
+    UnsetUpstream {
+        /// The branch to unset it for, the current one by default
+        #[clap(long)]
+        branch: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RemoteAction {
+    /// Add a remote
+    Add { name: String, url: String },
+    /// Rename a remote
+    Rename { old_name: String, new_name: String },
+    /// Remove a remote
+    Remove { name: String },
+    /// Change a remote's url
+    SetUrl {
+        name: String,
+        url: String,
+        /// Only change the url used for pushing
+        #[clap(long)]
+        push: bool,
+    },
+    /// Delete remote-tracking branches that no longer exist on the remote
+    Prune { name: String },
+}
+
+#[derive(Subcommand)]
+pub enum StashAction {
+    /// Stash the current changes
+    Push {
+        #[clap(short, long)]
+        message: Option<String>,
+        /// Also stash untracked files
+        #[clap(short = 'u', long)]
+        include_untracked: bool,
+        /// Leave the staged changes in the index
+        #[clap(long)]
+        keep_index: bool,
+    },
+    /// List the stashes
+    List,
+    /// Apply a stash, keeping it in the list
+    Apply {
+        #[clap(default_value = "stash@{0}")]
+        stash: String,
+    },
+    /// Apply a stash and remove it from the list
+    Pop {
+        #[clap(default_value = "stash@{0}")]
+        stash: String,
+    },
+    /// Delete a stash
+    Drop { stash: String },
+    /// Create a branch from a stash
+    Branch {
+        name: String,
+        #[clap(default_value = "stash@{0}")]
+        stash: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagAction {
+    /// List the tags, newest first
+    List,
+    /// Create a tag, annotated when it has a message
+    Create {
+        name: String,
+        /// The commit to tag instead of HEAD
+        #[clap(long)]
+        commit: Option<String>,
+        #[clap(short, long)]
+        message: Option<String>,
+    },
+    /// Delete a tag
+    Delete {
+        name: String,
+        /// Delete the tag from this remote instead of locally
+        #[clap(long)]
+        remote: Option<String>,
+    },
+    /// Push a tag, or every tag when no name is given
+    Push {
+        remote: String,
+        name: Option<String>,
+    },
+}
+
+impl GustCommand {
+    /// The menu to open when the command is missing inputs, `None` for commands without one
+    pub fn menu(&self) -> Option<Commands> {
+        Some(match self {
+            GustCommand::Add { .. } => Commands::Add,
+            GustCommand::Reset { .. } => Commands::Reset,
+            GustCommand::Commit { .. } => Commands::Commit,
+            GustCommand::Diff { .. } => Commands::Diff,
+            GustCommand::UndoCommit { .. } => Commands::UndoCommit,
+            GustCommand::Branches { .. } => Commands::Branches,
+            GustCommand::Conflicts { .. } => Commands::Conflicts,
+            GustCommand::Ignore { .. } => Commands::Ignore,
+            GustCommand::Push => Commands::Push,
+            GustCommand::Pull => Commands::Pull,
+            GustCommand::Remotes { .. } => Commands::Remotes,
+            GustCommand::Remove { .. } => Commands::Remove,
+            GustCommand::Stash { .. } => Commands::Stash,
+            GustCommand::Tags { .. } => Commands::Tags,
+            GustCommand::Rebase => Commands::Rebase,
+            GustCommand::Release => Commands::Release,
+            GustCommand::Changelog { .. } | GustCommand::LintMsg { .. } => return None,
+        })
+    }
+
+    /// Run the command without any prompts.
+    /// Returns `None` when inputs are missing and the menu should be opened instead.
+    pub fn run(self, backend: &impl GitBackend, config: &Config) -> Option<Result<(), GustError>> {
+        match self {
+            GustCommand::Add { paths, all } => {
+                if all {
+                    Some(backend.toplevel().and_then(|root| backend.add(vec![root])))
+                } else if paths.is_empty() {
+                    None
+                } else {
+                    Some(backend.add(paths))
+                }
+            }
+            GustCommand::Reset { paths } => {
+                if paths.is_empty() {
+                    None
+                } else {
+                    Some(backend.reset(paths))
+                }
+            }
+            GustCommand::Commit { message, no_lint } => {
+                if message.is_empty() {
+                    None
+                } else if !no_lint {
+                    Some(
+                        lint_message(backend, &message.join("\n\n"), config)
+                            .and_then(|_| backend.commit(Some(message_options(message)), config)),
+                    )
+                } else {
+                    Some(backend.commit(Some(message_options(message)), config))
+                }
+            }
+            GustCommand::Changelog {
+                from,
+                to,
+                title,
+                json,
+                write,
+            } => Some(print_changelog(
+                backend, config, from, to, title, json, write,
+            )),
+            GustCommand::LintMsg { file, .. } => Some(match file {
+                Some(file) => std::fs::read_to_string(&file)
+                    .map_err(|e| format!("{}: {}", file.display(), e).into())
+                    .and_then(|message| lint_message(backend, &message, config)),
+                // clap only allows leaving the file out with --install-hook
+                None => install_commit_msg_hook(backend),
+            }),
+            GustCommand::Diff {
+                staged,
+                word,
+                side_by_side,
+                from,
+                to,
+                paths,
+            } => {
+                if !staged && !word && !side_by_side && from.is_none() && paths.is_empty() {
+                    return None;
+                }
+                let mut source = vec![];
+                if staged {
+                    source.push("--cached".to_string());
+                }
+                source.extend(from);
+                source.extend(to);
+                Some(print_diff(backend, source, paths, word, side_by_side))
+            }
+            GustCommand::UndoCommit { commit } => commit.map(|commit| backend.revert(commit)),
+            GustCommand::Branches { action } => action.map(|action| match action {
+                BranchAction::Switch { name } => {
+                    if name.contains("remotes/") {
+                        backend.fetch(None)?;
+                    }
+                    backend.checkout(vec![name])
+                }
+                BranchAction::Create { name, from } => backend.create_branch(name, from),
+                BranchAction::Delete { name } => backend.delete_branch(name),
+                BranchAction::Rename { name, new_name } => backend.rename_branch(name, new_name),
+                BranchAction::SetUpstream { upstream, branch } => {
+                    backend.set_upstream(branch, upstream)
+                }
+                BranchAction::UnsetUpstream { branch } => backend.unset_upstream(branch),
+            }),
+            GustCommand::Conflicts {
+                ours,
+                theirs,
+                paths,
+            } => {
+                if !ours && !theirs {
+                    None
+                } else {
+                    Some(menus::take_side_for_paths(backend, paths, ours))
+                }
+            }
+            GustCommand::Ignore {
+                patterns,
+                template,
+                exclude,
+            } => {
+                if patterns.is_empty() && template.is_none() {
+                    return None;
+                }
+                Some(add_ignore_patterns(backend, patterns, template, exclude))
+            }
+            GustCommand::Rebase | GustCommand::Release => None,
+            GustCommand::Push => Some(backend.push()),
+            GustCommand::Pull => Some(backend.pull()),
+            GustCommand::Remotes { action } => action.map(|action| match action {
+                RemoteAction::Add { name, url } => backend.remote_add(name, url),
+                RemoteAction::Rename { old_name, new_name } => {
+                    backend.remote_rename(old_name, new_name)
+                }
+                RemoteAction::Remove { name } => backend.remote_remove(name),
+                RemoteAction::SetUrl { name, url, push } => backend.remote_set_url(name, url, push),
+                RemoteAction::Prune { name } => backend.remote_prune(name),
+            }),
+            GustCommand::Remove { paths, keep } => {
+                if paths.is_empty() {
+                    None
+                } else {
+                    Some(backend.rm(paths, keep))
+                }
+            }
+            GustCommand::Stash { action } => action.map(|action| match action {
+                StashAction::Push {
+                    message,
+                    include_untracked,
+                    keep_index,
+                } => backend.stash_push(message, include_untracked, keep_index),
+                StashAction::List => {
+                    for stash in backend.stash_list()?.unwrap_or_default() {
+                        println!("{}", stash);
+                    }
+                    Ok(())
+                }
+                StashAction::Apply { stash } => backend.stash_apply(stash, false),
+                StashAction::Pop { stash } => backend.stash_apply(stash, true),
+                StashAction::Drop { stash } => backend.stash_drop(stash),
+                StashAction::Branch { name, stash } => backend.stash_branch(name, stash),
+            }),
+            GustCommand::Tags { action } => action.map(|action| match action {
+                TagAction::List => {
+                    for tag in backend.tag_list()? {
+                        println!(
+                            "{}\t{}\t{}\t{}",
+                            tag.name, tag.commit, tag.date, tag.subject
+                        );
+                    }
+                    Ok(())
+                }
+                TagAction::Create {
+                    name,
+                    commit,
+                    message,
+                } => backend.create_tag(name, commit, message),
+                TagAction::Delete { name, remote } => backend.delete_tag(name, remote),
+                TagAction::Push { remote, name } => backend.push_tags(remote, name),
+            }),
+        }
+    }
+}
+
+fn print_diff(
+    backend: &impl GitBackend,
+    source: Vec<String>,
+    paths: Vec<String>,
+    word: bool,
+    side_by_side: bool,
+) -> Result<(), GustError> {
+    let mut options = vec!["--no-color".to_string(), "--no-ext-diff".to_string()];
+    if word {
+        options.push("--word-diff=plain".to_string());
+    }
+    options.extend(source);
+
+    let diff_output = match backend.diff(options, paths)? {
+        Some(output) => output,
+        None => {
+            println!("{}", "No differences found".bright_yellow());
+            return Ok(());
+        }
+    };
+
+    let rendered = if word {
+        diff::render_word_diff(&diff_output)
+    } else if side_by_side {
+        let (_, width) = console::Term::stdout().size();
+        diff::render_side_by_side(&diff::parse_diff(&diff_output)?, width as usize)
+    } else {
+        diff::render_unified(&diff::parse_diff(&diff_output)?)
+    };
+    cli::page_output(rendered)
+}
+
+fn add_ignore_patterns(
+    backend: &impl GitBackend,
+    patterns: Vec<String>,
+    template: Option<String>,
+    exclude: bool,
+) -> Result<(), GustError> {
+    let file = if exclude {
+        backend.git_dir()?.join("info").join("exclude")
+    } else {
+        backend.toplevel()?.join(".gitignore")
+    };
+
+    let mut added = ignore::append_patterns(&file, &patterns, None)?;
+    if let Some(template) = template {
+        added.extend(ignore::merge_template(&file, &template)?);
+    }
+
+    if added.is_empty() {
+        println!(
+            "{}",
+            format!("{} already had those patterns", file.display()).bright_yellow()
+        );
+    } else {
+        println!(
+            "{}",
+            format!("Added {} pattern(s) to {}", added.len(), file.display()).bright_green()
+        );
+    }
+    Ok(())
+}
+
+fn print_changelog(
+    backend: &impl GitBackend,
+    config: &Config,
+    from: Option<String>,
+    to: String,
+    title: Option<String>,
+    json: bool,
+    write: bool,
+) -> Result<(), GustError> {
+    let conventions = settings::load_convention(config.convention.clone())?;
+    let title = title.unwrap_or_else(|| "Unreleased".to_string());
+    let changelog = changelog::collect(backend, &conventions, from, &to, title)?;
+
+    if changelog.is_empty() {
+        println!("{}", "No commits found".bright_yellow());
+    } else if json {
+        println!("{}", changelog.to_json()?);
+    } else if write {
+        let file = backend.toplevel()?.join("CHANGELOG.md");
+        changelog::prepend_to_file(&file, &changelog.to_markdown(&conventions))?;
+        println!(
+            "{}",
+            format!("Added the release notes to {}", file.display()).bright_green()
+        );
+    } else {
+        print!("{}", changelog.to_markdown(&conventions));
+    }
+    Ok(())
+}
+
+fn message_options(message: Vec<String>) -> Vec<String> {
+    message
+        .into_iter()
+        .flat_map(|m| vec!["-m".to_string(), m])
+        .collect()
+}
+
+/// Print everything in `message` that doesn't follow the convention, failing if anything was found
+fn lint_message(
+    backend: &impl GitBackend,
+    message: &str,
+    config: &Config,
+) -> Result<(), GustError> {
+    let issues = convention::lint_with_config(message, config, &backend.comment_prefix()?)?;
+    if issues.is_empty() {
+        return Ok(());
+    }
+    for issue in &issues {
+        eprintln!("{}", issue.to_string().bright_yellow());
+    }
+    Err(GustError::Convention(format!(
+        "The commit message has {} problem(s)",
+        issues.len()
+    )))
+}
+
+const HOOK_MARKER: &str = "# Installed by `gust lint-msg --install-hook`";
+
+fn install_commit_msg_hook(backend: &impl GitBackend) -> Result<(), GustError> {
+    let hooks = backend.git_path("hooks")?;
+    let hook = hooks.join("commit-msg");
+    if hook.exists() && !std::fs::read_to_string(&hook)?.contains(HOOK_MARKER) {
+        return Err(format!(
+            "{} already exists, add `gust lint-msg \"$1\"` to it instead",
+            hook.display()
+        )
+        .into());
+    }
+
+    let gust = std::env::current_exe()?;
+    std::fs::create_dir_all(&hooks)?;
+    std::fs::write(
+        &hook,
+        format!(
+            "#!/bin/sh\n{}\nexec '{}' lint-msg \"$1\"\n",
+            HOOK_MARKER,
+            gust.display().to_string().replace('\'', r"'\''")
+        ),
+    )?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    println!(
+        "{}",
+        format!("Installed the commit-msg hook at {}", hook.display()).bright_green()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::FakeBackend;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+
+    use clap::CommandFactory;
+    use std::path::Path;
+
+    fn parse(args: &[&str]) -> Result<CliArguments, clap::Error> {
+        CliArguments::try_parse_from(std::iter::once("gust").chain(args.iter().copied()))
+    }
+
+    fn command(args: &[&str]) -> GustCommand {
+        parse(args).unwrap().command.unwrap()
+    }
+
+    fn backend() -> FakeBackend {
+        let backend = FakeBackend::new(PathBuf::from("/repo"));
+        backend.commit_file("a.txt", "a\n", "Add a.txt");
+        backend.write("a.txt", Some("changed\n"));
+        backend
+    }
+
+    #[test]
+    fn arguments_are_consistent() {
+        CliArguments::command().debug_assert();
+    }
+
+    #[test]
+    fn no_command_opens_the_menus() {
+        let args = parse(&[]).unwrap();
+        assert!(args.command.is_none() && !args.tui && !args.dry_run);
+        assert!(parse(&["--tui"]).unwrap().tui);
+    }
+
+    #[test]
+    fn dry_run_can_follow_the_command() {
+        let args = parse(&["stash", "list", "--dry-run"]).unwrap();
+        assert!(args.dry_run);
+        assert!(matches!(
+            args.command,
+            Some(GustCommand::Stash {
+                action: Some(StashAction::List)
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_conflicting_and_incomplete_options() {
+        for args in [
+            &["diff", "--to", "HEAD"][..],
+            &["diff", "--word", "--side-by-side"],
+            &["conflicts", "--ours"],
+            &["conflicts", "--ours", "--theirs", "a.txt"],
+            &["changelog", "--json", "--write"],
+            &["lint-msg"],
+            &["lint-msg", "--install-hook", "MSG"],
+            &["branches", "rename", "old"],
+            &["status"],
+        ] {
+            assert!(parse(args).is_err(), "gust {} parsed", args.join(" "));
+        }
+    }
+
+    #[test]
+    fn commands_missing_inputs_open_their_menu() {
+        let backend = backend();
+        for args in [
+            &["add"][..],
+            &["reset"],
+            &["commit"],
+            &["diff"],
+            &["undo-commit"],
+            &["branches"],
+            &["conflicts"],
+            &["ignore"],
+            &["remotes"],
+            &["remove"],
+            &["stash"],
+            &["tags"],
+            &["rebase"],
+            &["release"],
+        ] {
+            let command = command(args);
+            assert!(command.menu().is_some(), "gust {} has no menu", args[0]);
+            assert!(command.run(&backend, &Config::default()).is_none());
+        }
+        assert!(backend.calls().is_empty());
+
+        assert!(command(&["changelog"]).menu().is_none());
+        assert!(command(&["lint-msg", "MSG"]).menu().is_none());
+    }
+
+    #[test]
+    fn add_stages_the_given_paths_or_everything() {
+        let backend = backend();
+        command(&["add", "a.txt"])
+            .run(&backend, &Config::default())
+            .unwrap()
+            .unwrap();
+        command(&["add", "--all"])
+            .run(&backend, &Config::default())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(backend.calls(), ["git add -- a.txt", "git add -- /repo"]);
+        let repo = backend.repo.borrow();
+        assert_eq!(
+            repo.files[Path::new("a.txt")].index.as_deref(),
+            Some("changed\n")
+        );
+    }
+
+    #[test]
+    fn subcommand_actions_fill_in_their_defaults() {
+        let backend = backend();
+        command(&["stash", "push", "-m", "wip"])
+            .run(&backend, &Config::default())
+            .unwrap()
+            .unwrap();
+        command(&["stash", "pop"])
+            .run(&backend, &Config::default())
+            .unwrap()
+            .unwrap();
+        command(&["remotes", "add", "origin", "/srv/repo.git"])
+            .run(&backend, &Config::default())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            backend.calls(),
+            [
+                "git stash push",
+                "git stash pop stash@{0}",
+                "git remote add origin /srv/repo.git"
+            ]
+        );
+        assert_eq!(backend.repo.borrow().remotes[0].name, "origin");
+    }
+
+    #[test]
+    fn commit_lints_the_message_unless_told_not_to() {
+        // For the convention in the config directory
+        let _repo = TestRepo::new();
+        let backend = backend();
+        backend.add(vec!["a.txt"]).unwrap();
+
+        let error = command(&["commit", "-m", "changed a"])
+            .run(&backend, &Config::default())
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(error, GustError::Convention(_)));
+        assert_eq!(backend.repo.borrow().commits.len(), 1);
+
+        command(&["commit", "-m", "fix: change a", "-m", "Details"])
+            .run(&backend, &Config::default())
+            .unwrap()
+            .unwrap();
+        backend.write("a.txt", Some("again\n"));
+        backend.add(vec!["a.txt"]).unwrap();
+        command(&["commit", "--no-lint", "-m", "changed a again"])
+            .run(&backend, &Config::default())
+            .unwrap()
+            .unwrap();
+
+        let messages: Vec<String> = backend
+            .repo
+            .borrow()
+            .commits
+            .iter()
+            .map(|(_, message)| message.clone())
+            .collect();
+        assert_eq!(
+            messages,
+            ["Add a.txt", "fix: change a\n\nDetails", "changed a again"]
+        );
+    }
+
+    fn install_hook() -> Result<(), GustError> {
+        command(&["lint-msg", "--install-hook"])
+            .run(&GitCli, &Config::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn installs_the_hook_where_core_hooks_path_points() {
+        let repo = TestRepo::new();
+        let hooks = repo.outside("hooks");
+        repo.git(&["config", "core.hooksPath", hooks.to_str().unwrap()]);
+
+        install_hook().unwrap();
+        let hook = std::fs::read_to_string(hooks.join("commit-msg")).unwrap();
+        assert!(hook.contains(HOOK_MARKER));
+        assert!(!repo.work_tree.join(".git/hooks/commit-msg").exists());
+
+        // Gust's own hook is replaced, anyone else's is left alone
+        install_hook().unwrap();
+        std::fs::write(hooks.join("commit-msg"), "#!/bin/sh\nexit 0\n").unwrap();
+        assert!(install_hook().is_err());
+        assert_eq!(
+            std::fs::read_to_string(hooks.join("commit-msg")).unwrap(),
+            "#!/bin/sh\nexit 0\n"
+        );
+    }
+
+    #[test]
+    fn installs_the_hook_shared_by_linked_work_trees() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        let linked = repo.outside("linked");
+        repo.git(&["worktree", "add", "--quiet", linked.to_str().unwrap()]);
+        std::env::set_current_dir(&linked).unwrap();
+
+        install_hook().unwrap();
+        assert!(repo.work_tree.join(".git/hooks/commit-msg").exists());
+    }
+}
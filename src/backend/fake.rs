@@ -0,0 +1,993 @@
+use crate::backend::GitBackend;
+use crate::cli::{BranchInfo, LogCommit, Remote, TagInfo};
+use crate::error::GustError;
+use crate::ignore::IgnoreMatch;
+use crate::settings::Config;
+use crate::status::{BranchStatus, FileEntry, FileState, Operation, RepoStatus};
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// The contents of a file in HEAD, the index and the work tree, `None` where it doesn't exist
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FakeFile {
+    pub head: Option<String>,
+    pub index: Option<String>,
+    pub worktree: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeStash {
+    pub description: String,
+    pub files: BTreeMap<PathBuf, FakeFile>,
+}
+
+/// An in-memory repository. Only what the menus look at is modelled: there is a
+/// single line of history, and merges, conflicts and patches aren't supported.
+#[derive(Debug, Clone, Default)]
+pub struct FakeRepo {
+    pub files: BTreeMap<PathBuf, FakeFile>,
+    /// `(hash, message)`, oldest first
+    pub commits: Vec<(String, String)>,
+    pub current_branch: Option<String>,
+    pub branches: Vec<String>,
+    /// As `<remote>/<branch>`
+    pub remote_branches: Vec<String>,
+    pub upstream: Option<String>,
+    /// Newest first, like `git stash list`
+    pub stashes: Vec<FakeStash>,
+    pub remotes: Vec<Remote>,
+    /// `(name, hash)`
+    pub tags: Vec<(String, String)>,
+    /// Config variables by their full key, e.g. `core.commentChar`
+    pub config: BTreeMap<String, String>,
+    /// Every command that changed the repository, as it would be typed
+    pub calls: Vec<String>,
+}
+
+pub struct FakeBackend {
+    pub repo: RefCell<FakeRepo>,
+    pub toplevel: PathBuf,
+}
+
+impl FakeBackend {
+    /// An empty repository on `main`, with its work tree at `toplevel`
+    pub fn new(toplevel: PathBuf) -> Self {
+        FakeBackend {
+            repo: RefCell::new(FakeRepo {
+                current_branch: Some("main".to_string()),
+                branches: vec!["main".to_string()],
+                ..Default::default()
+            }),
+            toplevel,
+        }
+    }
+
+    /// Change a file in the work tree, `None` deletes it
+    pub fn write(&self, path: &str, contents: Option<&str>) {
+        let mut repo = self.repo.borrow_mut();
+        repo.files.entry(PathBuf::from(path)).or_default().worktree =
+            contents.map(|c| c.to_string());
+        repo.files.retain(|_, f| *f != FakeFile::default());
+    }
+
+    /// Add a commit containing `path` with `contents`
+    pub fn commit_file(&self, path: &str, contents: &str, message: &str) {
+        let mut repo = self.repo.borrow_mut();
+        let contents = Some(contents.to_string());
+        repo.files.insert(
+            PathBuf::from(path),
+            FakeFile {
+                head: contents.clone(),
+                index: contents.clone(),
+                worktree: contents,
+            },
+        );
+        push_commit(&mut repo, message.to_string());
+    }
+
+    pub fn calls(&self) -> Vec<String> {
+        self.repo.borrow().calls.clone()
+    }
+
+    fn record<S: AsRef<OsStr>>(&self, subcommand: &str, args: &[S]) {
+        let mut line = format!("git {}", subcommand);
+        for arg in args {
+            line.push(' ');
+            line.push_str(&arg.as_ref().to_string_lossy());
+        }
+        self.repo.borrow_mut().calls.push(line);
+    }
+}
+
+/// The index in `repo.commits` of `HEAD`, a tag or a (short) hash
+fn resolve(repo: &FakeRepo, rev: &str) -> Result<usize, GustError> {
+    let hash = repo
+        .tags
+        .iter()
+        .find(|(name, _)| name == rev)
+        .map(|(_, hash)| hash.as_str())
+        .unwrap_or(rev);
+    let index = if hash == "HEAD" {
+        repo.commits.len().checked_sub(1)
+    } else {
+        repo.commits.iter().position(|(h, _)| h.starts_with(hash))
+    };
+    index.ok_or_else(|| format!("bad revision '{}'", rev).into())
+}
+
+fn push_commit(repo: &mut FakeRepo, message: String) {
+    let hash = format!("{:07x}", 0xa11ce + repo.commits.len() * 0x1f3d);
+    repo.commits.push((hash, message));
+}
+
+fn file_state(from: &Option<String>, to: &Option<String>) -> FileState {
+    match (from, to) {
+        (None, Some(_)) => FileState::Added,
+        (Some(_), None) => FileState::Deleted,
+        (Some(a), Some(b)) if a != b => FileState::Modified,
+        _ => FileState::Unmodified,
+    }
+}
+
+fn unified_diff(path: &Path, old: &Option<String>, new: &Option<String>) -> String {
+    let old_lines: Vec<&str> = old
+        .as_deref()
+        .map(|c| c.lines().collect())
+        .unwrap_or_default();
+    let new_lines: Vec<&str> = new
+        .as_deref()
+        .map(|c| c.lines().collect())
+        .unwrap_or_default();
+    let name = path.display();
+
+    let mut output = format!("diff --git a/{} b/{}\n", name, name);
+    output.push_str(&match old {
+        Some(_) => format!("--- a/{}\n", name),
+        None => "--- /dev/null\n".to_string(),
+    });
+    output.push_str(&match new {
+        Some(_) => format!("+++ b/{}\n", name),
+        None => "+++ /dev/null\n".to_string(),
+    });
+    output.push_str(&format!(
+        "@@ -{},{} +{},{} @@\n",
+        if old_lines.is_empty() { 0 } else { 1 },
+        old_lines.len(),
+        if new_lines.is_empty() { 0 } else { 1 },
+        new_lines.len()
+    ));
+    for line in old_lines {
+        output.push_str(&format!("-{}\n", line));
+    }
+    for line in new_lines {
+        output.push_str(&format!("+{}\n", line));
+    }
+    output
+}
+
+/// Paths can be given relative to the top of the work tree or joined onto it
+fn matches_paths<S: AsRef<OsStr>>(toplevel: &Path, path: &Path, paths: &[S]) -> bool {
+    paths.is_empty()
+        || paths.iter().any(|p| {
+            let p = Path::new(p.as_ref());
+            path == p || toplevel.join(path) == p
+        })
+}
+
+fn stash_index(stash: &str) -> Result<usize, GustError> {
+    stash
+        .strip_prefix("stash@{")
+        .and_then(|s| s.strip_suffix('}'))
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| format!("'{}' is not a stash reference", stash).into())
+}
+
+impl GitBackend for FakeBackend {
+    fn status(&self) -> Result<RepoStatus, GustError> {
+        let repo = self.repo.borrow();
+        let mut entries = vec![];
+
+        for (path, file) in &repo.files {
+            let (index, worktree) = if file.head.is_none() && file.index.is_none() {
+                (FileState::Untracked, FileState::Untracked)
+            } else {
+                (
+                    file_state(&file.head, &file.index),
+                    file_state(&file.index, &file.worktree),
+                )
+            };
+            if index == FileState::Unmodified && worktree == FileState::Unmodified {
+                continue;
+            }
+            entries.push(FileEntry {
+                index,
+                worktree,
+                path: path.clone(),
+                orig_path: None,
+                submodule: None,
+                conflict: None,
+            });
+        }
+
+        Ok(RepoStatus {
+            branch: BranchStatus {
+                oid: repo.commits.last().map(|(hash, _)| hash.clone()),
+                head: repo.current_branch.clone(),
+                upstream: repo.upstream.clone(),
+                ahead: 0,
+                behind: 0,
+            },
+            entries,
+        })
+    }
+
+    fn branches(&self) -> Result<Option<Vec<BranchInfo>>, GustError> {
+        // Every branch shares the one fake history, so they all show its last commit
+        let repo = self.repo.borrow();
+        let subject = repo
+            .commits
+            .last()
+            .and_then(|(_, message)| message.lines().next())
+            .unwrap_or_default()
+            .to_string();
+        let local = repo.branches.iter().map(|b| {
+            let current = repo.current_branch.as_ref() == Some(b);
+            BranchInfo {
+                name: b.clone(),
+                current,
+                upstream: repo.upstream.clone().filter(|_| current),
+                subject: subject.clone(),
+                date: "2024-01-01".to_string(),
+                ..Default::default()
+            }
+        });
+        let remote = repo.remote_branches.iter().map(|b| BranchInfo {
+            name: format!("remotes/{}", b),
+            remote: true,
+            subject: subject.clone(),
+            date: "2024-01-01".to_string(),
+            ..Default::default()
+        });
+        Ok(Some(local.chain(remote).collect()))
+    }
+
+    fn log(&self, _branch: Option<String>) -> Result<Option<String>, GustError> {
+        let repo = self.repo.borrow();
+        if repo.commits.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(
+            repo.commits
+                .iter()
+                .rev()
+                .map(|(hash, message)| {
+                    format!("{} {}\n", hash, message.lines().next().unwrap_or_default())
+                })
+                .collect(),
+        ))
+    }
+
+    fn ls_tree(&self) -> Result<Option<String>, GustError> {
+        let files: String = self
+            .repo
+            .borrow()
+            .files
+            .iter()
+            .filter(|(_, f)| f.head.is_some())
+            .map(|(path, _)| format!("{}\n", path.display()))
+            .collect();
+        Ok(if files.is_empty() { None } else { Some(files) })
+    }
+
+    fn diff<S: AsRef<OsStr>>(
+        &self,
+        options: Vec<String>,
+        paths: Vec<S>,
+    ) -> Result<Option<String>, GustError> {
+        let cached = options.iter().any(|o| o == "--cached");
+        let output: String = self
+            .repo
+            .borrow()
+            .files
+            .iter()
+            .filter(|(path, f)| {
+                matches_paths(&self.toplevel, path, &paths)
+                    && (f.head.is_some() || f.index.is_some())
+            })
+            .filter_map(|(path, f)| {
+                let (old, new) = if cached {
+                    (&f.head, &f.index)
+                } else {
+                    (&f.index, &f.worktree)
+                };
+                if old == new {
+                    None
+                } else {
+                    Some(unified_diff(path, old, new))
+                }
+            })
+            .collect();
+        Ok(if output.is_empty() {
+            None
+        } else {
+            Some(output)
+        })
+    }
+
+    fn stash_list(&self) -> Result<Option<Vec<String>>, GustError> {
+        let stashes: Vec<String> = self
+            .repo
+            .borrow()
+            .stashes
+            .iter()
+            .enumerate()
+            .map(|(i, s)| format!("stash@{{{}}}: {}", i, s.description))
+            .collect();
+        Ok(if stashes.is_empty() {
+            None
+        } else {
+            Some(stashes)
+        })
+    }
+
+    fn stash_show(&self, stash: String) -> Result<String, GustError> {
+        let repo = self.repo.borrow();
+        let stash = repo
+            .stashes
+            .get(stash_index(&stash)?)
+            .ok_or_else(|| format!("{} doesn't exist", stash))?;
+        let mut stat: String = stash
+            .files
+            .keys()
+            .map(|path| format!(" {}\n", path.display()))
+            .collect();
+        stat.push_str(&format!(" {} file(s) changed\n", stash.files.len()));
+        Ok(stat)
+    }
+
+    fn show(&self, commit: String) -> Result<String, GustError> {
+        let repo = self.repo.borrow();
+        repo.commits
+            .iter()
+            .find(|(hash, _)| hash.starts_with(&commit))
+            .map(|(hash, message)| format!("commit {}\n\n    {}\n", hash, message))
+            .ok_or_else(|| format!("bad revision '{}'", commit).into())
+    }
+
+    fn commit_message(&self, commit: String) -> Result<String, GustError> {
+        let repo = self.repo.borrow();
+        let found = if commit == "HEAD" {
+            repo.commits.last()
+        } else {
+            repo.commits
+                .iter()
+                .find(|(hash, _)| hash.starts_with(&commit))
+        };
+        found
+            .map(|(_, message)| message.clone())
+            .ok_or_else(|| format!("bad revision '{}'", commit).into())
+    }
+
+    fn remote_branches_containing(&self, _commit: String) -> Result<Vec<String>, GustError> {
+        // The fake backend doesn't track which commits were pushed
+        Ok(vec![])
+    }
+
+    fn log_commits(&self, range: String) -> Result<Vec<LogCommit>, GustError> {
+        let repo = self.repo.borrow();
+        let (start, end) = match range.split_once("..") {
+            Some((from, to)) => (resolve(&repo, from)? + 1, resolve(&repo, to)?),
+            None => (0, resolve(&repo, &range)?),
+        };
+        Ok(repo.commits[start.min(end + 1)..=end]
+            .iter()
+            .rev()
+            .map(|(hash, message)| LogCommit {
+                hash: hash.clone(),
+                date: "2024-01-01".to_string(),
+                // The fake history is a straight line
+                merge: false,
+                message: message.clone(),
+            })
+            .collect())
+    }
+
+    fn latest_tag(&self, commit: String) -> Result<Option<String>, GustError> {
+        let repo = self.repo.borrow();
+        let index = resolve(&repo, &commit)?;
+        Ok(repo
+            .tags
+            .iter()
+            .filter_map(|(name, hash)| Some((resolve(&repo, hash).ok()?, name)))
+            .filter(|(tagged, _)| *tagged <= index)
+            .max_by_key(|(tagged, _)| *tagged)
+            .map(|(_, name)| name.clone()))
+    }
+
+    fn tags(&self) -> Result<Vec<String>, GustError> {
+        let mut tags: Vec<String> = self
+            .repo
+            .borrow()
+            .tags
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect();
+        tags.sort();
+        Ok(tags)
+    }
+
+    fn tag_list(&self) -> Result<Vec<TagInfo>, GustError> {
+        // The fake repository doesn't keep tag messages, so every tag looks lightweight
+        let repo = self.repo.borrow();
+        Ok(repo
+            .tags
+            .iter()
+            .rev()
+            .filter_map(|(name, hash)| {
+                let (hash, message) = &repo.commits[resolve(&repo, hash).ok()?];
+                Some(TagInfo {
+                    name: name.clone(),
+                    commit: hash[..hash.len().min(7)].to_string(),
+                    date: "2024-01-01".to_string(),
+                    subject: message.lines().next().unwrap_or_default().to_string(),
+                    annotated: false,
+                })
+            })
+            .collect())
+    }
+
+    fn recent_authors(&self) -> Result<Vec<String>, GustError> {
+        // The fake backend doesn't record who made each commit
+        Ok(vec![])
+    }
+
+    fn remotes(&self) -> Result<Option<Vec<Remote>>, GustError> {
+        let remotes = self.repo.borrow().remotes.clone();
+        Ok(if remotes.is_empty() {
+            None
+        } else {
+            Some(remotes)
+        })
+    }
+
+    fn rev_parse(&self, option: &str) -> Result<String, GustError> {
+        match option {
+            "--show-prefix" => Ok(String::new()),
+            "--show-toplevel" => Ok(self.toplevel.display().to_string()),
+            "--git-dir" => Ok(self.toplevel.join(".git").display().to_string()),
+            "--is-inside-work-tree" => Ok("true".to_string()),
+            _ => Err(format!("The fake backend doesn't support rev-parse {}", option).into()),
+        }
+    }
+
+    fn git_path(&self, path: &str) -> Result<PathBuf, GustError> {
+        Ok(self.toplevel.join(".git").join(path))
+    }
+
+    fn check_ignore(&self, _path: String) -> Result<Option<IgnoreMatch>, GustError> {
+        Ok(None)
+    }
+
+    fn is_repo(&self) -> Result<bool, GustError> {
+        Ok(true)
+    }
+
+    fn config_get(&self, key: &str) -> Result<Option<String>, GustError> {
+        Ok(self.repo.borrow().config.get(key).cloned())
+    }
+
+    fn operation_in_progress(&self) -> Result<Option<Operation>, GustError> {
+        Ok(None)
+    }
+
+    fn add<S: AsRef<OsStr>>(&self, paths: Vec<S>) -> Result<(), GustError> {
+        self.record("add --", &paths);
+        let mut repo = self.repo.borrow_mut();
+        for (path, file) in repo.files.iter_mut() {
+            if matches_paths(&self.toplevel, path, &paths) {
+                file.index = file.worktree.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn reset<S: AsRef<OsStr>>(&self, paths: Vec<S>) -> Result<(), GustError> {
+        self.record("reset --", &paths);
+        let mut repo = self.repo.borrow_mut();
+        for (path, file) in repo.files.iter_mut() {
+            if matches_paths(&self.toplevel, path, &paths) {
+                file.index = file.head.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn rm<S: AsRef<OsStr>>(&self, paths: Vec<S>, as_cached: bool) -> Result<(), GustError> {
+        self.record(if as_cached { "rm --cached --" } else { "rm --" }, &paths);
+        let mut repo = self.repo.borrow_mut();
+        for (path, file) in repo.files.iter_mut() {
+            if matches_paths(&self.toplevel, path, &paths) {
+                file.index = None;
+                if !as_cached {
+                    file.worktree = None;
+                }
+            }
+        }
+        repo.files.retain(|_, f| *f != FakeFile::default());
+        Ok(())
+    }
+
+    fn apply(&self, _patch: String, _options: Vec<String>) -> Result<(), GustError> {
+        Err("The fake backend can't apply patches".into())
+    }
+
+    fn commit(&self, options: Option<Vec<String>>, _config: &Config) -> Result<(), GustError> {
+        let options = options.unwrap_or_default();
+        self.record("commit", &options);
+
+        let mut messages: Vec<String> = options
+            .iter()
+            .zip(options.iter().skip(1))
+            .filter(|(flag, _)| *flag == "-m")
+            .map(|(_, message)| message.clone())
+            .collect();
+        let amend = options.iter().any(|o| o == "--amend");
+        let only = options.iter().any(|o| o == "--only");
+
+        let mut repo = self.repo.borrow_mut();
+        // `--fixup=<commit>` and `--squash=<commit>` name their target in the subject
+        if let Some((kind, target)) = options.iter().find_map(|o| {
+            o.strip_prefix("--fixup=")
+                .map(|t| ("fixup!", t))
+                .or_else(|| o.strip_prefix("--squash=").map(|t| ("squash!", t)))
+        }) {
+            let subject = repo
+                .commits
+                .iter()
+                .find(|(hash, _)| hash.starts_with(target))
+                .map(|(_, message)| message.lines().next().unwrap_or_default().to_string())
+                .ok_or_else(|| format!("could not lookup commit {}", target))?;
+            messages.insert(0, format!("{} {}", kind, subject));
+        }
+        if messages.is_empty() {
+            return Err("Aborting commit due to empty commit message.".into());
+        }
+
+        let staged = repo.files.values().any(|f| f.head != f.index);
+        if !staged && !amend {
+            return Err("nothing to commit".into());
+        }
+        if !only {
+            for file in repo.files.values_mut() {
+                file.head = file.index.clone();
+            }
+        }
+        if amend {
+            let last = repo
+                .commits
+                .last_mut()
+                .ok_or("You have nothing to amend.")?;
+            last.1 = messages.join("\n\n");
+        } else {
+            push_commit(&mut repo, messages.join("\n\n"));
+        }
+        Ok(())
+    }
+
+    fn rebase(&self, args: Vec<String>, _sequence_editor: Option<String>) -> Result<(), GustError> {
+        self.record("rebase", &args);
+        Err("The fake backend can't rebase".into())
+    }
+
+    fn revert(&self, commit_hash: String) -> Result<(), GustError> {
+        self.record("revert", &[&commit_hash]);
+        let mut repo = self.repo.borrow_mut();
+        let message = repo
+            .commits
+            .iter()
+            .find(|(hash, _)| hash.starts_with(&commit_hash))
+            .map(|(_, message)| message.clone())
+            .ok_or_else(|| format!("bad revision '{}'", commit_hash))?;
+        push_commit(&mut repo, format!("Revert \"{}\"", message));
+        Ok(())
+    }
+
+    fn cherry_pick(&self, commits: Vec<String>, record_origin: bool) -> Result<(), GustError> {
+        let mut args = commits.clone();
+        if record_origin {
+            args.insert(0, "-x".to_string());
+        }
+        self.record("cherry-pick", &args);
+
+        // The fake history has no branches of its own, so the commits are copied onto HEAD
+        let mut repo = self.repo.borrow_mut();
+        for commit in commits {
+            let (hash, message) = repo.commits[resolve(&repo, &commit)?].clone();
+            let message = if record_origin {
+                format!("{}\n\n(cherry picked from commit {})", message, hash)
+            } else {
+                message
+            };
+            push_commit(&mut repo, message);
+        }
+        Ok(())
+    }
+
+    fn merge(&self, args: Vec<String>) -> Result<(), GustError> {
+        self.record("merge", &args);
+        Err("The fake backend can't merge".into())
+    }
+
+    fn reset_merge(&self) -> Result<(), GustError> {
+        self.record::<&str>("reset --merge", &[]);
+        let mut repo = self.repo.borrow_mut();
+        // Staged files go back to HEAD, changes that were never staged are kept
+        for file in repo.files.values_mut().filter(|f| f.index != f.head) {
+            file.index = file.head.clone();
+            file.worktree = file.head.clone();
+        }
+        Ok(())
+    }
+
+    fn checkout<S: AsRef<OsStr>>(&self, args: Vec<S>) -> Result<(), GustError> {
+        let args: Vec<String> = args
+            .iter()
+            .map(|a| a.as_ref().to_string_lossy().to_string())
+            .collect();
+        self.record("checkout", &args);
+        let mut repo = self.repo.borrow_mut();
+
+        // Restoring files from the index
+        if let Some(separator) = args.iter().position(|a| a == "--") {
+            if separator != 0 {
+                return Err("The fake backend only restores files from the index".into());
+            }
+            let paths = &args[1..];
+            for (path, file) in repo.files.iter_mut() {
+                if matches_paths(&self.toplevel, path, paths) {
+                    file.worktree = file.index.clone();
+                }
+            }
+            return Ok(());
+        }
+
+        let target = args.last().ok_or("Nothing to check out")?;
+        let branch = match target.strip_prefix("remotes/") {
+            Some(remote_branch) => {
+                let local = remote_branch
+                    .split_once('/')
+                    .map(|b| b.1)
+                    .unwrap_or(remote_branch);
+                if !repo.branches.iter().any(|b| b == local) {
+                    repo.branches.push(local.to_string());
+                }
+                local.to_string()
+            }
+            None if repo.branches.contains(target) => target.clone(),
+            None => {
+                return Err(format!(
+                    "pathspec '{}' did not match any file(s) known to git",
+                    target
+                )
+                .into())
+            }
+        };
+        repo.current_branch = Some(branch);
+        Ok(())
+    }
+
+    fn create_branch(&self, name: String, start: Option<String>) -> Result<(), GustError> {
+        self.record(
+            "branch",
+            &[Some(&name), start.as_ref()]
+                .iter()
+                .flatten()
+                .collect::<Vec<_>>(),
+        );
+        let mut repo = self.repo.borrow_mut();
+        if repo.branches.contains(&name) {
+            return Err(format!("a branch named '{}' already exists", name).into());
+        }
+        repo.branches.push(name);
+        Ok(())
+    }
+
+    fn delete_branch(&self, name: String) -> Result<(), GustError> {
+        self.record("branch -D", &[&name]);
+        let mut repo = self.repo.borrow_mut();
+        if repo.current_branch.as_ref() == Some(&name) {
+            return Err(format!("Cannot delete branch '{}' checked out", name).into());
+        }
+        let before = repo.branches.len();
+        repo.branches.retain(|b| *b != name);
+        if repo.branches.len() == before {
+            return Err(format!("branch '{}' not found", name).into());
+        }
+        Ok(())
+    }
+
+    fn create_tag(
+        &self,
+        name: String,
+        commit: Option<String>,
+        message: Option<String>,
+    ) -> Result<(), GustError> {
+        self.record(
+            "tag",
+            &[message.as_ref(), Some(&name), commit.as_ref()]
+                .iter()
+                .flatten()
+                .collect::<Vec<_>>(),
+        );
+        let mut repo = self.repo.borrow_mut();
+        if repo.tags.iter().any(|(tag, _)| *tag == name) {
+            return Err(format!("tag '{}' already exists", name).into());
+        }
+        let index = resolve(&repo, commit.as_deref().unwrap_or("HEAD"))?;
+        let hash = repo.commits[index].0.clone();
+        repo.tags.push((name, hash));
+        Ok(())
+    }
+
+    fn delete_tag(&self, name: String, remote: Option<String>) -> Result<(), GustError> {
+        match remote {
+            Some(remote) => {
+                self.record("push", &[remote, "--delete".to_string(), name]);
+                // The fake repository doesn't keep the tags of its remotes
+                Ok(())
+            }
+            None => {
+                self.record("tag --delete", &[&name]);
+                let mut repo = self.repo.borrow_mut();
+                let before = repo.tags.len();
+                repo.tags.retain(|(tag, _)| *tag != name);
+                if repo.tags.len() == before {
+                    return Err(format!("tag '{}' not found.", name).into());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn push_tags(&self, remote: String, tag: Option<String>) -> Result<(), GustError> {
+        let target = tag.unwrap_or_else(|| "--tags".to_string());
+        self.record("push", &[&remote, &target]);
+        if !self.repo.borrow().remotes.iter().any(|r| r.name == remote) {
+            return Err(format!("'{}' does not appear to be a git repository", remote).into());
+        }
+        Ok(())
+    }
+
+    fn set_upstream(&self, branch: Option<String>, upstream: String) -> Result<(), GustError> {
+        self.record("branch", &[format!("--set-upstream-to={}", upstream)]);
+        if branch.is_some() && branch != self.repo.borrow().current_branch {
+            return Err("The fake backend only tracks the current branch's upstream".into());
+        }
+        self.repo.borrow_mut().upstream = Some(upstream);
+        Ok(())
+    }
+
+    fn unset_upstream(&self, branch: Option<String>) -> Result<(), GustError> {
+        self.record(
+            "branch --unset-upstream",
+            &branch.iter().collect::<Vec<_>>(),
+        );
+        if branch.is_some() && branch != self.repo.borrow().current_branch {
+            return Err("The fake backend only tracks the current branch's upstream".into());
+        }
+        if self.repo.borrow_mut().upstream.take().is_none() {
+            return Err("Branch has no upstream information".into());
+        }
+        Ok(())
+    }
+
+    fn rename_branch(&self, old_name: String, new_name: String) -> Result<(), GustError> {
+        self.record("branch --move", &[&old_name, &new_name]);
+        let mut repo = self.repo.borrow_mut();
+        if repo.branches.contains(&new_name) {
+            return Err(format!("a branch named '{}' already exists", new_name).into());
+        }
+        let branch = repo
+            .branches
+            .iter_mut()
+            .find(|b| **b == old_name)
+            .ok_or_else(|| format!("no branch named '{}'", old_name))?;
+        *branch = new_name.clone();
+        if repo.current_branch.as_ref() == Some(&old_name) {
+            repo.current_branch = Some(new_name);
+        }
+        Ok(())
+    }
+
+    fn fetch(&self, remote: Option<String>) -> Result<(), GustError> {
+        self.record("fetch", &[remote.unwrap_or_else(|| "--all".to_string())]);
+        Ok(())
+    }
+
+    fn pull(&self) -> Result<(), GustError> {
+        self.record::<&str>("pull", &[]);
+        match self.repo.borrow().upstream {
+            Some(_) => Ok(()),
+            None => Err("There is no tracking information for the current branch.".into()),
+        }
+    }
+
+    fn push(&self) -> Result<(), GustError> {
+        self.record::<&str>("push", &[]);
+        match self.repo.borrow().upstream {
+            Some(_) => Ok(()),
+            None => Err("The current branch has no upstream branch.".into()),
+        }
+    }
+
+    fn push_set_upstream(&self, remote: String, branch: String) -> Result<(), GustError> {
+        self.record("push --set-upstream", &[&remote, &branch]);
+        let mut repo = self.repo.borrow_mut();
+        let upstream = format!("{}/{}", remote, branch);
+        if !repo.remote_branches.contains(&upstream) {
+            repo.remote_branches.push(upstream.clone());
+        }
+        repo.upstream = Some(upstream);
+        Ok(())
+    }
+
+    fn stash_push(
+        &self,
+        message: Option<String>,
+        include_untracked: bool,
+        keep_index: bool,
+    ) -> Result<(), GustError> {
+        self.record::<&str>("stash push", &[]);
+        let mut repo = self.repo.borrow_mut();
+
+        let files: BTreeMap<PathBuf, FakeFile> = repo
+            .files
+            .iter()
+            .filter(|(_, f)| {
+                (f.head != f.index || f.index != f.worktree)
+                    && (include_untracked || f.head.is_some() || f.index.is_some())
+            })
+            .map(|(path, f)| (path.clone(), f.clone()))
+            .collect();
+        if files.is_empty() {
+            return Err("No local changes to save".into());
+        }
+
+        for path in files.keys() {
+            let file = repo.files.get_mut(path).unwrap();
+            if !keep_index {
+                file.index = file.head.clone();
+            }
+            file.worktree = file.index.clone();
+        }
+        repo.files.retain(|_, f| *f != FakeFile::default());
+
+        let branch = repo.current_branch.clone().unwrap_or_default();
+        let description = match message {
+            Some(message) => format!("On {}: {}", branch, message),
+            None => format!("WIP on {}", branch),
+        };
+        repo.stashes.insert(0, FakeStash { description, files });
+        Ok(())
+    }
+
+    fn stash_apply(&self, stash: String, pop: bool) -> Result<(), GustError> {
+        self.record(if pop { "stash pop" } else { "stash apply" }, &[&stash]);
+        let mut repo = self.repo.borrow_mut();
+        let index = stash_index(&stash)?;
+        let saved = repo
+            .stashes
+            .get(index)
+            .ok_or_else(|| format!("{} doesn't exist", stash))?
+            .clone();
+
+        for (path, saved_file) in saved.files {
+            let file = repo.files.entry(path).or_default();
+            // Like git, the stash is only restaged when it was applied with --index
+            file.worktree = saved_file.worktree;
+        }
+        if pop {
+            repo.stashes.remove(index);
+        }
+        Ok(())
+    }
+
+    fn stash_drop(&self, stash: String) -> Result<(), GustError> {
+        self.record("stash drop", &[&stash]);
+        let mut repo = self.repo.borrow_mut();
+        let index = stash_index(&stash)?;
+        if index >= repo.stashes.len() {
+            return Err(format!("{} doesn't exist", stash).into());
+        }
+        repo.stashes.remove(index);
+        Ok(())
+    }
+
+    fn stash_branch(&self, branch: String, stash: String) -> Result<(), GustError> {
+        self.create_branch(branch.clone(), None)?;
+        self.checkout(vec![branch])?;
+        self.stash_apply(stash, true)
+    }
+
+    fn operation(&self, operation: Operation, action: &str) -> Result<(), GustError> {
+        self.record(operation.command(), &[format!("--{}", action)]);
+        Err(format!("There is no {} in progress", operation).into())
+    }
+
+    fn remote_add(&self, name: String, url: String) -> Result<(), GustError> {
+        self.record("remote add", &[&name, &url]);
+        let mut repo = self.repo.borrow_mut();
+        if repo.remotes.iter().any(|r| r.name == name) {
+            return Err(format!("remote {} already exists.", name).into());
+        }
+        repo.remotes.push(Remote {
+            name,
+            fetch_url: url.clone(),
+            push_url: url,
+        });
+        Ok(())
+    }
+
+    fn remote_rename(&self, old_name: String, new_name: String) -> Result<(), GustError> {
+        self.record("remote rename", &[&old_name, &new_name]);
+        let mut repo = self.repo.borrow_mut();
+        let remote = repo
+            .remotes
+            .iter_mut()
+            .find(|r| r.name == old_name)
+            .ok_or_else(|| format!("No such remote: '{}'", old_name))?;
+        remote.name = new_name;
+        Ok(())
+    }
+
+    fn remote_remove(&self, name: String) -> Result<(), GustError> {
+        self.record("remote remove", &[&name]);
+        let mut repo = self.repo.borrow_mut();
+        repo.remotes.retain(|r| r.name != name);
+        let prefix = format!("{}/", name);
+        repo.remote_branches.retain(|b| !b.starts_with(&prefix));
+        Ok(())
+    }
+
+    fn remote_set_url(&self, name: String, url: String, push_only: bool) -> Result<(), GustError> {
+        self.record("remote set-url", &[&name, &url]);
+        let mut repo = self.repo.borrow_mut();
+        let remote = repo
+            .remotes
+            .iter_mut()
+            .find(|r| r.name == name)
+            .ok_or_else(|| format!("No such remote '{}'", name))?;
+        if !push_only {
+            remote.fetch_url = url.clone();
+        }
+        remote.push_url = url;
+        Ok(())
+    }
+
+    fn remote_prune(&self, name: String) -> Result<(), GustError> {
+        self.record("remote prune", &[&name]);
+        Ok(())
+    }
+
+    fn init(&self, directory: PathBuf, initial_branch: String) -> Result<(), GustError> {
+        self.record(
+            &format!("init --initial-branch={}", initial_branch),
+            &[directory],
+        );
+        Ok(())
+    }
+
+    fn clone_repo(
+        &self,
+        source: String,
+        directory: PathBuf,
+        _depth: Option<u32>,
+        _branch: Option<String>,
+    ) -> Result<(), GustError> {
+        self.record(&format!("clone -- {}", source), &[directory]);
+        Ok(())
+    }
+}
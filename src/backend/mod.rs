@@ -0,0 +1,372 @@
+#[cfg(test)]
+pub mod fake;
+#[cfg(test)]
+pub mod test_repo;
+
+use crate::cli::{self, BranchInfo, LogCommit, Remote, TagInfo};
+use crate::error::GustError;
+use crate::ignore::IgnoreMatch;
+use crate::settings::Config;
+use crate::status::{self, Operation, RepoStatus};
+
+use std::ffi::OsStr;
+use std::path::PathBuf;
+
+/// Everything the menus need from git. `GitCli` runs the `git` executable,
+/// `fake::FakeBackend` keeps a repository in memory for tests.
+pub trait GitBackend {
+    // Reading the repository
+    fn status(&self) -> Result<RepoStatus, GustError>;
+    fn branches(&self) -> Result<Option<Vec<BranchInfo>>, GustError>;
+    fn log(&self, branch: Option<String>) -> Result<Option<String>, GustError>;
+    fn ls_tree(&self) -> Result<Option<String>, GustError>;
+    fn diff<S: AsRef<OsStr>>(
+        &self,
+        options: Vec<String>,
+        paths: Vec<S>,
+    ) -> Result<Option<String>, GustError>;
+    fn stash_list(&self) -> Result<Option<Vec<String>>, GustError>;
+    fn stash_show(&self, stash: String) -> Result<String, GustError>;
+    fn show(&self, commit: String) -> Result<String, GustError>;
+    fn commit_message(&self, commit: String) -> Result<String, GustError>;
+    fn remote_branches_containing(&self, commit: String) -> Result<Vec<String>, GustError>;
+    fn recent_authors(&self) -> Result<Vec<String>, GustError>;
+    fn log_commits(&self, range: String) -> Result<Vec<LogCommit>, GustError>;
+    fn latest_tag(&self, commit: String) -> Result<Option<String>, GustError>;
+    fn tags(&self) -> Result<Vec<String>, GustError>;
+    fn tag_list(&self) -> Result<Vec<TagInfo>, GustError>;
+    fn remotes(&self) -> Result<Option<Vec<Remote>>, GustError>;
+    fn rev_parse(&self, option: &str) -> Result<String, GustError>;
+    fn check_ignore(&self, path: String) -> Result<Option<IgnoreMatch>, GustError>;
+    fn is_repo(&self) -> Result<bool, GustError>;
+    fn config_get(&self, key: &str) -> Result<Option<String>, GustError>;
+    /// Where `path` inside the `.git` directory really is, following `core.hooksPath`
+    /// and the directories linked work trees share
+    fn git_path(&self, path: &str) -> Result<PathBuf, GustError>;
+
+    /// The path of the `.git` directory of the current repository
+    fn git_dir(&self) -> Result<PathBuf, GustError> {
+        Ok(PathBuf::from(self.rev_parse("--git-dir")?))
+    }
+
+    /// What comment lines in a commit message start with, from `core.commentChar`.
+    /// `auto` only moves away from `#` when a line of the message starts with it,
+    /// which can't be told apart from a comment afterwards.
+    fn comment_prefix(&self) -> Result<String, GustError> {
+        Ok(match self.config_get("core.commentChar")? {
+            Some(prefix) if !prefix.is_empty() && prefix != "auto" => prefix,
+            _ => "#".to_string(),
+        })
+    }
+
+    /// The root directory of the current repository's work tree
+    fn toplevel(&self) -> Result<PathBuf, GustError> {
+        Ok(PathBuf::from(self.rev_parse("--show-toplevel")?))
+    }
+
+    fn operation_in_progress(&self) -> Result<Option<Operation>, GustError> {
+        Ok(status::operation_in_progress(&self.git_dir()?))
+    }
+
+    // Changing the repository
+    fn add<S: AsRef<OsStr>>(&self, paths: Vec<S>) -> Result<(), GustError>;
+    fn reset<S: AsRef<OsStr>>(&self, paths: Vec<S>) -> Result<(), GustError>;
+    fn rm<S: AsRef<OsStr>>(&self, paths: Vec<S>, as_cached: bool) -> Result<(), GustError>;
+    fn apply(&self, patch: String, options: Vec<String>) -> Result<(), GustError>;
+    fn commit(&self, options: Option<Vec<String>>, config: &Config) -> Result<(), GustError>;
+    fn revert(&self, commit_hash: String) -> Result<(), GustError>;
+    fn cherry_pick(&self, commits: Vec<String>, record_origin: bool) -> Result<(), GustError>;
+    fn merge(&self, args: Vec<String>) -> Result<(), GustError>;
+    fn reset_merge(&self) -> Result<(), GustError>;
+    fn rebase(&self, args: Vec<String>, sequence_editor: Option<String>) -> Result<(), GustError>;
+    fn checkout<S: AsRef<OsStr>>(&self, args: Vec<S>) -> Result<(), GustError>;
+    fn create_branch(&self, name: String, start: Option<String>) -> Result<(), GustError>;
+    fn delete_branch(&self, name: String) -> Result<(), GustError>;
+    fn create_tag(
+        &self,
+        name: String,
+        commit: Option<String>,
+        message: Option<String>,
+    ) -> Result<(), GustError>;
+    fn delete_tag(&self, name: String, remote: Option<String>) -> Result<(), GustError>;
+    fn push_tags(&self, remote: String, tag: Option<String>) -> Result<(), GustError>;
+    fn set_upstream(&self, branch: Option<String>, upstream: String) -> Result<(), GustError>;
+    fn unset_upstream(&self, branch: Option<String>) -> Result<(), GustError>;
+    fn rename_branch(&self, old_name: String, new_name: String) -> Result<(), GustError>;
+    fn fetch(&self, remote: Option<String>) -> Result<(), GustError>;
+    fn pull(&self) -> Result<(), GustError>;
+    fn push(&self) -> Result<(), GustError>;
+    fn push_set_upstream(&self, remote: String, branch: String) -> Result<(), GustError>;
+    fn stash_push(
+        &self,
+        message: Option<String>,
+        include_untracked: bool,
+        keep_index: bool,
+    ) -> Result<(), GustError>;
+    fn stash_apply(&self, stash: String, pop: bool) -> Result<(), GustError>;
+    fn stash_drop(&self, stash: String) -> Result<(), GustError>;
+    fn stash_branch(&self, branch: String, stash: String) -> Result<(), GustError>;
+    fn operation(&self, operation: Operation, action: &str) -> Result<(), GustError>;
+    fn remote_add(&self, name: String, url: String) -> Result<(), GustError>;
+    fn remote_rename(&self, old_name: String, new_name: String) -> Result<(), GustError>;
+    fn remote_remove(&self, name: String) -> Result<(), GustError>;
+    fn remote_set_url(&self, name: String, url: String, push_only: bool) -> Result<(), GustError>;
+    fn remote_prune(&self, name: String) -> Result<(), GustError>;
+    fn init(&self, directory: PathBuf, initial_branch: String) -> Result<(), GustError>;
+    fn clone_repo(
+        &self,
+        source: String,
+        directory: PathBuf,
+        depth: Option<u32>,
+        branch: Option<String>,
+    ) -> Result<(), GustError>;
+}
+
+/// Runs the `git` executable through the functions in `cli`
+pub struct GitCli;
+
+impl GitBackend for GitCli {
+    fn status(&self) -> Result<RepoStatus, GustError> {
+        status::git_status()
+    }
+
+    fn branches(&self) -> Result<Option<Vec<BranchInfo>>, GustError> {
+        cli::git_get_branches()
+    }
+
+    fn log(&self, branch: Option<String>) -> Result<Option<String>, GustError> {
+        cli::git_log(branch)
+    }
+
+    fn ls_tree(&self) -> Result<Option<String>, GustError> {
+        cli::git_ls_tree()
+    }
+
+    fn diff<S: AsRef<OsStr>>(
+        &self,
+        options: Vec<String>,
+        paths: Vec<S>,
+    ) -> Result<Option<String>, GustError> {
+        cli::git_diff(options, paths)
+    }
+
+    fn stash_list(&self) -> Result<Option<Vec<String>>, GustError> {
+        cli::git_stash_list()
+    }
+
+    fn stash_show(&self, stash: String) -> Result<String, GustError> {
+        cli::git_stash_show(stash)
+    }
+
+    fn show(&self, commit: String) -> Result<String, GustError> {
+        cli::git_show(commit)
+    }
+
+    fn commit_message(&self, commit: String) -> Result<String, GustError> {
+        cli::git_commit_message(commit)
+    }
+
+    fn remote_branches_containing(&self, commit: String) -> Result<Vec<String>, GustError> {
+        cli::git_remote_branches_containing(commit)
+    }
+
+    fn recent_authors(&self) -> Result<Vec<String>, GustError> {
+        cli::git_recent_authors()
+    }
+
+    fn log_commits(&self, range: String) -> Result<Vec<LogCommit>, GustError> {
+        cli::git_log_commits(range)
+    }
+
+    fn latest_tag(&self, commit: String) -> Result<Option<String>, GustError> {
+        cli::git_latest_tag(commit)
+    }
+
+    fn tags(&self) -> Result<Vec<String>, GustError> {
+        cli::git_tags()
+    }
+
+    fn tag_list(&self) -> Result<Vec<TagInfo>, GustError> {
+        cli::git_tag_list()
+    }
+
+    fn remotes(&self) -> Result<Option<Vec<Remote>>, GustError> {
+        cli::git_remotes()
+    }
+
+    fn rev_parse(&self, option: &str) -> Result<String, GustError> {
+        cli::git_rev_parse(option)
+    }
+
+    fn git_path(&self, path: &str) -> Result<PathBuf, GustError> {
+        cli::git_path(path)
+    }
+
+    fn check_ignore(&self, path: String) -> Result<Option<IgnoreMatch>, GustError> {
+        cli::git_check_ignore(path)
+    }
+
+    fn is_repo(&self) -> Result<bool, GustError> {
+        cli::git_is_repo()
+    }
+
+    fn config_get(&self, key: &str) -> Result<Option<String>, GustError> {
+        cli::git_config_get(key)
+    }
+
+    fn add<S: AsRef<OsStr>>(&self, paths: Vec<S>) -> Result<(), GustError> {
+        cli::git_add(paths)
+    }
+
+    fn reset<S: AsRef<OsStr>>(&self, paths: Vec<S>) -> Result<(), GustError> {
+        cli::git_reset(paths)
+    }
+
+    fn rm<S: AsRef<OsStr>>(&self, paths: Vec<S>, as_cached: bool) -> Result<(), GustError> {
+        cli::git_rm(paths, as_cached)
+    }
+
+    fn apply(&self, patch: String, options: Vec<String>) -> Result<(), GustError> {
+        cli::git_apply(patch, options)
+    }
+
+    fn commit(&self, options: Option<Vec<String>>, config: &Config) -> Result<(), GustError> {
+        cli::git_commit(options, config)
+    }
+
+    fn revert(&self, commit_hash: String) -> Result<(), GustError> {
+        cli::git_revert(commit_hash)
+    }
+
+    fn cherry_pick(&self, commits: Vec<String>, record_origin: bool) -> Result<(), GustError> {
+        cli::git_cherry_pick(commits, record_origin)
+    }
+
+    fn merge(&self, args: Vec<String>) -> Result<(), GustError> {
+        cli::git_merge(args)
+    }
+
+    fn reset_merge(&self) -> Result<(), GustError> {
+        cli::git_reset_merge()
+    }
+
+    fn rebase(&self, args: Vec<String>, sequence_editor: Option<String>) -> Result<(), GustError> {
+        cli::git_rebase(args, sequence_editor)
+    }
+
+    fn checkout<S: AsRef<OsStr>>(&self, args: Vec<S>) -> Result<(), GustError> {
+        cli::git_checkout(args)
+    }
+
+    fn create_branch(&self, name: String, start: Option<String>) -> Result<(), GustError> {
+        cli::git_create_branch(name, start)
+    }
+
+    fn delete_branch(&self, name: String) -> Result<(), GustError> {
+        cli::git_branch_delete(name)
+    }
+
+    fn create_tag(
+        &self,
+        name: String,
+        commit: Option<String>,
+        message: Option<String>,
+    ) -> Result<(), GustError> {
+        cli::git_create_tag(name, commit, message)
+    }
+
+    fn delete_tag(&self, name: String, remote: Option<String>) -> Result<(), GustError> {
+        cli::git_delete_tag(name, remote)
+    }
+
+    fn push_tags(&self, remote: String, tag: Option<String>) -> Result<(), GustError> {
+        cli::git_push_tags(remote, tag)
+    }
+
+    fn set_upstream(&self, branch: Option<String>, upstream: String) -> Result<(), GustError> {
+        cli::git_set_upstream(branch, upstream)
+    }
+
+    fn unset_upstream(&self, branch: Option<String>) -> Result<(), GustError> {
+        cli::git_unset_upstream(branch)
+    }
+
+    fn rename_branch(&self, old_name: String, new_name: String) -> Result<(), GustError> {
+        cli::git_rename_branch(old_name, new_name)
+    }
+
+    fn fetch(&self, remote: Option<String>) -> Result<(), GustError> {
+        cli::git_fetch(remote)
+    }
+
+    fn pull(&self) -> Result<(), GustError> {
+        cli::git_pull()
+    }
+
+    fn push(&self) -> Result<(), GustError> {
+        cli::git_push()
+    }
+
+    fn push_set_upstream(&self, remote: String, branch: String) -> Result<(), GustError> {
+        cli::git_push_set_upstream(remote, branch)
+    }
+
+    fn stash_push(
+        &self,
+        message: Option<String>,
+        include_untracked: bool,
+        keep_index: bool,
+    ) -> Result<(), GustError> {
+        cli::git_stash_push(message, include_untracked, keep_index)
+    }
+
+    fn stash_apply(&self, stash: String, pop: bool) -> Result<(), GustError> {
+        cli::git_stash_apply(stash, pop)
+    }
+
+    fn stash_drop(&self, stash: String) -> Result<(), GustError> {
+        cli::git_stash_drop(stash)
+    }
+
+    fn stash_branch(&self, branch: String, stash: String) -> Result<(), GustError> {
+        cli::git_stash_branch(branch, stash)
+    }
+
+    fn operation(&self, operation: Operation, action: &str) -> Result<(), GustError> {
+        cli::git_operation(operation, action)
+    }
+
+    fn remote_add(&self, name: String, url: String) -> Result<(), GustError> {
+        cli::git_remote_add(name, url)
+    }
+
+    fn remote_rename(&self, old_name: String, new_name: String) -> Result<(), GustError> {
+        cli::git_remote_rename(old_name, new_name)
+    }
+
+    fn remote_remove(&self, name: String) -> Result<(), GustError> {
+        cli::git_remote_remove(name)
+    }
+
+    fn remote_set_url(&self, name: String, url: String, push_only: bool) -> Result<(), GustError> {
+        cli::git_remote_set_url(name, url, push_only)
+    }
+
+    fn remote_prune(&self, name: String) -> Result<(), GustError> {
+        cli::git_remote_prune(name)
+    }
+
+    fn init(&self, directory: PathBuf, initial_branch: String) -> Result<(), GustError> {
+        cli::git_init(directory, initial_branch)
+    }
+
+    fn clone_repo(
+        &self,
+        source: String,
+        directory: PathBuf,
+        depth: Option<u32>,
+        branch: Option<String>,
+    ) -> Result<(), GustError> {
+        cli::git_clone(source, directory, depth, branch)
+    }
+}
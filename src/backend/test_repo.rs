@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// `GitCli` runs git in the working directory, which the whole test process shares
+static WORKING_DIRECTORY: Mutex<()> = Mutex::new(());
+static REPOS_MADE: AtomicUsize = AtomicUsize::new(0);
+
+/// A real repository on `main` in a temporary directory, for tests that drive the menus
+/// through `GitCli`. It's the working directory until dropped, and the Gust config directory
+/// points next to it at a copy of the default convention.
+pub struct TestRepo {
+    pub work_tree: PathBuf,
+    root: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl TestRepo {
+    pub fn new() -> Self {
+        // A test that panicked while holding the lock has already cleaned up after itself
+        let lock = WORKING_DIRECTORY
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let root = std::env::temp_dir().join(format!(
+            "gust-test-{}-{}",
+            std::process::id(),
+            REPOS_MADE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let work_tree = root.join("work");
+        let config_dir = root.join("config");
+        fs::create_dir_all(&work_tree).unwrap();
+        fs::create_dir_all(config_dir.join("gust")).unwrap();
+        fs::copy(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("ConventionalCommits.json"),
+            config_dir.join("gust").join("ConventionalCommits.json"),
+        )
+        .unwrap();
+        std::env::set_var("XDG_CONFIG_HOME", &config_dir);
+        // Repositories made or cloned by the menus need an identity too, and git mustn't
+        // wait on an editor, e.g. for the message of a squash
+        let git_config = config_dir.join("gitconfig");
+        fs::write(
+            &git_config,
+            "[user]\n\tname = Gust Tests\n\temail = tests@gust.invalid\n\
+             [commit]\n\tgpgSign = false\n\
+             [core]\n\teditor = true\n",
+        )
+        .unwrap();
+        std::env::set_var("GIT_CONFIG_GLOBAL", &git_config);
+        std::env::set_current_dir(&work_tree).unwrap();
+
+        let repo = TestRepo {
+            work_tree,
+            root,
+            _lock: lock,
+        };
+        repo.git(&["init", "--quiet", "--initial-branch=main"]);
+        repo
+    }
+
+    /// Run git in the repository, panicking if it fails. Returns stdout without the last newline.
+    pub fn git(&self, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.work_tree)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end_matches('\n')
+            .to_string()
+    }
+
+    pub fn write(&self, path: &str, contents: &str) {
+        fs::write(self.work_tree.join(path), contents).unwrap();
+    }
+
+    /// Add a commit containing `path` with `contents`
+    pub fn commit_file(&self, path: &str, contents: &str, message: &str) {
+        self.write(path, contents);
+        self.git(&["add", path]);
+        self.git(&["commit", "--quiet", "-m", message]);
+    }
+
+    /// A path named `name` next to the work tree, removed along with it
+    pub fn outside(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    /// Make a bare clone named `name` next to the work tree, for use as a remote
+    pub fn bare_clone(&self, name: &str) -> PathBuf {
+        let path = self.outside(name);
+        self.git(&["clone", "--quiet", "--bare", ".", path.to_str().unwrap()]);
+        path
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = std::env::set_current_dir(env!("CARGO_MANIFEST_DIR"));
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
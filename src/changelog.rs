@@ -0,0 +1,358 @@
+use crate::backend::GitBackend;
+use crate::cli::LogCommit;
+use crate::convention::{self, ConventionalSubject};
+use crate::error::GustError;
+use crate::settings::ConventionSettings;
+
+use serde::Serialize;
+use std::path::Path;
+
+/// A commit that followed the convention
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangelogEntry {
+    pub hash: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub scope: Option<String>,
+    pub description: String,
+    /// What breaks, from the `BREAKING CHANGE` footer or the subject of an important commit
+    pub breaking: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Changelog {
+    pub title: String,
+    /// The date of the newest commit
+    pub date: Option<String>,
+    pub entries: Vec<ChangelogEntry>,
+    /// Subjects of the commits that don't follow the convention
+    pub other: Vec<String>,
+}
+
+/// The changelog of the commits after `from` up to `to`. Without `from` it starts after
+/// the latest tag, or the one before it when `to` is tagged itself.
+pub fn collect(
+    backend: &impl GitBackend,
+    conventions: &ConventionSettings,
+    from: Option<String>,
+    to: &str,
+    title: String,
+) -> Result<Changelog, GustError> {
+    let commits = match from {
+        Some(from) => backend.log_commits(format!("{}..{}", from, to))?,
+        None => match backend.latest_tag(to.to_string())? {
+            None => backend.log_commits(to.to_string())?,
+            Some(tag) => {
+                let commits = backend.log_commits(format!("{}..{}", tag, to))?;
+                if commits.is_empty() {
+                    // The first tag has no tag before it
+                    match backend.latest_tag(format!("{}^", tag)) {
+                        Ok(Some(previous)) => {
+                            backend.log_commits(format!("{}..{}", previous, to))?
+                        }
+                        Ok(None) | Err(GustError::GitFailed { .. }) => {
+                            backend.log_commits(to.to_string())?
+                        }
+                        Err(e) => return Err(e),
+                    }
+                } else {
+                    commits
+                }
+            }
+        },
+    };
+    Ok(Changelog::from_commits(title, &commits, conventions))
+}
+
+impl Changelog {
+    pub fn from_commits(
+        title: String,
+        commits: &[LogCommit],
+        conventions: &ConventionSettings,
+    ) -> Self {
+        let mut changelog = Changelog {
+            title,
+            date: commits.first().map(|c| c.date.clone()),
+            entries: vec![],
+            other: vec![],
+        };
+
+        for commit in commits {
+            let subject = commit.message.lines().next().unwrap_or_default();
+            // Merges and fixups don't describe a change of their own
+            if ["Merge ", "fixup! ", "squash! ", "amend! "]
+                .iter()
+                .any(|prefix| subject.starts_with(prefix))
+            {
+                continue;
+            }
+
+            match ConventionalSubject::parse(subject, conventions) {
+                Some(parsed) => {
+                    let breaking = breaking_footer(&commit.message, conventions).or_else(|| {
+                        if parsed.important {
+                            Some(parsed.description.clone())
+                        } else {
+                            None
+                        }
+                    });
+                    changelog.entries.push(ChangelogEntry {
+                        hash: commit.hash.clone(),
+                        kind: parsed.kind,
+                        scope: parsed.scope,
+                        description: parsed.description,
+                        breaking,
+                    });
+                }
+                None => changelog
+                    .other
+                    .push(format!("{} ({})", subject, short_hash(&commit.hash))),
+            }
+        }
+        changelog
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.other.is_empty()
+    }
+
+    pub fn to_markdown(&self, conventions: &ConventionSettings) -> String {
+        let mut output = match &self.date {
+            Some(date) => format!("## {} ({})\n", self.title, date),
+            None => format!("## {}\n", self.title),
+        };
+
+        let breaking: Vec<&ChangelogEntry> = self
+            .entries
+            .iter()
+            .filter(|e| e.breaking.is_some())
+            .collect();
+        if !breaking.is_empty() {
+            output.push_str("\n### ⚠ BREAKING CHANGES\n\n");
+            for entry in breaking {
+                output.push_str(&format!(
+                    "- {}{}\n",
+                    scope_prefix(entry),
+                    entry.breaking.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+
+        // The convention's types in its order, then any others it doesn't list
+        let mut kinds: Vec<&str> = conventions.types.iter().map(|t| t.as_str()).collect();
+        let mut unlisted: Vec<&str> = self
+            .entries
+            .iter()
+            .map(|e| e.kind.as_str())
+            .filter(|k| !kinds.contains(k))
+            .collect();
+        unlisted.sort_unstable();
+        unlisted.dedup();
+        kinds.extend(unlisted);
+
+        for kind in kinds {
+            let mut entries: Vec<&ChangelogEntry> =
+                self.entries.iter().filter(|e| e.kind == kind).collect();
+            if entries.is_empty() {
+                continue;
+            }
+            entries.sort_by(|a, b| a.scope.cmp(&b.scope));
+
+            output.push_str(&format!("\n### {}\n\n", section_title(kind)));
+            for entry in entries {
+                output.push_str(&format!(
+                    "- {}{} ({})\n",
+                    scope_prefix(entry),
+                    entry.description,
+                    short_hash(&entry.hash)
+                ));
+            }
+        }
+
+        if !self.other.is_empty() {
+            output.push_str("\n### Other\n\n");
+            for subject in &self.other {
+                output.push_str(&format!("- {}\n", subject));
+            }
+        }
+        output
+    }
+
+    pub fn to_json(&self) -> Result<String, GustError> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string().into())
+    }
+}
+
+/// Add `markdown` above the newest release in `path`, keeping anything before it at the top
+pub fn prepend_to_file(path: &Path, markdown: &str) -> Result<(), GustError> {
+    let existing = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::from("# Changelog\n")
+    };
+
+    // Releases are `## ` headings, the file's own heading and introduction stay above them
+    let split = if existing.starts_with("## ") {
+        0
+    } else {
+        existing
+            .find("\n## ")
+            .map(|i| i + 1)
+            .unwrap_or(existing.len())
+    };
+    let (intro, releases) = existing.split_at(split);
+
+    let mut output = intro.trim_end().to_string();
+    if !output.is_empty() {
+        output.push_str("\n\n");
+    }
+    output.push_str(markdown.trim_end());
+    output.push('\n');
+    if !releases.is_empty() {
+        output.push('\n');
+        output.push_str(releases);
+    }
+    std::fs::write(path, output)?;
+    Ok(())
+}
+
+/// The value of the first footer starting with `BREAKING`, e.g. `BREAKING CHANGE` or `BREAKING-CHANGE`
+fn breaking_footer(message: &str, conventions: &ConventionSettings) -> Option<String> {
+    message.lines().skip(1).find_map(|line| {
+        let (token, value) = convention::parse_footer(line, conventions)?;
+        if token.starts_with("BREAKING") {
+            Some(value.trim().to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn scope_prefix(entry: &ChangelogEntry) -> String {
+    match &entry.scope {
+        Some(scope) => format!("**{}:** ", scope),
+        None => String::new(),
+    }
+}
+
+fn short_hash(hash: &str) -> &str {
+    &hash[..hash.len().min(7)]
+}
+
+fn section_title(kind: &str) -> String {
+    match kind {
+        "feat" => "Features".to_string(),
+        "fix" => "Bug Fixes".to_string(),
+        "perf" => "Performance".to_string(),
+        "refactor" => "Refactoring".to_string(),
+        "docs" => "Documentation".to_string(),
+        "build" => "Build".to_string(),
+        "ci" => "Continuous Integration".to_string(),
+        "chore" => "Chores".to_string(),
+        "style" => "Style".to_string(),
+        "test" => "Tests".to_string(),
+        "revert" => "Reverts".to_string(),
+        _ => kind.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conventions() -> ConventionSettings {
+        serde_json::from_str(include_str!("../ConventionalCommits.json")).unwrap()
+    }
+
+    fn commit(hash: &str, message: &str) -> LogCommit {
+        LogCommit {
+            hash: hash.to_string(),
+            date: "2024-01-01".to_string(),
+            merge: false,
+            message: message.to_string(),
+        }
+    }
+
+    /// Newest first, like `git log`
+    fn changelog() -> Changelog {
+        let commits = vec![
+            commit("aaaaaaa1", "fix(ui): stop the button flickering"),
+            commit("bbbbbbb2", "Merge branch 'feature'"),
+            commit("ccccccc3", "fixup! feat: add a button"),
+            commit("ddddddd4", "Update the readme"),
+            commit("eeeeeee5", "feat!: drop the old button"),
+            commit(
+                "fffffff6",
+                "feat(ui): add a button\n\nBody\n\nBREAKING-CHANGE: the menu moved",
+            ),
+        ];
+        Changelog::from_commits("v1.0.0".to_string(), &commits, &conventions())
+    }
+
+    #[test]
+    fn sorts_commits_into_entries_and_others() {
+        let changelog = changelog();
+        assert_eq!(changelog.date.as_deref(), Some("2024-01-01"));
+        assert_eq!(changelog.other, vec!["Update the readme (ddddddd)"]);
+
+        let entries: Vec<(&str, Option<&str>, &str)> = changelog
+            .entries
+            .iter()
+            .map(|e| (e.kind.as_str(), e.scope.as_deref(), e.description.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("fix", Some("ui"), "stop the button flickering"),
+                ("feat", None, "drop the old button"),
+                ("feat", Some("ui"), "add a button"),
+            ]
+        );
+    }
+
+    #[test]
+    fn takes_breaking_changes_from_footers_and_important_subjects() {
+        let changelog = changelog();
+        let breaking: Vec<Option<&str>> = changelog
+            .entries
+            .iter()
+            .map(|e| e.breaking.as_deref())
+            .collect();
+        assert_eq!(
+            breaking,
+            vec![None, Some("drop the old button"), Some("the menu moved")]
+        );
+
+        let footer = commit("1234567", "fix: x\n\nBREAKING CHANGE: spaces work too");
+        let changelog = Changelog::from_commits(String::new(), &[footer], &conventions());
+        assert_eq!(
+            changelog.entries[0].breaking.as_deref(),
+            Some("spaces work too")
+        );
+    }
+
+    #[test]
+    fn renders_markdown_in_the_convention_order() {
+        assert_eq!(
+            changelog().to_markdown(&conventions()),
+            "## v1.0.0 (2024-01-01)\n\
+             \n### ⚠ BREAKING CHANGES\n\n\
+             - drop the old button\n\
+             - **ui:** the menu moved\n\
+             \n### Bug Fixes\n\n\
+             - **ui:** stop the button flickering (aaaaaaa)\n\
+             \n### Features\n\n\
+             - drop the old button (eeeeeee)\n\
+             - **ui:** add a button (fffffff)\n\
+             \n### Other\n\n\
+             - Update the readme (ddddddd)\n"
+        );
+    }
+
+    #[test]
+    fn no_commits_make_an_empty_changelog() {
+        let changelog = Changelog::from_commits("v1.0.0".to_string(), &[], &conventions());
+        assert!(changelog.is_empty());
+        assert_eq!(changelog.to_markdown(&conventions()), "## v1.0.0\n");
+    }
+}
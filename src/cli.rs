@@ -1,9 +1,15 @@
+use crate::error::GustError;
+use crate::git::{self, GitCommand};
+use crate::ignore::IgnoreMatch;
+use crate::prompt;
 use crate::settings::Config;
+use crate::status::Operation;
 use crate::utils;
 
 use colored::Colorize;
-use console;
-use dialoguer::{theme, Confirm, FuzzySelect, Input, MultiSelect, Select};
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
 #[derive(Debug)]
@@ -13,61 +19,63 @@ pub enum UserResponse<T> {
     None,
 }
 
-pub fn get_input(prompt: String) -> Result<String, String> {
-    Input::<String>::new()
-        .with_prompt(prompt)
-        .interact_text_on(&console::Term::stderr())
-        .map_err(|e| e.to_string())
+pub fn get_input(prompt: String) -> Result<String, GustError> {
+    prompt::with_prompter(|p| p.input(&prompt, None, None, false))
 }
 
-pub fn ask_choice_cli(prompt: String) -> Result<bool, String> {
-    match Confirm::new()
-        .with_prompt(prompt)
-        .interact_on_opt(&console::Term::stderr())
-        .expect("Couldn't confirm if user wanted to stage all changed files")
-    {
-        Some(choice) => return Ok(choice),
-        None => Err("An error occured, and no confirmation was gotten".to_string()),
+pub fn get_input_default(prompt: String, default: String) -> Result<String, GustError> {
+    prompt::with_prompter(|p| p.input(&prompt, None, Some(&default), false))
+}
+
+pub fn get_input_optional(prompt: String) -> Result<Option<String>, GustError> {
+    let input = prompt::with_prompter(|p| p.input(&prompt, None, None, true))?;
+
+    if input.trim().is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(input))
     }
 }
 
-pub fn ask_yes_no(prompt: String, default_yes: bool) -> Result<bool, String> {
-    let default = if default_yes { "Y" } else { "N" };
-    let user_input: String = Input::new()
-        .with_prompt(format!("{} (y/n)", prompt).to_string())
-        .default(default.into())
-        .interact_text()
-        .expect("Couldn't ask a yes or no question");
+/// Ask for text, starting with `initial` already typed in
+pub fn get_input_initial(prompt: String, initial: String) -> Result<String, GustError> {
+    prompt::with_prompter(|p| p.input(&prompt, Some(&initial), None, false))
+}
+
+/// Open `text` in the user's editor, `None` if it was closed without saving
+pub fn edit_text(text: &str, trim_newlines: bool) -> Result<Option<String>, GustError> {
+    prompt::with_prompter(|p| p.edit(text, trim_newlines))
+}
 
-    match user_input.chars().nth(0).unwrap() {
-        'y' | 'Y' => Ok(true),
-        _ => Ok(false),
+pub fn ask_choice_cli(prompt: String) -> Result<bool, GustError> {
+    match prompt::with_prompter(|p| p.confirm(&prompt, None))? {
+        Some(choice) => Ok(choice),
+        None => Err(GustError::Cancelled),
     }
 }
 
+pub fn ask_yes_no(prompt: String, default_yes: bool) -> Result<bool, GustError> {
+    Ok(prompt::with_prompter(|p| p.confirm(&prompt, Some(default_yes)))?.unwrap_or(default_yes))
+}
+
 pub fn filter_choice_cli(
     mut choices: Vec<String>,
     allow_none: bool,
-) -> Result<Option<String>, String> {
+) -> Result<Option<String>, GustError> {
     if allow_none {
         choices.push("None".to_string());
     }
-    let selection = FuzzySelect::with_theme(&theme::ColorfulTheme::default())
-        .items(&choices)
-        .with_prompt("Select one:")
-        .default(1)
-        .interact_on_opt(&console::Term::stderr())
-        .expect("Couldn't fuzzy search");
+    let selection = prompt::with_prompter(|p| p.select("Select one:", &choices, true, 1))?;
 
     match selection {
         Some(index) => {
             if index == choices.len() {
-                return Ok(None);
+                Ok(None)
             } else {
-                return Ok(Some(choices[index].to_string()));
+                Ok(Some(choices[index].to_string()))
             }
         }
-        None => Err("No item was selected!".green().to_string()),
+        None => Err(GustError::Cancelled),
     }
 }
 
@@ -76,7 +84,7 @@ pub fn choice_single(
     prompt: String,
     has_all: bool,
     has_none: bool,
-) -> Result<UserResponse<usize>, String> {
+) -> Result<UserResponse<usize>, GustError> {
     if has_all {
         choices.insert(0, "All".to_string());
     }
@@ -84,26 +92,18 @@ pub fn choice_single(
         choices.push("None".to_string());
     }
 
-    let selected = Select::new()
-        .items(&choices)
-        .with_prompt(prompt)
-        .interact_on_opt(&console::Term::stderr())
-        .expect("Couldn't start `select`");
+    let selected = prompt::with_prompter(|p| p.select(&prompt, &choices, false, 0))?;
 
     match selected {
-        None => Err("No items were selected".to_string()),
+        None => Err(GustError::Cancelled),
         Some(index) => {
-            if has_all {
-                if index == 0 {
-                    return Ok(UserResponse::All);
-                }
+            if has_all && index == 0 {
+                return Ok(UserResponse::All);
             }
-            if has_none {
-                if index == choices.len() - 1 {
-                    return Ok(UserResponse::None);
-                }
+            if has_none && index == choices.len() - 1 {
+                return Ok(UserResponse::None);
             }
-            return Ok(UserResponse::Some(index));
+            Ok(UserResponse::Some(index))
         }
     }
 }
@@ -113,7 +113,7 @@ pub fn choice_no_limit(
     prompt: String,
     has_none: bool,
     has_all: bool,
-) -> Result<UserResponse<Vec<String>>, String> {
+) -> Result<UserResponse<Vec<String>>, GustError> {
     if has_all {
         choices.insert(0, "All".to_string());
     }
@@ -121,23 +121,20 @@ pub fn choice_no_limit(
         choices.push("None".to_string());
     }
 
-    let selected: Option<Vec<usize>> = MultiSelect::new()
-        .items(&choices)
-        .with_prompt(prompt)
-        .interact_on_opt(&console::Term::stderr())
-        .expect("Couldn't make a choice");
+    let selected: Option<Vec<usize>> =
+        prompt::with_prompter(|p| p.multi_select(&prompt, &choices))?;
 
     match selected {
-        None => Err("No items were selected".to_string()),
+        None => Err(GustError::Cancelled),
         Some(indexes) => {
-            let mut all_choices = Vec::<String>::new();
-            all_choices.reserve(indexes.len());
+            let mut all_choices = Vec::<String>::with_capacity(indexes.len());
             for i in indexes.into_iter() {
                 all_choices.push(utils::strip_colors(choices[i].to_string()).to_owned());
             }
-            if all_choices.contains(&choices[choices.len() - 1].to_string()) {
+            // Only the added 'None' and 'All' entries mean anything but themselves
+            if has_none && all_choices.contains(&choices[choices.len() - 1].to_string()) {
                 Ok(UserResponse::None)
-            } else if all_choices.contains(&choices[0].to_string()) {
+            } else if has_all && all_choices.contains(&choices[0].to_string()) {
                 Ok(UserResponse::All)
             } else {
                 Ok(UserResponse::Some(all_choices))
@@ -146,333 +143,1210 @@ pub fn choice_no_limit(
     }
 }
 
-pub fn git_status_short() -> Result<Option<String>, String> {
-    let git_status_cmd = Command::new("git")
-        .arg("status")
-        .arg("--short")
-        .stdout(Stdio::piped())
-        .spawn()
-        .expect("Couldn't call git add!");
-
-    let git_status = git_status_cmd
-        .wait_with_output()
-        .map_err(|e| e.to_string())?;
+/// A local or remote-tracking branch, with where it stands against its upstream
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BranchInfo {
+    /// `main`, or `remotes/origin/main` for a remote-tracking branch
+    pub name: String,
+    pub remote: bool,
+    /// Whether the branch is checked out
+    pub current: bool,
+    /// The branch a local branch tracks, e.g. `origin/main`
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    /// The subject of the last commit on the branch
+    pub subject: String,
+    /// The committer date of the last commit, `YYYY-MM-DD`
+    pub date: String,
+}
 
-    if git_status.status.success() {
-        let status_output = String::from_utf8_lossy(&git_status.stdout).to_string();
-        if status_output.len() == 1 || status_output.len() == 0 {
-            Ok(None)
+impl BranchInfo {
+    /// `* main [origin/main: ahead 1, behind 2] 2024-01-01 subject` for the pickers
+    pub fn label(&self) -> String {
+        let mut output = if self.current {
+            format!("* {}", self.name.bright_green())
+        } else if self.remote {
+            format!("  {}", self.name.bright_red())
         } else {
-            Ok(Some(status_output))
+            format!("  {}", self.name)
+        };
+
+        if let Some(upstream) = &self.upstream {
+            let mut counts = vec![];
+            if self.ahead > 0 {
+                counts.push(format!("ahead {}", self.ahead));
+            }
+            if self.behind > 0 {
+                counts.push(format!("behind {}", self.behind));
+            }
+            let tracking = if counts.is_empty() {
+                format!(" [{}]", upstream)
+            } else {
+                format!(" [{}: {}]", upstream, counts.join(", "))
+            };
+            output.push_str(&tracking.bright_blue().to_string());
         }
-    } else {
-        Err(String::from_utf8_lossy(&git_status.stderr).to_string())
+        output.push_str(&format!(" {} {}", self.date.bright_yellow(), self.subject));
+        output
     }
 }
 
-pub fn git_get_branches() -> Result<Option<Vec<String>>, String> {
-    let cmd = Command::new("git")
-        .arg("branch")
-        .arg("-a")
-        .stdout(Stdio::piped())
-        .spawn()
-        .expect("Couldn't call git add!");
-
-    let output = cmd.wait_with_output().map_err(|e| e.to_string())?;
-
-    if output.status.success() {
-        let branches: Vec<String> = String::from_utf8_lossy(&output.stdout)
-            .to_string()
-            .split("\n")
-            .filter_map(|i| {
-                if i.contains("HEAD") {
-                    None
-                } else {
-                    if i.len() > 2 {
-                        Some(i[2..].to_string())
-                    } else {
-                        None
-                    }
+/// Every local and remote-tracking branch, local ones first
+pub fn git_get_branches() -> Result<Option<Vec<BranchInfo>>, GustError> {
+    let output = GitCommand::new("for-each-ref")
+        .arg("--format=%(HEAD)%1f%(refname)%1f%(refname:short)%1f%(upstream:short)%1f%(upstream:track,nobracket)%1f%(committerdate:short)%1f%(contents:subject)")
+        .arg("refs/heads")
+        .arg("refs/remotes")
+        .capture_stdout()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        let branches: Vec<BranchInfo> = output
+            .stdout_string()
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.splitn(7, '\x1f').collect();
+                if fields.len() < 7 || fields[1].ends_with("/HEAD") {
+                    // `origin/HEAD` only points at another remote branch
+                    return None;
                 }
+                let remote = fields[1].starts_with("refs/remotes/");
+                let (ahead, behind) = parse_track(fields[4]);
+                Some(BranchInfo {
+                    name: if remote {
+                        format!("remotes/{}", fields[2])
+                    } else {
+                        fields[2].to_string()
+                    },
+                    remote,
+                    current: fields[0] == "*",
+                    upstream: Some(fields[3].to_string()).filter(|u| !u.is_empty()),
+                    ahead,
+                    behind,
+                    date: fields[5].to_string(),
+                    subject: fields[6].to_string(),
+                })
             })
             .collect();
 
-        Ok(Some(utils::strip_vec_colors(branches)))
+        Ok(Some(branches))
     } else {
-        Err(String::from_utf8_lossy(&output.stderr).to_string())
+        Err(output.into_error())
+    }
+}
+
+/// Read `ahead 1, behind 2` from `%(upstream:track,nobracket)`, `gone` or empty means neither
+fn parse_track(track: &str) -> (usize, usize) {
+    let mut ahead = 0;
+    let mut behind = 0;
+    for part in track.split(", ") {
+        match part.split_once(' ') {
+            Some(("ahead", count)) => ahead = count.parse().unwrap_or(0),
+            Some(("behind", count)) => behind = count.parse().unwrap_or(0),
+            _ => {}
+        }
     }
+    (ahead, behind)
 }
 
-pub fn git_fetch() -> Result<(), String> {
-    Command::new("git")
-        .arg("fetch")
-        .arg("--all")
-        .spawn()
-        .map_err(|e| e.to_string())?
-        .wait()
-        .map_err(|e| e.to_string())?;
+/// Rename the local branch `old_name`
+pub fn git_rename_branch(old_name: String, new_name: String) -> Result<(), GustError> {
+    let output = GitCommand::new("branch")
+        .arg("--move")
+        .arg(old_name)
+        .arg(new_name)
+        .run()?;
 
-    Ok(())
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
 }
 
-pub fn git_log(branch: Option<String>) -> Result<Option<String>, String> {
-    let mut args = vec![];
+/// Stop `branch` (the current branch if `None`) from tracking its upstream
+pub fn git_unset_upstream(branch: Option<String>) -> Result<(), GustError> {
+    let output = GitCommand::new("branch")
+        .arg("--unset-upstream")
+        .args(branch)
+        .run()?;
 
-    if branch.is_some() {
-        args.push(branch.unwrap());
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
     }
+}
 
-    let git_log_cmd = Command::new("git")
-        .arg("log")
-        .arg("--oneline")
-        .args(args)
-        .stdout(Stdio::piped())
-        .spawn()
-        .expect("Couldn't call git log --oneline!");
+/// Fetch from `remote`, or from every remote if `None`
+pub fn git_fetch(remote: Option<String>) -> Result<(), GustError> {
+    GitCommand::new("fetch")
+        .arg(remote.unwrap_or_else(|| "--all".to_string()))
+        .run()?;
 
-    let git_log = git_log_cmd.wait_with_output().map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    if git_log.status.success() {
-        let log_output = String::from_utf8_lossy(&git_log.stdout).to_string();
-        if log_output.len() == 1 || log_output.len() == 0 {
+pub fn git_log(branch: Option<String>) -> Result<Option<String>, GustError> {
+    let output = GitCommand::new("log")
+        .arg("--oneline")
+        .args(branch)
+        .capture_stdout()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        let log_output = output.stdout_string();
+        if log_output.len() == 1 || log_output.is_empty() {
             Ok(None)
         } else {
             Ok(Some(log_output))
         }
     } else {
-        Err(String::from_utf8_lossy(&git_log.stderr).to_string())
+        Err(output.into_error())
     }
 }
 
 pub fn git_create_branch(
     new_branch: String,
     starting_commit_hash: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), GustError> {
     let mut args = vec![];
 
-    if starting_commit_hash.is_some() {
-        args.push(starting_commit_hash.unwrap());
+    if let Some(hash) = starting_commit_hash {
+        args.push(hash);
     }
 
     args.push(new_branch);
 
-    let git_create_branch_cmd = Command::new("git")
-        .arg("branch")
-        .args(args)
-        .spawn()
-        .expect("Coun't create new branch");
+    let output = GitCommand::new("branch").args(args).run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
 
-    let output = git_create_branch_cmd
-        .wait_with_output()
-        .map_err(|e| e.to_string())?;
+pub fn git_pull() -> Result<(), GustError> {
+    let output = GitCommand::new("pull").run()?;
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    if !output.success() {
+        Err(output.into_error())
     } else {
         Ok(())
     }
 }
 
-pub fn git_pull() -> Result<(), String> {
-    let git_pull_cmd = Command::new("git")
-        .arg("pull")
-        .spawn()
-        .expect("Couldn't run `git pull`");
+pub fn git_push() -> Result<(), GustError> {
+    let output = GitCommand::new("push").run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
 
-    let output = git_pull_cmd.wait_with_output().map_err(|e| e.to_string())?;
+pub fn git_checkout<S: AsRef<OsStr>>(files: Vec<S>) -> Result<(), GustError> {
+    let output = GitCommand::new("checkout").args(files).run()?;
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    if !output.success() {
+        Err(output.into_error())
     } else {
         Ok(())
     }
 }
 
-pub fn git_push() -> Result<(), String> {
-    let git_push_cmd = Command::new("git")
-        .arg("push")
-        .spawn()
-        .expect("Couldn't run `git push`");
+pub fn git_add<S: AsRef<OsStr>>(input: Vec<S>) -> Result<(), GustError> {
+    let output = GitCommand::new("add").arg("--").args(input).run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        if !git::is_full_screen() {
+            println!("{}", "Files Staged!".bright_green());
+        }
+        Ok(())
+    }
+}
 
-    let output = git_push_cmd.wait_with_output().map_err(|e| e.to_string())?;
+pub fn git_reset<S: AsRef<OsStr>>(input: Vec<S>) -> Result<(), GustError> {
+    let output = GitCommand::new("reset").arg("--").args(input).run()?;
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    if !output.success() {
+        Err(output.into_error())
     } else {
         Ok(())
     }
 }
 
-pub fn git_checkout(files: Vec<String>) -> Result<(), String> {
-    let git_checkout_cmd = Command::new("git")
-        .arg("checkout")
-        .args(files)
-        .spawn()
-        .expect("Couldn't run `git checkout`");
+pub fn git_commit(passed_options: Option<Vec<String>>, config: &Config) -> Result<(), GustError> {
+    let mut options: Vec<String> = vec![];
 
-    let output = git_checkout_cmd
-        .wait_with_output()
-        .map_err(|e| e.to_string())?;
+    if let Some(passed) = passed_options {
+        options = passed;
+    }
+
+    if config.verbose_commit {
+        options.push("-v".to_string());
+    }
+
+    let output = GitCommand::new("commit").args(options).run()?;
+
+    if !output.success() {
+        return Err(output.into_error());
+    }
+    Ok(())
+}
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+/// The full message of `commit`, subject and body
+pub fn git_commit_message(commit: String) -> Result<String, GustError> {
+    let output = GitCommand::new("log")
+        .arg("-1")
+        .arg("--format=%B")
+        .arg(commit)
+        .capture()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        Ok(output.stdout_string().trim_end().to_string())
     } else {
-        Ok(())
+        Err(output.into_error())
     }
 }
 
-pub fn git_add(input: Vec<String>) -> Result<(), String> {
-    let git_add_cmd = Command::new("git")
-        .arg("add")
-        .args(input)
-        .spawn()
-        .expect("Couldn't run `git add`");
+/// The authors of the last 200 commits as `Name <email>`, most recent first, without the current user
+pub fn git_recent_authors() -> Result<Vec<String>, GustError> {
+    let output = GitCommand::new("log")
+        .arg("-n")
+        .arg("200")
+        .arg("--format=%aN <%aE>")
+        .capture()
+        .read_only()
+        .run()?;
+    if !output.success() {
+        return Err(output.into_error());
+    }
+
+    let me = GitCommand::new("config")
+        .arg("user.email")
+        .capture()
+        .read_only()
+        .run()?
+        .stdout_string();
+    let me = format!("<{}>", me.trim());
+
+    let mut authors = Vec::<String>::new();
+    for author in output.stdout_string().lines() {
+        if !author.ends_with(&me) && !authors.iter().any(|a| a == author) {
+            authors.push(author.to_string());
+        }
+    }
+    Ok(authors)
+}
 
-    let output = git_add_cmd.wait_with_output().map_err(|e| e.to_string())?;
+/// A commit from `git log` with its full message
+#[derive(Debug, Clone)]
+pub struct LogCommit {
+    pub hash: String,
+    /// The committer date, `YYYY-MM-DD`
+    pub date: String,
+    /// Whether the commit has more than one parent
+    pub merge: bool,
+    pub message: String,
+}
+
+/// Every commit in `range` (e.g. `v1.0.0..HEAD`), newest first
+pub fn git_log_commits(range: String) -> Result<Vec<LogCommit>, GustError> {
+    let output = GitCommand::new("log")
+        .arg("--format=%H%x1f%cs%x1f%P%x1f%B%x1e")
+        .arg(range)
+        .arg("--")
+        .capture()
+        .read_only()
+        .run()?;
+    if !output.success() {
+        return Err(output.into_error());
+    }
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    Ok(output
+        .stdout_string()
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(4, '\x1f');
+            Some(LogCommit {
+                hash: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                merge: fields.next()?.split_whitespace().count() > 1,
+                message: fields.next()?.trim_end().to_string(),
+            })
+        })
+        .collect())
+}
+
+/// The most recent tag reachable from `commit`, `None` if there isn't one
+pub fn git_latest_tag(commit: String) -> Result<Option<String>, GustError> {
+    let output = GitCommand::new("describe")
+        .arg("--tags")
+        .arg("--abbrev=0")
+        .arg(commit)
+        .capture()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        Ok(Some(output.stdout_string().trim().to_string()))
+    } else if output.stderr.contains("No names found")
+        || output.stderr.contains("No tags can describe")
+    {
+        Ok(None)
+    } else {
+        Err(output.into_error())
+    }
+}
+
+/// The value of a git config variable, `None` if it isn't set
+pub fn git_config_get(key: &str) -> Result<Option<String>, GustError> {
+    let output = GitCommand::new("config")
+        .arg("--get")
+        .arg(key)
+        .capture()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        Ok(Some(
+            output.stdout_string().trim_end_matches('\n').to_string(),
+        ))
+    } else if output.code == Some(1) {
+        Ok(None)
+    } else {
+        Err(output.into_error())
+    }
+}
+
+/// The names of every tag in the repository
+pub fn git_tags() -> Result<Vec<String>, GustError> {
+    let output = GitCommand::new("tag")
+        .arg("--list")
+        .arg("--format=%(refname:short)")
+        .capture()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        Ok(output
+            .stdout_string()
+            .lines()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect())
+    } else {
+        Err(output.into_error())
+    }
+}
+
+pub struct TagInfo {
+    pub name: String,
+    /// The short hash of the commit the tag points to
+    pub commit: String,
+    /// When an annotated tag was made, or the commit date of a lightweight tag, `YYYY-MM-DD`
+    pub date: String,
+    /// The subject of an annotated tag's message, or of the tagged commit
+    pub subject: String,
+    pub annotated: bool,
+}
+
+/// Every tag, newest first
+pub fn git_tag_list() -> Result<Vec<TagInfo>, GustError> {
+    let output = GitCommand::new("for-each-ref")
+        .arg("--sort=-creatordate")
+        .arg("--format=%(refname:short)%1f%(objecttype)%1f%(objectname:short)%1f%(*objectname:short)%1f%(creatordate:short)%1f%(contents:subject)")
+        .arg("refs/tags")
+        .capture()
+        .read_only()
+        .run()?;
+    if !output.success() {
+        return Err(output.into_error());
+    }
+
+    Ok(output
+        .stdout_string()
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(6, '\x1f').collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let annotated = fields[1] == "tag";
+            Some(TagInfo {
+                name: fields[0].to_string(),
+                // An annotated tag is an object of its own, `*objectname` is the commit it wraps
+                commit: if annotated { fields[3] } else { fields[2] }.to_string(),
+                date: fields[4].to_string(),
+                subject: fields[5].to_string(),
+                annotated,
+            })
+        })
+        .collect())
+}
+
+/// Delete `name` locally, or from `remote` when one is given
+pub fn git_delete_tag(name: String, remote: Option<String>) -> Result<(), GustError> {
+    let command = match remote {
+        Some(remote) => GitCommand::new("push")
+            .arg(remote)
+            .arg("--delete")
+            .arg(format!("refs/tags/{}", name)),
+        None => GitCommand::new("tag").arg("--delete").arg(name),
+    };
+
+    let output = command.run()?;
+    if !output.success() {
+        Err(output.into_error())
     } else {
-        println!("{}", "Files Staged!".bright_green());
         Ok(())
     }
 }
 
-pub fn git_reset(input: Vec<String>) -> Result<(), String> {
-    let git_reset_cmd = Command::new("git")
-        .arg("reset")
-        .args(input)
-        .spawn()
-        .expect("Couldn't run `git reset`");
+/// Push `tag` to `remote`, or every tag when it's `None`
+pub fn git_push_tags(remote: String, tag: Option<String>) -> Result<(), GustError> {
+    let command = GitCommand::new("push").arg(remote);
+    let command = match tag {
+        Some(tag) => command.arg(format!("refs/tags/{}", tag)),
+        None => command.arg("--tags"),
+    };
+
+    let output = command.run()?;
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
 
-    let output = git_reset_cmd
-        .wait_with_output()
-        .map_err(|e| e.to_string())?;
+/// Tag `commit` (`HEAD` if `None`), the tag is annotated when it has a `message`
+pub fn git_create_tag(
+    name: String,
+    commit: Option<String>,
+    message: Option<String>,
+) -> Result<(), GustError> {
+    let mut command = GitCommand::new("tag");
+    if let Some(message) = message {
+        command = command
+            .arg("--annotate")
+            .arg("--cleanup=whitespace")
+            .arg("-m")
+            .arg(message);
+    }
+    command = command.arg(name);
+    if let Some(commit) = commit {
+        command = command.arg(commit);
+    }
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    let output = command.run()?;
+    if !output.success() {
+        Err(output.into_error())
     } else {
         Ok(())
     }
 }
 
-pub fn git_commit(passed_options: Option<Vec<String>>, config: &Config) -> Result<(), String> {
-    let git_commit_cmd;
-    let mut options: Vec<String> = vec![];
+/// The remote-tracking branches that already contain `commit`
+pub fn git_remote_branches_containing(commit: String) -> Result<Vec<String>, GustError> {
+    let output = GitCommand::new("branch")
+        .arg("--remotes")
+        .arg("--format=%(refname:short)")
+        .arg("--contains")
+        .arg(commit)
+        .capture()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        Ok(output
+            .stdout_string()
+            .lines()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect())
+    } else {
+        Err(output.into_error())
+    }
+}
 
-    if passed_options.is_some() {
-        options = passed_options.unwrap();
+/// Run `git rebase`, `sequence_editor` replaces the editor git opens on the todo list
+pub fn git_rebase(args: Vec<String>, sequence_editor: Option<String>) -> Result<(), GustError> {
+    let mut command = GitCommand::new("rebase").args(args);
+    if let Some(editor) = sequence_editor {
+        command = command.env("GIT_SEQUENCE_EDITOR", editor);
     }
+    let output = command.run()?;
 
-    if config.verbose_commit {
-        options.push("-v".to_string());
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
     }
+}
 
-    // for debugging
-    let opts = options.join(" ");
+/// Run `git merge` with `args`, the branch to merge last
+pub fn git_merge(args: Vec<String>) -> Result<(), GustError> {
+    let output = GitCommand::new("merge").args(args).run()?;
 
-    git_commit_cmd = Command::new("git")
-        .arg("commit")
-        .args(options)
-        .spawn()
-        .expect(format!("Couldn't call `git commit {}`!", opts).as_str());
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
 
-    let output = git_commit_cmd
-        .wait_with_output()
-        .map_err(|e| e.to_string())?;
+/// Throw away a merge that has no MERGE_HEAD to abort, like a conflicted `merge --squash`
+pub fn git_reset_merge() -> Result<(), GustError> {
+    let output = GitCommand::new("reset").arg("--merge").run()?;
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
     }
-    Ok(())
 }
 
-pub fn git_revert(commit_hash: String) -> Result<(), String> {
-    let git_revert_cmd = Command::new("git")
-        .arg("revert")
-        .arg(commit_hash)
-        .spawn()
-        .expect("Couldn't run `git reset`");
+/// Apply `commits` on top of HEAD in the given order, `record_origin` adds a
+/// "(cherry picked from commit ...)" line to each message
+pub fn git_cherry_pick(commits: Vec<String>, record_origin: bool) -> Result<(), GustError> {
+    let mut command = GitCommand::new("cherry-pick");
+    if record_origin {
+        command = command.arg("-x");
+    }
+    let output = command.args(commits).run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
 
-    let output = git_revert_cmd
-        .wait_with_output()
-        .map_err(|e| e.to_string())?;
+pub fn git_revert(commit_hash: String) -> Result<(), GustError> {
+    let output = GitCommand::new("revert").arg(commit_hash).run()?;
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    if !output.success() {
+        Err(output.into_error())
     } else {
         Ok(())
     }
 }
 
-pub fn git_ls_tree() -> Result<Option<String>, String> {
-    let git_ls_tree_cmd = Command::new("git")
-        .arg("ls-tree")
+pub fn git_ls_tree() -> Result<Option<String>, GustError> {
+    let output = GitCommand::new("ls-tree")
         .arg("--full-tree")
         .arg("-r")
         .arg("--name-only")
         .arg("HEAD")
-        .stdout(Stdio::piped())
-        .spawn()
-        .expect("Couldn't run `git ls-tree`");
+        .capture_stdout()
+        .read_only()
+        .run()?;
 
-    let git_output = git_ls_tree_cmd
-        .wait_with_output()
-        .map_err(|e| e.to_string())?;
-
-    if git_output.status.success() {
-        let output = String::from_utf8_lossy(&git_output.stdout).to_string();
-        if output.len() <= 1 {
+    if output.success() {
+        let files = output.stdout_string();
+        if files.len() <= 1 {
             Ok(None)
         } else {
-            Ok(Some(output))
+            Ok(Some(files))
         }
     } else {
-        Err(String::from_utf8_lossy(&git_output.stderr).to_string())
+        Err(output.into_error())
     }
 }
 
-pub fn git_rm(files: Vec<String>, as_cached: bool) -> Result<(), String> {
+pub fn git_rm<S: AsRef<OsStr>>(files: Vec<S>, as_cached: bool) -> Result<(), GustError> {
     let mut args = vec![];
 
     if as_cached {
         args.push(String::from("--cached"));
     }
 
-    args.append(&mut files.clone());
-
-    let git_rm_cmd = Command::new("git")
-        .arg("rm")
+    let output = GitCommand::new("rm")
         .args(args)
-        .spawn()
-        .expect("Couldn't run `git add`");
+        .arg("--")
+        .args(files)
+        .run()?;
 
-    let output = git_rm_cmd.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        if !git::is_full_screen() {
+            println!("{}", "Files no longer tracking!".bright_green());
+        }
+        Ok(())
+    }
+}
+
+pub fn git_branch_delete(branch: String) -> Result<(), GustError> {
+    let output = GitCommand::new("branch").arg("-D").arg(branch).run()?;
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    if !output.success() {
+        Err(output.into_error())
     } else {
         println!("{}", "Files no longer tracking!".bright_green());
         Ok(())
     }
 }
 
-pub fn git_branch_delete(branch: String) -> Result<(), String> {
-    let git_branch_cmd = Command::new("git")
+pub fn git_diff<S: AsRef<OsStr>>(
+    options: Vec<String>,
+    paths: Vec<S>,
+) -> Result<Option<String>, GustError> {
+    let output = GitCommand::new("diff")
+        .args(options)
+        .arg("--")
+        .args(paths)
+        .capture()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        let diff_output = output.stdout_string();
+        if diff_output.trim().is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(diff_output))
+        }
+    } else {
+        Err(output.into_error())
+    }
+}
+
+pub fn git_apply(patch: String, options: Vec<String>) -> Result<(), GustError> {
+    let output = GitCommand::new("apply")
+        .args(options)
+        .arg("-")
+        .stdin(patch)
+        .capture()
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn git_stash_push(
+    message: Option<String>,
+    include_untracked: bool,
+    keep_index: bool,
+) -> Result<(), GustError> {
+    let mut args = vec![];
+
+    if include_untracked {
+        args.push("--include-untracked".to_string());
+    }
+    if keep_index {
+        args.push("--keep-index".to_string());
+    }
+    if let Some(message) = message {
+        args.push("--message".to_string());
+        args.push(message);
+    }
+
+    let output = GitCommand::new("stash").arg("push").args(args).run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        println!("{}", "Changes stashed!".bright_green());
+        Ok(())
+    }
+}
+
+/// Returns every stash as `stash@{n}: <description>`, newest first
+pub fn git_stash_list() -> Result<Option<Vec<String>>, GustError> {
+    let output = GitCommand::new("stash")
+        .arg("list")
+        .capture_stdout()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        let stashes: Vec<String> = output
+            .stdout_string()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        if stashes.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(stashes))
+        }
+    } else {
+        Err(output.into_error())
+    }
+}
+
+pub fn git_stash_show(stash: String) -> Result<String, GustError> {
+    let output = GitCommand::new("stash")
+        .arg("show")
+        .arg("--stat")
+        .arg(stash)
+        .capture()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        Ok(output.stdout_string())
+    } else {
+        Err(output.into_error())
+    }
+}
+
+/// A commit's message followed by its diffstat and patch
+pub fn git_show(commit: String) -> Result<String, GustError> {
+    let output = GitCommand::new("show")
+        .arg("--stat")
+        .arg("--patch")
+        .arg(commit)
+        .capture()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        Ok(output.stdout_string())
+    } else {
+        Err(output.into_error())
+    }
+}
+
+/// Apply a stash to the working tree, removing it from the list when `pop` is set
+pub fn git_stash_apply(stash: String, pop: bool) -> Result<(), GustError> {
+    let output = GitCommand::new("stash")
+        .arg(if pop { "pop" } else { "apply" })
+        .arg(stash)
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn git_stash_drop(stash: String) -> Result<(), GustError> {
+    let output = GitCommand::new("stash").arg("drop").arg(stash).run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn git_stash_branch(branch: String, stash: String) -> Result<(), GustError> {
+    let output = GitCommand::new("stash")
         .arg("branch")
-        .arg("-D")
         .arg(branch)
-        .spawn()
-        .expect("Couldn't run `git branch -D' for that branch");
+        .arg(stash)
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Show `text` through `$PAGER` (or `less -R`), falling back to printing it
+pub fn page_output(text: String) -> Result<(), GustError> {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+    let mut pager_parts = pager.split_whitespace();
+
+    let pager_cmd = match pager_parts.next() {
+        Some(program) => Command::new(program)
+            .args(pager_parts)
+            .stdin(Stdio::piped())
+            .spawn(),
+        None => {
+            println!("{}", text);
+            return Ok(());
+        }
+    };
+
+    match pager_cmd {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The pager closing early (user quit) isn't an error
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            child.wait()?;
+        }
+        Err(_) => println!("{}", text),
+    }
+    Ok(())
+}
 
-    let output = git_branch_cmd
-        .wait_with_output()
-        .map_err(|e| e.to_string())?;
+/// Run `git rev-parse <option>`, returning its trimmed output
+pub fn git_rev_parse(option: &str) -> Result<String, GustError> {
+    let output = GitCommand::new("rev-parse")
+        .arg(option)
+        .capture()
+        .read_only()
+        .run()?;
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    if output.success() {
+        Ok(output.stdout_string().trim_end().to_string())
+    } else {
+        Err(output.into_error())
+    }
+}
+
+/// Run `git <operation> --<action>`, e.g. `git rebase --continue`
+pub fn git_path(path: &str) -> Result<PathBuf, GustError> {
+    let output = GitCommand::new("rev-parse")
+        .arg("--git-path")
+        .arg(path)
+        .capture()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        Ok(PathBuf::from(output.stdout_string().trim_end()))
+    } else {
+        Err(output.into_error())
+    }
+}
+
+pub fn git_operation(operation: Operation, action: &str) -> Result<(), GustError> {
+    let output = GitCommand::new(operation.command())
+        .arg(format!("--{}", action))
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
     } else {
-        println!("{}", "Files no longer tracking!".bright_green());
         Ok(())
     }
 }
+
+#[derive(Debug, Clone, Default)]
+pub struct Remote {
+    pub name: String,
+    pub fetch_url: String,
+    pub push_url: String,
+}
+
+pub fn git_remotes() -> Result<Option<Vec<Remote>>, GustError> {
+    let output = GitCommand::new("remote")
+        .arg("-v")
+        .capture()
+        .read_only()
+        .run()?;
+
+    if !output.success() {
+        return Err(output.into_error());
+    }
+
+    // Every remote is listed twice, as `<name>\t<url> (fetch)` and `<name>\t<url> (push)`
+    let mut remotes = Vec::<Remote>::new();
+    for line in output.stdout_string().lines() {
+        let (name, rest) = match line.split_once('\t') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let (url, kind) = rest.rsplit_once(' ').unwrap_or((rest, ""));
+
+        let index = match remotes.iter().position(|r| r.name == name) {
+            Some(index) => index,
+            None => {
+                remotes.push(Remote {
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+                remotes.len() - 1
+            }
+        };
+        if kind == "(push)" {
+            remotes[index].push_url = url.to_string();
+        } else {
+            remotes[index].fetch_url = url.to_string();
+        }
+    }
+
+    if remotes.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(remotes))
+    }
+}
+
+pub fn git_remote_add(name: String, url: String) -> Result<(), GustError> {
+    let output = GitCommand::new("remote")
+        .arg("add")
+        .arg(name)
+        .arg(url)
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        println!("{}", "Remote added!".bright_green());
+        Ok(())
+    }
+}
+
+pub fn git_remote_rename(old_name: String, new_name: String) -> Result<(), GustError> {
+    let output = GitCommand::new("remote")
+        .arg("rename")
+        .arg(old_name)
+        .arg(new_name)
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        println!("{}", "Remote renamed!".bright_green());
+        Ok(())
+    }
+}
+
+pub fn git_remote_remove(name: String) -> Result<(), GustError> {
+    let output = GitCommand::new("remote").arg("remove").arg(name).run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        println!("{}", "Remote removed!".bright_green());
+        Ok(())
+    }
+}
+
+/// Change the url of a remote, only the url used for pushing if `push_only` is set
+pub fn git_remote_set_url(name: String, url: String, push_only: bool) -> Result<(), GustError> {
+    let mut args = vec![];
+
+    if push_only {
+        args.push("--push".to_string());
+    }
+
+    let output = GitCommand::new("remote")
+        .arg("set-url")
+        .args(args)
+        .arg(name)
+        .arg(url)
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        println!("{}", "Remote url changed!".bright_green());
+        Ok(())
+    }
+}
+
+/// Delete remote-tracking branches whose branch no longer exists on the remote
+pub fn git_remote_prune(name: String) -> Result<(), GustError> {
+    let output = GitCommand::new("remote").arg("prune").arg(name).run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Make `branch` (or the current branch) track `upstream`, e.g. `origin/main`
+pub fn git_set_upstream(branch: Option<String>, upstream: String) -> Result<(), GustError> {
+    let output = GitCommand::new("branch")
+        .arg(format!("--set-upstream-to={}", upstream))
+        .args(branch)
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Push `branch` to `remote`, setting it as the branch's upstream
+pub fn git_push_set_upstream(remote: String, branch: String) -> Result<(), GustError> {
+    let output = GitCommand::new("push")
+        .arg("--set-upstream")
+        .arg(remote)
+        .arg(branch)
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether the current directory is inside a git work tree
+pub fn git_is_repo() -> Result<bool, GustError> {
+    let output = GitCommand::new("rev-parse")
+        .arg("--is-inside-work-tree")
+        .capture()
+        .read_only()
+        .run()?;
+
+    Ok(output.success() && output.stdout_string().trim() == "true")
+}
+
+pub fn git_init(directory: PathBuf, initial_branch: String) -> Result<(), GustError> {
+    let output = GitCommand::new("init")
+        .arg(format!("--initial-branch={}", initial_branch))
+        .arg(directory)
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Clone `source` (a url or local path) into `directory`.
+/// `depth` makes a shallow clone, `branch` checks out that branch instead of the default.
+pub fn git_clone(
+    source: String,
+    directory: PathBuf,
+    depth: Option<u32>,
+    branch: Option<String>,
+) -> Result<(), GustError> {
+    let mut args = vec![];
+
+    if let Some(depth) = depth {
+        args.push(format!("--depth={}", depth));
+        // Local clones ignore --depth unless they go through the file transport
+        if std::path::Path::new(&source).exists() {
+            args.push("--no-local".to_string());
+        }
+    }
+    if let Some(branch) = branch {
+        args.push("--branch".to_string());
+        args.push(branch);
+    }
+
+    let output = GitCommand::new("clone")
+        .args(args)
+        .arg("--")
+        .arg(source)
+        .arg(directory)
+        .run()?;
+
+    if !output.success() {
+        Err(output.into_error())
+    } else {
+        println!("{}", "Repository cloned!".bright_green());
+        Ok(())
+    }
+}
+
+/// Explain which ignore file and pattern ignore `path`, `None` if it isn't ignored
+pub fn git_check_ignore(path: String) -> Result<Option<IgnoreMatch>, GustError> {
+    let output = GitCommand::new("check-ignore")
+        .arg("--verbose")
+        .arg("--stdin")
+        .arg("-z")
+        .stdin(format!("{}\0", path))
+        .capture()
+        .read_only()
+        .run()?;
+
+    // Exit code 1 means nothing matched
+    match output.code {
+        Some(0) => Ok(IgnoreMatch::parse(&output.stdout_string())),
+        Some(1) => Ok(None),
+        _ => Err(output.into_error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+
+    fn plain_label(branch: &BranchInfo) -> String {
+        utils::strip_colors(branch.label())
+    }
+
+    #[test]
+    fn parses_the_upstream_track() {
+        assert_eq!(parse_track("ahead 1, behind 2"), (1, 2));
+        assert_eq!(parse_track("ahead 3"), (3, 0));
+        assert_eq!(parse_track("behind 4"), (0, 4));
+        assert_eq!(parse_track("gone"), (0, 0));
+        assert_eq!(parse_track(""), (0, 0));
+    }
+
+    #[test]
+    fn labels_branches_with_where_they_stand() {
+        let mut branch = BranchInfo {
+            name: "main".to_string(),
+            current: true,
+            upstream: Some("origin/main".to_string()),
+            ahead: 1,
+            behind: 2,
+            date: "2024-01-01".to_string(),
+            subject: "Add a.txt".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            plain_label(&branch),
+            "* main [origin/main: ahead 1, behind 2] 2024-01-01 Add a.txt"
+        );
+
+        branch.current = false;
+        branch.behind = 0;
+        assert_eq!(
+            plain_label(&branch),
+            "  main [origin/main: ahead 1] 2024-01-01 Add a.txt"
+        );
+
+        // In step with the upstream, or without one
+        branch.ahead = 0;
+        assert_eq!(
+            plain_label(&branch),
+            "  main [origin/main] 2024-01-01 Add a.txt"
+        );
+        branch.upstream = None;
+        assert_eq!(plain_label(&branch), "  main 2024-01-01 Add a.txt");
+    }
+
+    #[test]
+    fn lists_branches_ahead_and_behind_their_upstream() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        let remote = repo.bare_clone("remote.git");
+        repo.git(&["remote", "add", "origin", remote.to_str().unwrap()]);
+        repo.git(&["fetch", "--quiet", "origin"]);
+        repo.git(&["branch", "--quiet", "--set-upstream-to=origin/main"]);
+        repo.git(&["branch", "topic"]);
+
+        // One commit only here, and one only on the remote
+        repo.commit_file("b.txt", "b\n", "Add b.txt");
+        repo.git(&["switch", "--quiet", "topic"]);
+        repo.commit_file("c.txt", "c\n", "Add c.txt");
+        repo.git(&["update-ref", "refs/remotes/origin/main", "topic"]);
+        repo.git(&["switch", "--quiet", "main"]);
+
+        let branches = git_get_branches().unwrap().unwrap();
+        let names: Vec<&str> = branches.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["main", "topic", "remotes/origin/main"]);
+
+        let main = &branches[0];
+        assert!(main.current && !main.remote);
+        assert_eq!(main.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((main.ahead, main.behind), (1, 1));
+        assert_eq!(main.subject, "Add b.txt");
+
+        assert_eq!(branches[1].upstream, None);
+        assert!(branches[2].remote && !branches[2].current);
+    }
+}
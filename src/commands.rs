@@ -1,3 +1,4 @@
+use crate::error::GustError;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
@@ -13,24 +14,14 @@ pub enum BasicCommands {
 }
 
 impl BasicCommands {
-    pub fn from_string(input: String) -> Result<Self, String> {
+    pub fn from_string(input: String) -> Result<Self, GustError> {
         match input.to_lowercase().as_str() {
             "add" => Ok(BasicCommands::Add),
             "reset" => Ok(BasicCommands::Reset),
             "commit" => Ok(BasicCommands::Commit),
             "advanced options" => Ok(BasicCommands::AdvancedOptions),
             "quit" => Ok(BasicCommands::Quit),
-            _ => Err(format!("{} {}", "Unrecognized command: {}".red(), input)),
-        }
-    }
-
-    pub fn to_string(&self) -> String {
-        match self {
-            BasicCommands::Add => String::from("Add"),
-            BasicCommands::Reset => String::from("Reset"),
-            BasicCommands::Commit => String::from("Commit"),
-            BasicCommands::AdvancedOptions => String::from("Advanced Options"),
-            BasicCommands::Quit => String::from("Quit"),
+            _ => Err(format!("{} {}", "Unrecognized command: {}".red(), input).into()),
         }
     }
 
@@ -39,7 +30,20 @@ impl BasicCommands {
         for item in BasicCommands::iter() {
             output.push(item.to_string())
         }
-        return output;
+        output
+    }
+}
+
+impl std::fmt::Display for BasicCommands {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BasicCommands::Add => "Add",
+            BasicCommands::Reset => "Reset",
+            BasicCommands::Commit => "Commit",
+            BasicCommands::AdvancedOptions => "Advanced Options",
+            BasicCommands::Quit => "Quit",
+        };
+        write!(f, "{}", name)
     }
 }
 
@@ -48,41 +52,43 @@ pub enum Commands {
     Add,
     Reset,
     Commit,
+    Diff,
     UndoCommit,
     Branches,
+    Conflicts,
+    Ignore,
     Push,
     Pull,
+    Remotes,
     Remove,
+    Stash,
+    Rebase,
+    Tags,
+    Release,
     Quit,
 }
 
 impl Commands {
-    pub fn from_string(input: String) -> Result<Self, String> {
+    pub fn from_string(input: String) -> Result<Self, GustError> {
         match input.to_lowercase().as_str() {
             "add" => Ok(Commands::Add),
             "reset" => Ok(Commands::Reset),
             "commit" => Ok(Commands::Commit),
+            "diff" => Ok(Commands::Diff),
             "undo_commit" => Ok(Commands::UndoCommit),
             "branches" => Ok(Commands::Branches),
+            "conflicts" => Ok(Commands::Conflicts),
+            "ignore" => Ok(Commands::Ignore),
             "push" => Ok(Commands::Push),
             "pull" => Ok(Commands::Pull),
+            "remotes" => Ok(Commands::Remotes),
             "remove" => Ok(Commands::Remove),
+            "stash" => Ok(Commands::Stash),
+            "rebase" => Ok(Commands::Rebase),
+            "tags" => Ok(Commands::Tags),
+            "release" => Ok(Commands::Release),
             "quit" => Ok(Commands::Quit),
-            _ => Err(format!("{} {}", "Unrecognized command: {}".red(), input)),
-        }
-    }
-
-    pub fn to_string(&self) -> String {
-        match self {
-            Commands::Add => String::from("Add"),
-            Commands::Reset => String::from("Reset"),
-            Commands::Commit => String::from("Commit"),
-            Commands::UndoCommit => String::from("Undo_commit"),
-            Commands::Branches => String::from("Branches"),
-            Commands::Push => String::from("Push"),
-            Commands::Pull => String::from("Pull"),
-            Commands::Remove => String::from("Remove"),
-            Commands::Quit => String::from("Quit"),
+            _ => Err(format!("{} {}", "Unrecognized command: {}".red(), input).into()),
         }
     }
 
@@ -91,6 +97,31 @@ impl Commands {
         for item in Commands::iter() {
             output.push(item.to_string())
         }
-        return output;
+        output
+    }
+}
+
+impl std::fmt::Display for Commands {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Commands::Add => "Add",
+            Commands::Reset => "Reset",
+            Commands::Commit => "Commit",
+            Commands::Diff => "Diff",
+            Commands::UndoCommit => "Undo_commit",
+            Commands::Branches => "Branches",
+            Commands::Conflicts => "Conflicts",
+            Commands::Ignore => "Ignore",
+            Commands::Push => "Push",
+            Commands::Pull => "Pull",
+            Commands::Remotes => "Remotes",
+            Commands::Remove => "Remove",
+            Commands::Stash => "Stash",
+            Commands::Rebase => "Rebase",
+            Commands::Tags => "Tags",
+            Commands::Release => "Release",
+            Commands::Quit => "Quit",
+        };
+        write!(f, "{}", name)
     }
 }
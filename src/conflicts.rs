@@ -0,0 +1,268 @@
+use crate::error::GustError;
+use colored::Colorize;
+
+/// How a single conflict in a file should be resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    OursThenTheirs,
+    TheirsThenOurs,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConflictHunk {
+    /// Everything after `<<<<<<<` on the opening marker, usually `HEAD`
+    pub ours_label: String,
+    pub ours: Vec<String>,
+    /// Only present with `merge.conflictStyle = diff3` or `zdiff3`
+    pub base: Option<Vec<String>>,
+    pub theirs_label: String,
+    pub theirs: Vec<String>,
+    pub resolution: Option<Resolution>,
+
+    /// The original marker lines, needed to write an unresolved conflict back out
+    markers: Vec<String>,
+}
+
+impl ConflictHunk {
+    pub fn print(&self) {
+        println!(
+            "{}",
+            format!("<<<<<<< ours ({})", self.ours_label.trim()).bright_blue()
+        );
+        for line in &self.ours {
+            print!("{}", line.green());
+        }
+        if let Some(base) = &self.base {
+            println!("{}", "||||||| base".dimmed());
+            for line in base {
+                print!("{}", line.dimmed());
+            }
+        }
+        println!("{}", "=======".bright_blue());
+        for line in &self.theirs {
+            print!("{}", line.red());
+        }
+        println!(
+            "{}",
+            format!(">>>>>>> theirs ({})", self.theirs_label.trim()).bright_blue()
+        );
+    }
+
+    fn resolved_lines(&self) -> Vec<String> {
+        match self.resolution {
+            Some(Resolution::Ours) => self.ours.clone(),
+            Some(Resolution::Theirs) => self.theirs.clone(),
+            Some(Resolution::OursThenTheirs) => [self.ours.clone(), self.theirs.clone()].concat(),
+            Some(Resolution::TheirsThenOurs) => [self.theirs.clone(), self.ours.clone()].concat(),
+            None => {
+                let mut lines = vec![self.markers[0].clone()];
+                lines.extend(self.ours.iter().cloned());
+                if let Some(base) = &self.base {
+                    lines.push(self.markers[1].clone());
+                    lines.extend(base.iter().cloned());
+                }
+                lines.push(self.markers[self.markers.len() - 2].clone());
+                lines.extend(self.theirs.iter().cloned());
+                lines.push(self.markers[self.markers.len() - 1].clone());
+                lines
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Segment {
+    Text(Vec<String>),
+    Conflict(ConflictHunk),
+}
+
+/// Split the contents of a conflicted file into plain text and conflicts.
+/// Lines keep their line endings so the file can be written back unchanged.
+pub fn parse_conflicts(content: &str) -> Result<Vec<Segment>, GustError> {
+    enum Part {
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    let mut segments = Vec::<Segment>::new();
+    let mut text = Vec::<String>::new();
+    let mut current: Option<(ConflictHunk, Part)> = None;
+
+    for line in content.split_inclusive('\n') {
+        let marker = line.trim_end_matches(['\n', '\r']);
+
+        match current.take() {
+            None => {
+                if let Some(label) = marker.strip_prefix("<<<<<<<") {
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    current = Some((
+                        ConflictHunk {
+                            ours_label: label.to_string(),
+                            ours: vec![],
+                            base: None,
+                            theirs_label: String::new(),
+                            theirs: vec![],
+                            resolution: None,
+                            markers: vec![line.to_string()],
+                        },
+                        Part::Ours,
+                    ));
+                } else {
+                    text.push(line.to_string());
+                }
+            }
+            Some((mut hunk, part)) => {
+                if marker.starts_with("|||||||") && matches!(part, Part::Ours) {
+                    hunk.markers.push(line.to_string());
+                    hunk.base = Some(vec![]);
+                    current = Some((hunk, Part::Base));
+                } else if marker == "=======" && !matches!(part, Part::Theirs) {
+                    hunk.markers.push(line.to_string());
+                    current = Some((hunk, Part::Theirs));
+                } else if let (Some(label), Part::Theirs) = (marker.strip_prefix(">>>>>>>"), &part)
+                {
+                    hunk.theirs_label = label.to_string();
+                    hunk.markers.push(line.to_string());
+                    segments.push(Segment::Conflict(hunk));
+                } else {
+                    match part {
+                        Part::Ours => hunk.ours.push(line.to_string()),
+                        Part::Base => hunk
+                            .base
+                            .get_or_insert_with(Vec::new)
+                            .push(line.to_string()),
+                        Part::Theirs => hunk.theirs.push(line.to_string()),
+                    }
+                    current = Some((hunk, part));
+                }
+            }
+        }
+    }
+
+    if current.is_some() {
+        return Err("A conflict in the file is missing its closing marker".into());
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+/// Join the segments back into file contents, keeping the markers of unresolved conflicts
+pub fn render_segments(segments: &[Segment]) -> String {
+    let mut output = String::new();
+    for segment in segments {
+        let lines = match segment {
+            Segment::Text(lines) => lines.clone(),
+            Segment::Conflict(hunk) => hunk.resolved_lines(),
+        };
+        for line in lines {
+            output.push_str(&line);
+        }
+    }
+    output
+}
+
+pub fn has_conflict_markers(content: &str) -> bool {
+    parse_conflicts(content)
+        .map(|segments| segments.iter().any(|s| matches!(s, Segment::Conflict(_))))
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MERGE: &str =
+        "start\n<<<<<<< HEAD\nours 1\nours 2\n=======\ntheirs\n>>>>>>> feature\nend\n";
+    const DIFF3: &str =
+        "<<<<<<< HEAD\r\nours\r\n||||||| base\r\nbase\r\n=======\r\ntheirs\r\n>>>>>>> feature\r\n";
+
+    fn conflicts(segments: &mut [Segment]) -> Vec<&mut ConflictHunk> {
+        segments
+            .iter_mut()
+            .filter_map(|s| match s {
+                Segment::Conflict(hunk) => Some(hunk),
+                Segment::Text(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_text_and_conflicts() {
+        let mut segments = parse_conflicts(MERGE).unwrap();
+        assert_eq!(segments.len(), 3);
+        assert!(matches!(&segments[0], Segment::Text(lines) if lines == &["start\n"]));
+        assert!(matches!(&segments[2], Segment::Text(lines) if lines == &["end\n"]));
+
+        let hunk = &conflicts(&mut segments)[0];
+        assert_eq!(hunk.ours_label.trim(), "HEAD");
+        assert_eq!(hunk.ours, vec!["ours 1\n", "ours 2\n"]);
+        assert_eq!(hunk.base, None);
+        assert_eq!(hunk.theirs_label.trim(), "feature");
+        assert_eq!(hunk.theirs, vec!["theirs\n"]);
+    }
+
+    #[test]
+    fn reads_the_base_of_diff3_conflicts() {
+        let mut segments = parse_conflicts(DIFF3).unwrap();
+        let hunk = &conflicts(&mut segments)[0];
+        assert_eq!(hunk.ours, vec!["ours\r\n"]);
+        assert_eq!(hunk.base, Some(vec!["base\r\n".to_string()]));
+        assert_eq!(hunk.theirs, vec!["theirs\r\n"]);
+    }
+
+    #[test]
+    fn unresolved_conflicts_are_written_back_unchanged() {
+        for content in [MERGE, DIFF3, "no conflicts\nat all", ""] {
+            assert_eq!(render_segments(&parse_conflicts(content).unwrap()), content);
+        }
+        assert!(has_conflict_markers(MERGE));
+        assert!(!has_conflict_markers("no conflicts\n"));
+    }
+
+    #[test]
+    fn renders_each_resolution() {
+        for (resolution, middle) in [
+            (Resolution::Ours, "ours 1\nours 2\n"),
+            (Resolution::Theirs, "theirs\n"),
+            (Resolution::OursThenTheirs, "ours 1\nours 2\ntheirs\n"),
+            (Resolution::TheirsThenOurs, "theirs\nours 1\nours 2\n"),
+        ] {
+            let mut segments = parse_conflicts(MERGE).unwrap();
+            conflicts(&mut segments)[0].resolution = Some(resolution);
+            assert_eq!(
+                render_segments(&segments),
+                format!("start\n{}end\n", middle)
+            );
+        }
+    }
+
+    #[test]
+    fn resolves_conflicts_independently() {
+        let content = format!("{}{}", MERGE, MERGE);
+        let mut segments = parse_conflicts(&content).unwrap();
+        conflicts(&mut segments)[1].resolution = Some(Resolution::Theirs);
+
+        let rendered = render_segments(&segments);
+        assert_eq!(rendered, format!("{}start\ntheirs\nend\n", MERGE));
+        assert!(has_conflict_markers(&rendered));
+    }
+
+    #[test]
+    fn separator_outside_a_conflict_is_text() {
+        let segments = parse_conflicts("a\n=======\nb\n").unwrap();
+        assert_eq!(segments.len(), 1);
+    }
+
+    #[test]
+    fn unclosed_conflict_is_an_error() {
+        assert!(parse_conflicts("<<<<<<< HEAD\nours\n=======\ntheirs\n").is_err());
+        assert!(has_conflict_markers("<<<<<<< HEAD\nours\n"));
+    }
+}
@@ -0,0 +1,368 @@
+use crate::error::GustError;
+use crate::settings::{self, Config, ConventionSettings};
+
+/// A subject line split into the parts of the commit convention, e.g. `feat(ui)!: add a button`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConventionalSubject {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub important: bool,
+    pub description: String,
+}
+
+impl ConventionalSubject {
+    /// Split `subject` using the delimiters of `conventions`, `None` if it doesn't follow them.
+    /// The type and scope aren't checked against the ones the convention lists.
+    pub fn parse(subject: &str, conventions: &ConventionSettings) -> Option<Self> {
+        let (mut head, description) = subject.split_once(conventions.separator.as_str())?;
+
+        let important = !conventions.important_symbol.is_empty()
+            && head.ends_with(conventions.important_symbol.as_str());
+        if important {
+            head = &head[..head.len() - conventions.important_symbol.len()];
+        }
+
+        let opening = conventions.scope_delimeters.opening.as_str();
+        let closing = conventions.scope_delimeters.closing.as_str();
+        let (kind, scope) = match head.split_once(opening) {
+            Some((kind, rest)) => {
+                let scope = rest.strip_suffix(closing)?;
+                if scope.is_empty() {
+                    return None;
+                }
+                (kind, Some(scope.to_string()))
+            }
+            None => (head, None),
+        };
+
+        if kind.is_empty()
+            || !kind
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            return None;
+        }
+
+        Some(ConventionalSubject {
+            kind: kind.to_string(),
+            scope,
+            important,
+            description: description
+                .strip_prefix(' ')
+                .unwrap_or(description)
+                .to_string(),
+        })
+    }
+
+    /// Everything before the description, ending with the separator and a space
+    pub fn prefix(&self, conventions: &ConventionSettings) -> String {
+        let mut prefix = self.kind.clone();
+        if let Some(scope) = &self.scope {
+            prefix.push_str(&conventions.scope_delimeters.opening);
+            prefix.push_str(scope);
+            prefix.push_str(&conventions.scope_delimeters.closing);
+        }
+        if self.important {
+            prefix.push_str(&conventions.important_symbol);
+        }
+        prefix.push_str(&conventions.separator);
+        prefix.push(' ');
+        prefix
+    }
+}
+
+/// Something in a commit message that doesn't follow the convention
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    /// 1 based, counting the lines of the message without comments
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// The message git will record, without comments or anything below the `--verbose` scissors line.
+/// `comment` is what comment lines start with, see `GitBackend::comment_prefix`.
+pub fn strip_comments(message: &str, comment: &str) -> String {
+    let scissors = format!(
+        "{} ------------------------ >8 ------------------------",
+        comment
+    );
+    message
+        .lines()
+        .take_while(|l| !l.starts_with(&scissors))
+        .filter(|l| !l.starts_with(comment))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+/// Check a commit message against `conventions`.
+/// Merges, reverts and `fixup!`/`squash!` commits are written by git and aren't checked.
+pub fn lint_message(
+    message: &str,
+    conventions: &ConventionSettings,
+    comment: &str,
+) -> Vec<LintIssue> {
+    let message = strip_comments(message, comment);
+    let lines: Vec<&str> = message.lines().collect();
+    let mut issues = Vec::new();
+    let mut issue = |line: usize, message: String| issues.push(LintIssue { line, message });
+
+    let subject = match lines.first() {
+        Some(subject) if !subject.trim().is_empty() => *subject,
+        _ => {
+            issue(1, "the message is empty".to_string());
+            return issues;
+        }
+    };
+    if ["Merge ", "Revert \"", "fixup! ", "squash! ", "amend! "]
+        .iter()
+        .any(|prefix| subject.starts_with(prefix))
+    {
+        return issues;
+    }
+
+    match ConventionalSubject::parse(subject, conventions) {
+        None if !subject.contains(conventions.separator.as_str()) => issue(
+            1,
+            format!(
+                "the subject is missing the '{}' after the type",
+                conventions.separator
+            ),
+        ),
+        None => issue(
+            1,
+            format!(
+                "the subject doesn't start with type{}scope{}{}{} ",
+                conventions.scope_delimeters.opening,
+                conventions.scope_delimeters.closing,
+                conventions.important_symbol,
+                conventions.separator
+            ),
+        ),
+        Some(parsed) => {
+            if !conventions.types.contains(&parsed.kind) {
+                issue(
+                    1,
+                    format!(
+                        "unknown type '{}', expected one of: {}",
+                        parsed.kind,
+                        conventions.types.join(", ")
+                    ),
+                );
+            }
+            if let Some(scope) = parsed.scope {
+                if !conventions.scopes.is_empty() && !conventions.scopes.contains(&scope) {
+                    issue(
+                        1,
+                        format!(
+                            "unknown scope '{}', expected one of: {}",
+                            scope,
+                            conventions.scopes.join(", ")
+                        ),
+                    );
+                }
+            }
+            if parsed.description.trim().is_empty() {
+                issue(1, "the subject has no description".to_string());
+            }
+        }
+    }
+
+    let length = subject.chars().count();
+    if length > conventions.subject_length {
+        issue(
+            1,
+            format!(
+                "the subject is {} characters long, the limit is {}",
+                length, conventions.subject_length
+            ),
+        );
+    }
+
+    if lines.len() > 1 && !lines[1].trim().is_empty() {
+        issue(
+            2,
+            "the body must be separated from the subject by a blank line".to_string(),
+        );
+    }
+
+    // Footers are the last paragraph, if its first line looks like one
+    let footer_start = lines
+        .iter()
+        .rposition(|l| l.trim().is_empty())
+        .map(|blank| blank + 1)
+        .filter(|start| *start > 1 && *start < lines.len());
+    if let Some(start) = footer_start {
+        if parse_footer(lines[start], conventions).is_some() {
+            for (index, line) in lines.iter().enumerate().skip(start + 1) {
+                // Indented lines continue the footer above them
+                if parse_footer(line, conventions).is_none()
+                    && !line.starts_with(char::is_whitespace)
+                {
+                    issue(
+                        index + 1,
+                        format!("'{}' isn't a 'Token: value' or 'Token #value' footer", line),
+                    );
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// Lint `message` with the convention from the config, nothing is checked when conventions are turned off
+pub fn lint_with_config(
+    message: &str,
+    config: &Config,
+    comment: &str,
+) -> Result<Vec<LintIssue>, GustError> {
+    if !config.use_commit_convention {
+        return Ok(vec![]);
+    }
+    let conventions = settings::load_convention(config.convention.clone())?;
+    Ok(lint_message(message, &conventions, comment))
+}
+
+/// Split a footer line into its token and value.
+/// Tokens use `-` instead of spaces, except `BREAKING CHANGE` and the footers the convention lists.
+pub fn parse_footer<'a>(
+    line: &'a str,
+    conventions: &ConventionSettings,
+) -> Option<(&'a str, &'a str)> {
+    let (token, value) = line.split_once(": ").or_else(|| line.split_once(" #"))?;
+
+    let listed = token == "BREAKING CHANGE" || conventions.footers.iter().any(|f| f == token);
+    let word = !token.is_empty() && token.chars().all(|c| c.is_alphanumeric() || c == '-');
+    if (listed || word) && !value.trim().is_empty() {
+        Some((token, value))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conventions() -> ConventionSettings {
+        serde_json::from_str(include_str!("../ConventionalCommits.json")).unwrap()
+    }
+
+    fn lint(message: &str) -> Vec<(usize, String)> {
+        lint_message(message, &conventions(), "#")
+            .into_iter()
+            .map(|issue| (issue.line, issue.message))
+            .collect()
+    }
+
+    #[test]
+    fn parses_subjects() {
+        let conventions = conventions();
+        let parsed = ConventionalSubject::parse("feat(ui)!: add a button", &conventions).unwrap();
+        assert_eq!(parsed.kind, "feat");
+        assert_eq!(parsed.scope.as_deref(), Some("ui"));
+        assert!(parsed.important);
+        assert_eq!(parsed.description, "add a button");
+        assert_eq!(parsed.prefix(&conventions), "feat(ui)!: ");
+
+        for subject in ["add a button", "feat(): x", "(ui): x", "fe at: x"] {
+            assert_eq!(ConventionalSubject::parse(subject, &conventions), None);
+        }
+    }
+
+    #[test]
+    fn accepts_a_conventional_message() {
+        let message = "feat(ui): add a button\n\nIt does things.\n\n\
+                       Refs: #12\nBREAKING CHANGE: the old one is gone\n  and won't return\n";
+        assert_eq!(lint(message), vec![]);
+    }
+
+    #[test]
+    fn reports_broken_subjects() {
+        assert_eq!(lint(""), vec![(1, "the message is empty".to_string())]);
+        assert_eq!(
+            lint("add a button"),
+            vec![(
+                1,
+                "the subject is missing the ':' after the type".to_string()
+            )]
+        );
+        assert_eq!(
+            lint("feat(ui: add a button"),
+            vec![(
+                1,
+                "the subject doesn't start with type(scope)!: ".to_string()
+            )]
+        );
+        assert_eq!(lint("feat: ").len(), 1);
+        assert_eq!(lint("feat: ")[0].1, "the subject has no description");
+    }
+
+    #[test]
+    fn reports_unknown_types_and_scopes() {
+        let issues = lint("feet(db): add a table");
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].1.starts_with("unknown type 'feet'"));
+        assert!(issues[1].1.starts_with("unknown scope 'db'"));
+    }
+
+    #[test]
+    fn reports_long_subjects() {
+        let subject = format!("feat: {}", "a".repeat(70));
+        assert_eq!(
+            lint(&subject),
+            vec![(
+                1,
+                "the subject is 76 characters long, the limit is 72".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_the_layout_of_the_body_and_footers() {
+        assert_eq!(
+            lint("feat: add a button\nno blank line"),
+            vec![(
+                2,
+                "the body must be separated from the subject by a blank line".to_string()
+            )]
+        );
+        assert_eq!(
+            lint("feat: add a button\n\nRefs: #12\nnot a footer"),
+            vec![(
+                4,
+                "'not a footer' isn't a 'Token: value' or 'Token #value' footer".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn skips_messages_written_by_git() {
+        for message in [
+            "Merge branch 'main'",
+            "Revert \"feat: add a button\"",
+            "fixup! feat: add a button",
+        ] {
+            assert_eq!(lint(message), vec![]);
+        }
+    }
+
+    #[test]
+    fn strips_comments_with_the_configured_prefix() {
+        let message = "feat: x\n\n#12 stays\n; a comment\n\
+                       ; ------------------------ >8 ------------------------\ndiff";
+        assert_eq!(strip_comments(message, ";"), "feat: x\n\n#12 stays");
+        assert_eq!(
+            strip_comments("# a comment\nfeat: x\n# another\n", "#"),
+            "feat: x"
+        );
+        assert_eq!(lint_message(message, &conventions(), ";"), vec![]);
+    }
+}
@@ -0,0 +1,650 @@
+use crate::error::GustError;
+use colored::Colorize;
+use regex::Regex;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+    /// The `\ No newline at end of file` marker, refers to the line before it
+    NoNewline,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    /// The line without its leading `+`, `-` or ` `
+    pub content: String,
+    pub selected: bool,
+}
+
+impl DiffLine {
+    pub fn is_change(&self) -> bool {
+        matches!(self.kind, LineKind::Added | LineKind::Removed)
+    }
+
+    pub fn colored(&self) -> String {
+        match self.kind {
+            LineKind::Context => format!(" {}", self.content),
+            LineKind::Added => format!("+{}", self.content).green().to_string(),
+            LineKind::Removed => format!("-{}", self.content).red().to_string(),
+            LineKind::NoNewline => format!("\\{}", self.content).dimmed().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    /// The function/section name git prints after the range
+    pub section: String,
+    pub lines: Vec<DiffLine>,
+}
+
+impl Hunk {
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@{}",
+            self.old_start, self.old_len, self.new_start, self.new_len, self.section
+        )
+    }
+
+    /// The ranges of `lines` holding each run of changes, split apart by context lines
+    pub fn change_groups(&self) -> Vec<Range<usize>> {
+        let mut groups = Vec::<Range<usize>>::new();
+        let mut start: Option<usize> = None;
+
+        for (i, line) in self.lines.iter().enumerate() {
+            match line.kind {
+                LineKind::Context => {
+                    if let Some(s) = start.take() {
+                        groups.push(s..i);
+                    }
+                }
+                _ => {
+                    if start.is_none() {
+                        start = Some(i);
+                    }
+                }
+            }
+        }
+        if let Some(s) = start {
+            groups.push(s..self.lines.len());
+        }
+        groups
+    }
+
+    pub fn set_selected(&mut self, range: Range<usize>, selected: bool) {
+        for line in &mut self.lines[range] {
+            line.selected = selected;
+        }
+    }
+
+    /// Print the lines in `range` along with up to three lines of context each side
+    pub fn print_range(&self, range: Range<usize>) {
+        let start = range.start.saturating_sub(3);
+        let end = (range.end + 3).min(self.lines.len());
+
+        println!("{}", self.header().cyan());
+        for line in &self.lines[start..end] {
+            println!("{}", line.colored());
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FilePatch {
+    /// The `diff --git`, `index`, `---` and `+++` lines
+    pub header: Vec<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    /// The path on the new side of the diff, as written in the `+++` line
+    pub fn path(&self) -> Option<String> {
+        self.header
+            .iter()
+            .find_map(|l| l.strip_prefix("+++ "))
+            .map(|p| p.strip_prefix("b/").unwrap_or(p).to_string())
+    }
+
+    /// Build a patch containing only the selected lines.
+    ///
+    /// When `reverse` is set the patch is meant for `git apply --reverse`, so the
+    /// new side is the one that has to match, and unselected additions are kept
+    /// as context instead of unselected removals.
+    pub fn to_patch(&self, reverse: bool) -> Option<String> {
+        let mut output = self.header.join("\n");
+        output.push('\n');
+
+        let mut delta: isize = 0;
+        let mut has_changes = false;
+
+        for hunk in &self.hunks {
+            // Each kept line's prefix and content, with the marker that follows it if
+            // it doesn't end in a newline
+            let mut kept = Vec::<(char, &str, Option<String>)>::new();
+            let mut last_emitted = false;
+
+            for line in &hunk.lines {
+                let emitted = match (line.kind, line.selected) {
+                    (LineKind::Context, _) => Some(' '),
+                    (LineKind::Removed, true) => Some('-'),
+                    (LineKind::Added, true) => Some('+'),
+                    (LineKind::Removed, false) if !reverse => Some(' '),
+                    (LineKind::Added, false) if reverse => Some(' '),
+                    (LineKind::Removed, false) | (LineKind::Added, false) => None,
+                    (LineKind::NoNewline, _) => {
+                        if let Some(last) = kept.last_mut().filter(|_| last_emitted) {
+                            last.2 = Some(format!("\\{}", line.content));
+                        }
+                        continue;
+                    }
+                };
+
+                last_emitted = emitted.is_some();
+                if let Some(prefix) = emitted {
+                    kept.push((prefix, &line.content, None));
+                }
+            }
+
+            let mut body = Vec::<String>::new();
+            let mut old_len = 0;
+            let mut new_len = 0;
+            let mut changed = false;
+
+            for (i, (prefix, content, marker)) in kept.iter().enumerate() {
+                match prefix {
+                    ' ' => {
+                        old_len += 1;
+                        new_len += 1;
+                    }
+                    '-' => {
+                        old_len += 1;
+                        changed = true;
+                    }
+                    _ => {
+                        new_len += 1;
+                        changed = true;
+                    }
+                }
+
+                // A line without a newline has to be the last one on its side. Leaving out
+                // lines can put others after it, so that side needs the newline after all.
+                let rest = &kept[i + 1..];
+                let old_follows = rest.iter().any(|(p, _, _)| *p != '+');
+                let new_follows = rest.iter().any(|(p, _, _)| *p != '-');
+                match (prefix, marker) {
+                    (' ', Some(marker)) if old_follows != new_follows => {
+                        let (old, new) = (format!("-{}", content), format!("+{}", content));
+                        if new_follows {
+                            body.extend([old, marker.clone(), new]);
+                        } else {
+                            body.extend([old, new, marker.clone()]);
+                        }
+                        changed = true;
+                    }
+                    (' ', Some(_)) if old_follows => body.push(format!(" {}", content)),
+                    ('-', Some(_)) if old_follows => body.push(format!("-{}", content)),
+                    ('+', Some(_)) if new_follows => body.push(format!("+{}", content)),
+                    _ => {
+                        body.push(format!("{}{}", prefix, content));
+                        body.extend(marker.clone());
+                    }
+                }
+            }
+
+            if !changed {
+                continue;
+            }
+            has_changes = true;
+
+            // The side that has to match keeps its position, the other side is
+            // shifted by whatever the previously emitted hunks added or removed.
+            // A side with no lines points at the line before the hunk.
+            let (old_start, new_start) = if reverse {
+                let before = hunk.new_start - usize::from(hunk.new_len > 0);
+                let old_before = (before as isize - delta) as usize;
+                (old_before + usize::from(old_len > 0), hunk.new_start)
+            } else {
+                let before = hunk.old_start - usize::from(hunk.old_len > 0);
+                let new_before = (before as isize + delta) as usize;
+                (hunk.old_start, new_before + usize::from(new_len > 0))
+            };
+            delta += new_len as isize - old_len as isize;
+
+            output.push_str(&format!(
+                "@@ -{},{} +{},{} @@{}\n",
+                old_start, old_len, new_start, new_len, hunk.section
+            ));
+            for line in body {
+                output.push_str(&line);
+                output.push('\n');
+            }
+        }
+
+        if has_changes {
+            Some(output)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse the output of `git diff --no-color` into one patch per file.
+/// Only the `\n` ending each line is removed, so a CRLF file keeps its `\r` in the patch.
+pub fn parse_diff(diff: &str) -> Result<Vec<FilePatch>, GustError> {
+    let mut patches = Vec::<FilePatch>::new();
+
+    for line in diff.split_inclusive('\n') {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        if line.starts_with("diff --git ") {
+            patches.push(FilePatch {
+                header: vec![line.to_string()],
+                hunks: vec![],
+            });
+            continue;
+        }
+
+        let patch = match patches.last_mut() {
+            Some(patch) => patch,
+            None => return Err(format!("Unexpected line before any diff header: {}", line).into()),
+        };
+
+        if line.starts_with("@@ ") {
+            patch.hunks.push(parse_hunk_header(line)?);
+            continue;
+        }
+
+        match patch.hunks.last_mut() {
+            // Anything before the first hunk belongs to the file header
+            None => patch.header.push(line.to_string()),
+            Some(hunk) => {
+                let mut chars = line.chars();
+                let kind = match chars.next() {
+                    Some(' ') | None => LineKind::Context,
+                    Some('+') => LineKind::Added,
+                    Some('-') => LineKind::Removed,
+                    Some('\\') => LineKind::NoNewline,
+                    Some(_) => return Err(format!("Unexpected line in diff hunk: {}", line).into()),
+                };
+                hunk.lines.push(DiffLine {
+                    kind,
+                    content: chars.as_str().to_string(),
+                    selected: false,
+                });
+            }
+        }
+    }
+
+    Ok(patches)
+}
+
+fn parse_hunk_header(line: &str) -> Result<Hunk, GustError> {
+    let malformed = || format!("Malformed hunk header: {}", line);
+
+    let inner = line.strip_prefix("@@ ").ok_or_else(malformed)?;
+    let (ranges, section) = inner.split_once(" @@").ok_or_else(malformed)?;
+    let (old, new) = ranges.split_once(' ').ok_or_else(malformed)?;
+
+    let parse_range = |range: &str| -> Result<(usize, usize), GustError> {
+        let (start, len) = range.split_once(',').unwrap_or((range, "1"));
+        Ok((
+            start.parse().map_err(|_| malformed())?,
+            len.parse().map_err(|_| malformed())?,
+        ))
+    };
+    let (old_start, old_len) = parse_range(old.strip_prefix('-').ok_or_else(malformed)?)?;
+    let (new_start, new_len) = parse_range(new.strip_prefix('+').ok_or_else(malformed)?)?;
+
+    Ok(Hunk {
+        old_start,
+        old_len,
+        new_start,
+        new_len,
+        section: section.to_string(),
+        lines: vec![],
+    })
+}
+
+/// Render patches the way `git diff --color` would
+pub fn render_unified(patches: &[FilePatch]) -> String {
+    let mut output = Vec::<String>::new();
+    for patch in patches {
+        for line in &patch.header {
+            output.push(line.bold().to_string());
+        }
+        for hunk in &patch.hunks {
+            output.push(hunk.header().cyan().to_string());
+            for line in &hunk.lines {
+                output.push(line.colored());
+            }
+        }
+    }
+    output.join("\n")
+}
+
+/// Render patches with the old file on the left and the new file on the right
+pub fn render_side_by_side(patches: &[FilePatch], width: usize) -> String {
+    // Leave room for the ` │ ` between the columns
+    let column = width.saturating_sub(3) / 2;
+
+    let mut output = Vec::<String>::new();
+    for patch in patches {
+        output.push(patch.path().unwrap_or_default().bold().to_string());
+        for hunk in &patch.hunks {
+            output.push(hunk.header().cyan().to_string());
+
+            let mut removed = Vec::<&DiffLine>::new();
+            let mut added = Vec::<&DiffLine>::new();
+            for line in &hunk.lines {
+                match line.kind {
+                    LineKind::Removed => removed.push(line),
+                    LineKind::Added => added.push(line),
+                    LineKind::NoNewline => {}
+                    LineKind::Context => {
+                        pair_rows(&mut output, &mut removed, &mut added, column);
+                        let text = pad_cell(&line.content, column);
+                        output.push(format!("{} │ {}", text, text));
+                    }
+                }
+            }
+            pair_rows(&mut output, &mut removed, &mut added, column);
+        }
+    }
+    output.join("\n")
+}
+
+/// Pair up a run of removed lines with the added lines that replaced them
+fn pair_rows(
+    output: &mut Vec<String>,
+    removed: &mut Vec<&DiffLine>,
+    added: &mut Vec<&DiffLine>,
+    column: usize,
+) {
+    for i in 0..removed.len().max(added.len()) {
+        let left = match removed.get(i) {
+            Some(line) => pad_cell(&line.content, column).red().to_string(),
+            None => pad_cell("", column),
+        };
+        let right = match added.get(i) {
+            Some(line) => pad_cell(&line.content, column).green().to_string(),
+            None => String::new(),
+        };
+        output.push(format!("{} │ {}", left, right));
+    }
+    removed.clear();
+    added.clear();
+}
+
+fn pad_cell(text: &str, column: usize) -> String {
+    // Tabs and the `\r` of CRLF lines would throw off the column widths
+    let text = text.trim_end_matches('\r').replace('\t', "    ");
+    console::pad_str(&text, column, console::Alignment::Left, Some("…")).to_string()
+}
+
+/// Color the `[-removed-]` and `{+added+}` markers of `git diff --word-diff=plain`
+pub fn render_word_diff(diff: &str) -> String {
+    let removed = Regex::new(r"\[-(.*?)-\]").unwrap();
+    let added = Regex::new(r"\{\+(.*?)\+\}").unwrap();
+
+    let mut output = Vec::<String>::new();
+    for line in diff.lines() {
+        if line.starts_with("@@") {
+            output.push(line.cyan().to_string());
+        } else if line.starts_with("diff --git")
+            || line.starts_with("index ")
+            || line.starts_with("--- ")
+            || line.starts_with("+++ ")
+        {
+            output.push(line.bold().to_string());
+        } else {
+            let line = removed.replace_all(line, |c: &regex::Captures| {
+                c[1].red().strikethrough().to_string()
+            });
+            let line = added.replace_all(&line, |c: &regex::Captures| c[1].green().to_string());
+            output.push(line.to_string());
+        }
+    }
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "diff --git a/f b/f\nindex 1111111..2222222 100644\n--- a/f\n+++ b/f\n";
+
+    fn patch(hunks: &str) -> FilePatch {
+        parse_diff(&format!("{}{}", HEADER, hunks))
+            .unwrap()
+            .remove(0)
+    }
+
+    /// Select the lines of the first hunk whose `+`/`-` line is in `lines`
+    fn select(patch: &mut FilePatch, lines: &[&str]) {
+        for hunk in &mut patch.hunks {
+            for line in &mut hunk.lines {
+                let text = match line.kind {
+                    LineKind::Added => format!("+{}", line.content),
+                    LineKind::Removed => format!("-{}", line.content),
+                    _ => continue,
+                };
+                line.selected = lines.contains(&text.as_str());
+            }
+        }
+    }
+
+    fn hunks_of(patch: &str) -> &str {
+        patch.strip_prefix(HEADER).unwrap()
+    }
+
+    #[test]
+    fn parses_headers_hunks_and_lines() {
+        let patches = parse_diff(&format!(
+            "{}@@ -1,3 +1,3 @@ fn main() {{\n a\n-b\n+B\n c\n@@ -10 +10,2 @@\n x\n+y\ndiff --git a/g b/g\nnew file mode 100644\n",
+            HEADER
+        ))
+        .unwrap();
+
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].header.len(), 4);
+        assert_eq!(patches[0].path().as_deref(), Some("f"));
+
+        let hunk = &patches[0].hunks[0];
+        assert_eq!(
+            (hunk.old_start, hunk.old_len, hunk.new_start, hunk.new_len),
+            (1, 3, 1, 3)
+        );
+        assert_eq!(hunk.section, " fn main() {");
+        let kinds: Vec<LineKind> = hunk.lines.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LineKind::Context,
+                LineKind::Removed,
+                LineKind::Added,
+                LineKind::Context
+            ]
+        );
+        assert_eq!(hunk.change_groups(), vec![1..3]);
+
+        // A range without a length covers one line
+        let second = &patches[0].hunks[1];
+        assert_eq!((second.old_start, second.old_len), (10, 1));
+        assert!(patches[1].hunks.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_diffs() {
+        assert!(parse_diff("@@ -1 +1 @@\n").is_err());
+        assert!(parse_diff(&format!("{}@@ -x +1 @@\n", HEADER)).is_err());
+        assert!(parse_diff(&format!("{}@@ -1 +1 @@\n?what\n", HEADER)).is_err());
+    }
+
+    #[test]
+    fn nothing_selected_makes_no_patch() {
+        let patch = patch("@@ -1,2 +1,2 @@\n a\n-b\n+B\n");
+        assert_eq!(patch.to_patch(false), None);
+        assert_eq!(patch.to_patch(true), None);
+    }
+
+    #[test]
+    fn unselected_removals_become_context_and_additions_are_dropped() {
+        let mut patch = patch("@@ -1,3 +1,3 @@\n a\n-b\n-c\n+B\n+C\n");
+        select(&mut patch, &["-b", "+B"]);
+
+        assert_eq!(
+            hunks_of(&patch.to_patch(false).unwrap()),
+            "@@ -1,3 +1,3 @@\n a\n-b\n c\n+B\n"
+        );
+    }
+
+    #[test]
+    fn reverse_keeps_unselected_additions_as_context() {
+        let mut patch = patch("@@ -1,3 +1,3 @@\n a\n-b\n-c\n+B\n+C\n");
+        select(&mut patch, &["-b", "+B"]);
+
+        assert_eq!(
+            hunks_of(&patch.to_patch(true).unwrap()),
+            "@@ -1,3 +1,3 @@\n a\n-b\n+B\n C\n"
+        );
+    }
+
+    #[test]
+    fn later_hunks_are_shifted_by_the_earlier_ones() {
+        let hunks = "@@ -1,2 +1,3 @@\n a\n+new\n b\n@@ -10,2 +11,1 @@\n x\n-y\n";
+
+        // Without the first hunk the second one lands a line earlier on the new side
+        let mut second_only = patch(hunks);
+        select(&mut second_only, &["-y"]);
+        assert_eq!(
+            hunks_of(&second_only.to_patch(false).unwrap()),
+            "@@ -10,2 +10,1 @@\n x\n-y\n"
+        );
+
+        let mut both = patch(hunks);
+        select(&mut both, &["+new", "-y"]);
+        assert_eq!(
+            hunks_of(&both.to_patch(false).unwrap()),
+            "@@ -1,2 +1,3 @@\n a\n+new\n b\n@@ -10,2 +11,1 @@\n x\n-y\n"
+        );
+
+        // Unstaging only the second hunk, the index still has the first one
+        let mut reverse = patch(hunks);
+        select(&mut reverse, &["-y"]);
+        assert_eq!(
+            hunks_of(&reverse.to_patch(true).unwrap()),
+            "@@ -11,2 +11,1 @@\n x\n-y\n"
+        );
+    }
+
+    #[test]
+    fn additions_to_an_empty_side_point_at_the_line_before() {
+        let mut patch = patch("@@ -0,0 +1,2 @@\n+a\n+b\n");
+        select(&mut patch, &["+b"]);
+        assert_eq!(
+            hunks_of(&patch.to_patch(false).unwrap()),
+            "@@ -0,0 +1,1 @@\n+b\n"
+        );
+    }
+
+    #[test]
+    fn no_newline_marker_stays_with_the_last_line() {
+        let mut patch =
+            patch("@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+B\n\\ No newline at end of file\n");
+        select(&mut patch, &["-b", "+B"]);
+        assert_eq!(
+            hunks_of(&patch.to_patch(false).unwrap()),
+            "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+B\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn unselected_line_without_newline_is_rewritten_when_lines_follow_it() {
+        // Index `a\nb`, work tree `a\nb\nc`, staging only `+c`
+        let hunks = "@@ -1,2 +1,3 @@\n a\n-b\n\\ No newline at end of file\n+b\n+c\n\\ No newline at end of file\n";
+        let mut forward = patch(hunks);
+        select(&mut forward, &["+c"]);
+        assert_eq!(
+            hunks_of(&forward.to_patch(false).unwrap()),
+            "@@ -1,2 +1,3 @@\n a\n-b\n\\ No newline at end of file\n+b\n+c\n\\ No newline at end of file\n"
+        );
+
+        // HEAD `a\nb\nc`, index `a\nb`, unstaging only `-c` puts `c` back before the kept `b`
+        let hunks = "@@ -1,3 +1,2 @@\n a\n-b\n-c\n\\ No newline at end of file\n+b\n\\ No newline at end of file\n";
+        let mut reverse = patch(hunks);
+        select(&mut reverse, &["-c"]);
+        assert_eq!(
+            hunks_of(&reverse.to_patch(true).unwrap()),
+            "@@ -1,3 +1,2 @@\n a\n-c\n b\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn unselected_line_without_newline_stays_context_when_nothing_follows() {
+        let hunks = "@@ -1,2 +1,2 @@\n-a\n+A\n b\n\\ No newline at end of file\n";
+        let mut patch = patch(hunks);
+        select(&mut patch, &["-a", "+A"]);
+        assert_eq!(
+            hunks_of(&patch.to_patch(false).unwrap()),
+            "@@ -1,2 +1,2 @@\n-a\n+A\n b\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn crlf_lines_keep_their_carriage_return() {
+        let hunks = "@@ -1,3 +1,3 @@\n a\r\n-b\r\n+B\r\n c\r\n";
+        let mut patch = patch(hunks);
+        assert_eq!(patch.hunks[0].lines[1].content, "b\r");
+
+        select(&mut patch, &["-b\r", "+B\r"]);
+        assert_eq!(hunks_of(&patch.to_patch(false).unwrap()), hunks);
+    }
+
+    fn plain(text: &str) -> Vec<String> {
+        console::strip_ansi_codes(text)
+            .lines()
+            .map(|l| l.trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn side_by_side_pairs_removed_lines_with_their_replacements() {
+        let hunks = "@@ -1,4 +1,4 @@\n a\n-b\n-c\n+B\n d\n+e\n";
+        let rendered = render_side_by_side(&[patch(hunks)], 23);
+        assert_eq!(
+            plain(&rendered),
+            [
+                "f",
+                "@@ -1,4 +1,4 @@",
+                "a          │ a",
+                "b          │ B",
+                "c          │",
+                "d          │ d",
+                "           │ e",
+            ]
+        );
+    }
+
+    #[test]
+    fn side_by_side_truncates_long_lines_and_expands_tabs() {
+        let hunks = "@@ -1 +1 @@\n-\tindented line\r\n+short\r\n";
+        let rendered = render_side_by_side(&[patch(hunks)], 23);
+        assert_eq!(plain(&rendered)[2], "    inden… │ short");
+    }
+
+    #[test]
+    fn word_diff_drops_the_markers() {
+        let diff = format!("{}@@ -1 +1 @@\nthe [-old-]{{+new+}} word\n", HEADER);
+        let rendered = render_word_diff(&diff);
+        let lines = plain(&rendered);
+        assert_eq!(lines[..4], HEADER.lines().collect::<Vec<_>>()[..]);
+        assert_eq!(lines[4..], ["@@ -1 +1 @@", "the oldnew word"]);
+    }
+}
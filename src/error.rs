@@ -0,0 +1,155 @@
+use std::fmt;
+
+/// Everything that can go wrong while running Gust
+#[derive(Debug)]
+pub enum GustError {
+    /// The `git` executable couldn't be started
+    GitNotInstalled(std::io::Error),
+    NotARepo,
+    /// Git ran but exited with a non-zero code, `code` is `None` if it was killed by a signal
+    GitFailed {
+        command: String,
+        code: Option<i32>,
+        stderr: String,
+    },
+    /// The user backed out of a prompt
+    Cancelled,
+    Config(String),
+    Convention(String),
+    Io(std::io::Error),
+    Message(String),
+}
+
+impl GustError {
+    /// Whether Gust can carry on with the menus after this error
+    pub fn is_recoverable(&self) -> bool {
+        !matches!(self, GustError::GitNotInstalled(_) | GustError::NotARepo)
+    }
+}
+
+impl fmt::Display for GustError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GustError::GitNotInstalled(e) => write!(f, "Couldn't run git, is it installed?: {}", e),
+            GustError::NotARepo => write!(f, "No git repository was found here"),
+            GustError::GitFailed {
+                command,
+                code,
+                stderr,
+            } => {
+                match code {
+                    Some(code) => write!(f, "`{}` exited with code {}", command, code)?,
+                    None => write!(f, "`{}` was stopped by a signal", command)?,
+                }
+                if !stderr.trim().is_empty() {
+                    write!(f, ":\n{}", stderr.trim_end())?;
+                }
+                Ok(())
+            }
+            GustError::Cancelled => write!(f, "Cancelled"),
+            GustError::Config(message) => write!(f, "Config error: {}", message),
+            GustError::Convention(message) => write!(f, "Convention error: {}", message),
+            GustError::Io(e) => write!(f, "{}", e),
+            GustError::Message(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GustError {}
+
+impl From<std::io::Error> for GustError {
+    fn from(e: std::io::Error) -> Self {
+        GustError::Io(e)
+    }
+}
+
+impl From<String> for GustError {
+    fn from(message: String) -> Self {
+        GustError::Message(message)
+    }
+}
+
+impl From<&str> for GustError {
+    fn from(message: &str) -> Self {
+        GustError::Message(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+    use crate::git::GitCommand;
+
+    fn failed(code: Option<i32>, stderr: &str) -> GustError {
+        GustError::GitFailed {
+            command: "git switch main".to_string(),
+            code,
+            stderr: stderr.to_string(),
+        }
+    }
+
+    #[test]
+    fn failed_commands_show_their_code_and_stderr() {
+        assert_eq!(
+            failed(Some(128), "fatal: invalid reference: main\n").to_string(),
+            "`git switch main` exited with code 128:\nfatal: invalid reference: main"
+        );
+        assert_eq!(
+            failed(Some(1), "  \n").to_string(),
+            "`git switch main` exited with code 1"
+        );
+        assert_eq!(
+            failed(None, "").to_string(),
+            "`git switch main` was stopped by a signal"
+        );
+    }
+
+    #[test]
+    fn only_a_missing_git_or_repository_stops_gust() {
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "not found");
+        assert!(!GustError::GitNotInstalled(missing).is_recoverable());
+        assert!(!GustError::NotARepo.is_recoverable());
+
+        assert!(failed(Some(1), "").is_recoverable());
+        assert!(GustError::Cancelled.is_recoverable());
+        assert!(GustError::Convention("bad".to_string()).is_recoverable());
+        assert!(GustError::from("message").is_recoverable());
+    }
+
+    #[test]
+    fn converts_from_io_errors_and_messages() {
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert!(matches!(GustError::from(io), GustError::Io(e) if e.to_string() == "denied"));
+        assert!(matches!(GustError::from("text"), GustError::Message(m) if m == "text"));
+        assert_eq!(GustError::from(String::from("text")).to_string(), "text");
+        assert_eq!(
+            GustError::Config("bad key".to_string()).to_string(),
+            "Config error: bad key"
+        );
+    }
+
+    #[test]
+    fn git_failures_keep_what_git_printed() {
+        let _repo = TestRepo::new();
+        let output = GitCommand::new("switch")
+            .arg("missing")
+            .capture()
+            .run()
+            .unwrap();
+        assert!(!output.success());
+
+        match output.into_error() {
+            GustError::GitFailed {
+                command,
+                code,
+                stderr,
+            } => {
+                assert_eq!(command, "git switch missing");
+                assert_eq!(code, Some(128));
+                assert!(stderr.contains("missing"), "{}", stderr);
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+}
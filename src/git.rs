@@ -0,0 +1,313 @@
+use crate::error::GustError;
+use colored::Colorize;
+use std::ffi::{OsStr, OsString};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static PRINT_COMMANDS: AtomicBool = AtomicBool::new(false);
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+static FULL_SCREEN: AtomicBool = AtomicBool::new(false);
+
+/// Set how every git command is run, from `Config::print_git_command` and `--dry-run`
+pub fn configure(print_commands: bool, dry_run: bool) {
+    PRINT_COMMANDS.store(print_commands, Ordering::Relaxed);
+    DRY_RUN.store(dry_run, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// While the full-screen mode is drawn git mustn't write to the terminal or read from it,
+/// so everything it prints is captured and kept for the error instead
+pub fn set_full_screen(full_screen: bool) {
+    FULL_SCREEN.store(full_screen, Ordering::Relaxed);
+}
+
+/// Whether the full-screen mode is drawn, it shows what happened in its own status bar
+pub fn is_full_screen() -> bool {
+    FULL_SCREEN.load(Ordering::Relaxed)
+}
+
+/// A single git invocation. Every git command Gust runs goes through here so it
+/// can be echoed, or skipped with `--dry-run`.
+pub struct GitCommand {
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    stdin: Option<String>,
+    capture_stdout: bool,
+    capture_stderr: bool,
+    read_only: bool,
+}
+
+/// The result of a git command, `code` is `None` if git was killed by a signal
+pub struct GitOutput {
+    pub command: String,
+    pub code: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: String,
+    pub duration: Duration,
+}
+
+impl GitCommand {
+    pub fn new<S: AsRef<OsStr>>(subcommand: S) -> Self {
+        GitCommand {
+            args: vec![subcommand.as_ref().to_os_string()],
+            envs: vec![],
+            stdin: None,
+            capture_stdout: false,
+            capture_stderr: false,
+            read_only: false,
+        }
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(mut self, arg: S) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+        self
+    }
+
+    /// Set an environment variable for git, such as the editor it should open
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(mut self, key: K, value: V) -> Self {
+        self.envs
+            .push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    /// Write `input` to git's stdin
+    pub fn stdin(mut self, input: String) -> Self {
+        self.stdin = Some(input);
+        self
+    }
+
+    /// Keep stdout and stderr instead of letting git print them
+    pub fn capture(mut self) -> Self {
+        self.capture_stdout = true;
+        self.capture_stderr = true;
+        self
+    }
+
+    /// Keep only stdout, git's progress and errors are still printed
+    pub fn capture_stdout(mut self) -> Self {
+        self.capture_stdout = true;
+        self
+    }
+
+    /// The command only reads the repository, so it isn't echoed and still runs in a dry run
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// The command as it would be typed in a shell
+    pub fn command_line(&self) -> String {
+        let mut line = String::new();
+        for (key, value) in &self.envs {
+            line.push_str(&format!(
+                "{}={} ",
+                key.to_string_lossy(),
+                shell_quote(&value.to_string_lossy())
+            ));
+        }
+        line.push_str("git");
+        for arg in &self.args {
+            line.push(' ');
+            line.push_str(&shell_quote(&arg.to_string_lossy()));
+        }
+        line
+    }
+
+    pub fn run(self) -> Result<GitOutput, GustError> {
+        let full_screen = is_full_screen();
+        let print_commands =
+            PRINT_COMMANDS.load(Ordering::Relaxed) && !self.read_only && !full_screen;
+
+        if is_dry_run() && !self.read_only {
+            if !full_screen {
+                println!("{} {}", "dry run:".bright_yellow(), self.command_line());
+            }
+            return Ok(GitOutput {
+                command: self.command_line(),
+                code: Some(0),
+                stdout: vec![],
+                stderr: String::new(),
+                duration: Duration::ZERO,
+            });
+        }
+        if print_commands {
+            println!(
+                "{} {}",
+                "$".bright_black(),
+                self.command_line().bright_black()
+            );
+        }
+
+        let start = Instant::now();
+        let mut child = Command::new("git")
+            .args(&self.args)
+            .envs(self.envs.iter().map(|(k, v)| (k, v)))
+            .stdin(if self.stdin.is_some() {
+                Stdio::piped()
+            } else if full_screen {
+                Stdio::null()
+            } else {
+                Stdio::inherit()
+            })
+            .stdout(if self.capture_stdout || full_screen {
+                Stdio::piped()
+            } else {
+                Stdio::inherit()
+            })
+            .stderr(if self.capture_stderr || full_screen {
+                Stdio::piped()
+            } else {
+                Stdio::inherit()
+            })
+            .spawn()
+            .map_err(GustError::GitNotInstalled)?;
+
+        // Written from another thread, git can fill the stdout pipe before reading all of
+        // its input. The handle is dropped once written so git sees the end of it.
+        let writer = match self.stdin.clone() {
+            Some(input) => {
+                let mut stdin = child.stdin.take().ok_or("Couldn't open git's stdin")?;
+                Some(std::thread::spawn(move || {
+                    stdin.write_all(input.as_bytes())
+                }))
+            }
+            None => None,
+        };
+
+        let output = child.wait_with_output()?;
+        if let Some(writer) = writer {
+            match writer.join().map_err(|_| "Couldn't write to git's stdin")? {
+                // git stopped reading early, what it printed says why
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+                result => result?,
+            }
+        }
+        let git_output = GitOutput {
+            command: self.command_line(),
+            code: output.status.code(),
+            stdout: output.stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            duration: start.elapsed(),
+        };
+
+        if print_commands {
+            let code = git_output
+                .code
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| "signal".to_string());
+            println!(
+                "{}",
+                format!(
+                    "  exited with {} in {}ms",
+                    code,
+                    git_output.duration.as_millis()
+                )
+                .bright_black()
+            );
+        }
+        Ok(git_output)
+    }
+}
+
+/// Quote `arg` so it can be pasted into a shell
+pub fn shell_quote(arg: &str) -> String {
+    if arg.is_empty()
+        || arg.contains(|c: char| c.is_whitespace() || "'\"$`\\*?;&|<>(){}".contains(c))
+    {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    } else {
+        arg.to_string()
+    }
+}
+
+impl GitOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    pub fn stdout_string(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).to_string()
+    }
+
+    /// The error for a command that didn't succeed
+    pub fn into_error(self) -> GustError {
+        GustError::GitFailed {
+            command: self.command,
+            code: self.code,
+            stderr: self.stderr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+
+    #[test]
+    fn quotes_only_what_the_shell_would_change() {
+        assert_eq!(shell_quote("commit"), "commit");
+        assert_eq!(shell_quote("--format=%H"), "--format=%H");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("fix: a thing"), "'fix: a thing'");
+        assert_eq!(shell_quote("stash@{0}"), "'stash@{0}'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn dry_run_skips_only_commands_that_change_the_repository() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+
+        configure(false, true);
+        let tag = GitCommand::new("tag").arg("a tag").run();
+        let head = GitCommand::new("rev-parse")
+            .arg("HEAD")
+            .capture()
+            .read_only()
+            .run();
+        configure(false, false);
+
+        let tag = tag.unwrap();
+        assert!(tag.success());
+        assert_eq!(tag.command, "git tag 'a tag'");
+        assert_eq!(repo.git(&["tag"]), "");
+        assert_eq!(
+            head.unwrap().stdout_string().trim(),
+            repo.git(&["rev-parse", "HEAD"])
+        );
+    }
+
+    #[test]
+    fn large_input_doesnt_block_on_the_output() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        // Far more than a pipe holds, git answers each line while the rest is still being written
+        let input = "HEAD\n".repeat(20_000);
+
+        let output = GitCommand::new("cat-file")
+            .arg("--batch-check")
+            .stdin(input)
+            .capture()
+            .read_only()
+            .run()
+            .unwrap();
+        assert!(output.success());
+        assert_eq!(output.stdout_string().lines().count(), 20_000);
+    }
+}
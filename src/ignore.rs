@@ -0,0 +1,324 @@
+use crate::error::GustError;
+use std::path::{Component, Path, PathBuf};
+
+/// Gitignore templates bundled into the binary, as `(name, contents)`
+pub const TEMPLATES: &[(&str, &str)] = &[
+    (
+        "General",
+        include_str!("../templates/gitignore/General.gitignore"),
+    ),
+    (
+        "Rust",
+        include_str!("../templates/gitignore/Rust.gitignore"),
+    ),
+    (
+        "Node",
+        include_str!("../templates/gitignore/Node.gitignore"),
+    ),
+    (
+        "Python",
+        include_str!("../templates/gitignore/Python.gitignore"),
+    ),
+    ("Go", include_str!("../templates/gitignore/Go.gitignore")),
+    (
+        "Java",
+        include_str!("../templates/gitignore/Java.gitignore"),
+    ),
+    ("C", include_str!("../templates/gitignore/C.gitignore")),
+];
+
+/// What part of a path should be ignored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoreScope {
+    File,
+    Extension,
+    Directory,
+}
+
+/// A pattern that ignores a path, in the forms needed for each ignore file
+#[derive(Debug, Clone)]
+pub struct IgnoreRule {
+    /// For the `.gitignore` at the repository root or `.git/info/exclude`
+    pub root_pattern: String,
+    /// The directory (relative to the repository root) a nested `.gitignore` goes in
+    pub nested_dir: PathBuf,
+    /// For the nested `.gitignore`
+    pub nested_pattern: String,
+}
+
+impl IgnoreRule {
+    /// Build the rule ignoring `path` (relative to the repository root).
+    /// Returns `None` if the scope doesn't apply, e.g. an extension for a file without one.
+    pub fn new(path: &Path, scope: IgnoreScope) -> Option<Self> {
+        let parent = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let name = path.file_name()?.to_string_lossy().to_string();
+
+        match scope {
+            IgnoreScope::File => Some(IgnoreRule {
+                root_pattern: format!("/{}", to_pattern(path)),
+                nested_dir: parent,
+                nested_pattern: format!("/{}", name),
+            }),
+            IgnoreScope::Extension => {
+                let extension = path.extension()?.to_string_lossy().to_string();
+                Some(IgnoreRule {
+                    root_pattern: format!("*.{}", extension),
+                    nested_dir: parent,
+                    nested_pattern: format!("*.{}", extension),
+                })
+            }
+            IgnoreScope::Directory => {
+                let dir_name = parent.file_name()?.to_string_lossy().to_string();
+                Some(IgnoreRule {
+                    root_pattern: format!("/{}/", to_pattern(&parent)),
+                    nested_dir: parent.parent().unwrap_or(Path::new("")).to_path_buf(),
+                    nested_pattern: format!("/{}/", dir_name),
+                })
+            }
+        }
+    }
+}
+
+/// The pattern that decides whether a path is ignored, from `git check-ignore`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreMatch {
+    /// The ignore file the pattern is in
+    pub source: String,
+    pub line: String,
+    /// Starts with `!` when it un-ignores the path
+    pub pattern: String,
+}
+
+impl IgnoreMatch {
+    /// Parse the output of `git check-ignore --verbose --stdin -z`, which is
+    /// `<source>\0<line>\0<pattern>\0<path>\0` so any of them can contain `:`
+    pub fn parse(output: &str) -> Option<Self> {
+        let mut fields = output.split('\0');
+        Some(IgnoreMatch {
+            source: fields.next().filter(|s| !s.is_empty())?.to_string(),
+            line: fields.next()?.to_string(),
+            pattern: fields.next()?.to_string(),
+        })
+    }
+}
+
+/// Gitignore patterns always use `/`, whatever the platform
+fn to_pattern(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+/// Join `prefix` (from `git rev-parse --show-prefix`) and a path relative to it,
+/// resolving any `..` so the result is relative to the repository root
+pub fn root_relative(prefix: &Path, path: &Path) -> PathBuf {
+    let mut output = PathBuf::new();
+    for component in prefix.join(path).components() {
+        match component {
+            Component::ParentDir => {
+                output.pop();
+            }
+            Component::Normal(part) => output.push(part),
+            _ => {}
+        }
+    }
+    output
+}
+
+/// Append the patterns missing from the ignore file at `file`, creating it if needed.
+/// Returns the patterns that were added.
+pub fn append_patterns(
+    file: &Path,
+    patterns: &[String],
+    heading: Option<&str>,
+) -> Result<Vec<String>, GustError> {
+    let existing = if file.exists() {
+        std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e))?
+    } else {
+        String::new()
+    };
+    let existing_patterns: Vec<&str> = existing.lines().map(|l| l.trim()).collect();
+
+    let mut added = Vec::<String>::new();
+    for pattern in patterns {
+        let pattern = pattern.trim();
+        if pattern.is_empty()
+            || pattern.starts_with('#')
+            || existing_patterns.contains(&pattern)
+            || added.iter().any(|a| a == pattern)
+        {
+            continue;
+        }
+        added.push(pattern.to_string());
+    }
+
+    if added.is_empty() {
+        return Ok(added);
+    }
+
+    let mut output = existing;
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+    if let Some(heading) = heading {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&format!("# {}\n", heading));
+    }
+    for pattern in &added {
+        output.push_str(pattern);
+        output.push('\n');
+    }
+
+    if let Some(parent) = file.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    std::fs::write(file, output).map_err(|e| format!("{}: {}", file.display(), e))?;
+    Ok(added)
+}
+
+/// Merge a bundled template into the ignore file at `file`, skipping patterns it already has
+pub fn merge_template(file: &Path, template: &str) -> Result<Vec<String>, GustError> {
+    let (name, contents) = TEMPLATES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(template))
+        .ok_or_else(|| format!("There is no '{}' template", template))?;
+
+    let patterns: Vec<String> = contents.lines().map(|l| l.to_string()).collect();
+    append_patterns(file, &patterns, Some(&format!("{} template", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path in the temporary directory that's removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            TempFile(std::env::temp_dir().join(format!(
+                "gust-ignore-{}-{}",
+                std::process::id(),
+                name
+            )))
+        }
+
+        fn read(&self) -> String {
+            std::fs::read_to_string(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn appends_only_new_patterns() {
+        let file = TempFile::new("dedup");
+        std::fs::write(&file.0, "/target/\n*.log\n").unwrap();
+
+        let added = append_patterns(
+            &file.0,
+            &patterns(&["*.log", " *.tmp ", "*.tmp", "# note", ""]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(added, ["*.tmp"]);
+        assert_eq!(file.read(), "/target/\n*.log\n*.tmp\n");
+
+        assert!(append_patterns(&file.0, &patterns(&["*.tmp"]), None)
+            .unwrap()
+            .is_empty());
+        assert_eq!(file.read(), "/target/\n*.log\n*.tmp\n");
+    }
+
+    #[test]
+    fn ends_the_last_line_before_appending() {
+        let file = TempFile::new("newline");
+        std::fs::write(&file.0, "*.log").unwrap();
+
+        append_patterns(&file.0, &patterns(&["*.tmp"]), Some("Editors")).unwrap();
+        assert_eq!(file.read(), "*.log\n\n# Editors\n*.tmp\n");
+    }
+
+    #[test]
+    fn creates_a_missing_file() {
+        let file = TempFile::new("missing");
+        append_patterns(&file.0, &patterns(&["*.tmp"]), Some("Editors")).unwrap();
+        assert_eq!(file.read(), "# Editors\n*.tmp\n");
+    }
+
+    #[test]
+    fn merges_a_template_without_repeating_patterns() {
+        let file = TempFile::new("template");
+        std::fs::write(&file.0, "/target/\n").unwrap();
+
+        let added = merge_template(&file.0, "rust").unwrap();
+        assert!(!added.contains(&"/target/".to_string()));
+        assert!(added.contains(&"debug/".to_string()));
+        assert!(file
+            .read()
+            .starts_with("/target/\n\n# Rust template\ndebug/\n"));
+
+        assert!(merge_template(&file.0, "Rust").unwrap().is_empty());
+        assert_eq!(
+            merge_template(&file.0, "Cobol").unwrap_err().to_string(),
+            "There is no 'Cobol' template"
+        );
+    }
+
+    #[test]
+    fn builds_rules_for_each_scope() {
+        let path = Path::new("src/gen/out.rs");
+
+        let file = IgnoreRule::new(path, IgnoreScope::File).unwrap();
+        assert_eq!(file.root_pattern, "/src/gen/out.rs");
+        assert_eq!(file.nested_dir, Path::new("src/gen"));
+        assert_eq!(file.nested_pattern, "/out.rs");
+
+        let extension = IgnoreRule::new(path, IgnoreScope::Extension).unwrap();
+        assert_eq!(extension.root_pattern, "*.rs");
+        assert_eq!(extension.nested_dir, Path::new("src/gen"));
+        assert_eq!(extension.nested_pattern, "*.rs");
+
+        let directory = IgnoreRule::new(path, IgnoreScope::Directory).unwrap();
+        assert_eq!(directory.root_pattern, "/src/gen/");
+        assert_eq!(directory.nested_dir, Path::new("src"));
+        assert_eq!(directory.nested_pattern, "/gen/");
+    }
+
+    #[test]
+    fn scopes_that_dont_apply_make_no_rule() {
+        assert!(IgnoreRule::new(Path::new("Makefile"), IgnoreScope::Extension).is_none());
+        assert!(IgnoreRule::new(Path::new("Makefile"), IgnoreScope::Directory).is_none());
+    }
+
+    #[test]
+    fn resolves_paths_against_the_prefix() {
+        assert_eq!(
+            root_relative(Path::new("src/menus"), Path::new("../cli.rs")),
+            Path::new("src/cli.rs")
+        );
+    }
+
+    #[test]
+    fn parses_check_ignore_output_with_colons() {
+        let found = IgnoreMatch::parse("C:/ignore:d\x003\x00!a:b\x00a:b\x00").unwrap();
+        assert_eq!(found.source, "C:/ignore:d");
+        assert_eq!(found.line, "3");
+        assert_eq!(found.pattern, "!a:b");
+
+        assert_eq!(IgnoreMatch::parse(""), None);
+    }
+}
@@ -3,74 +3,146 @@ pub use commands::{BasicCommands, Commands};
 
 mod utils;
 
+mod backend;
+mod changelog;
 mod cli;
+mod conflicts;
+mod convention;
+mod diff;
+mod error;
+mod git;
+mod ignore;
 mod menus;
+mod prompt;
+mod rebase;
+mod release;
 mod settings;
+mod status;
+mod tui;
 
+mod args;
+
+use args::CliArguments;
+use backend::{GitBackend, GitCli};
 use clap::Parser;
 use colored::Colorize;
-
-#[derive(Parser)]
-struct CliArguments {
-    command: Option<String>,
-    args: Option<String>,
-}
+use error::GustError;
+use settings::Config;
 
 fn main() {
+    let args = CliArguments::parse();
+
+    let config = settings::load_config().unwrap_or_else(|e| exit_with(e));
+    git::configure(config.print_git_command, args.dry_run);
+    let backend = &GitCli;
+
+    // A command given on the command line runs once, reporting failure with the exit code
+    if let Some(command) = args.command {
+        let result = match backend.is_repo() {
+            Ok(true) => {
+                let menu = command.menu();
+                command.run(backend, &config).unwrap_or_else(|| match menu {
+                    Some(menu) => {
+                        settings::check_for_convention_file(&config)?;
+                        run_menu(backend, menu, &config)
+                    }
+                    None => Ok(()),
+                })
+            }
+            Ok(false) => Err(GustError::NotARepo),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result.and(settings::save_config(config)) {
+            exit_with(e);
+        }
+        return;
+    }
+
+    if let Err(e) = settings::check_for_convention_file(&config) {
+        exit_with(e);
+    }
+
     println!(
         "{}",
         "Gust - Git, with rust. A simple git helper for the average user.\n".bright_blue()
     );
     println!(
-        "{}{}{}{}",
+        "{}",
         "window 'edit' throws error when notepad exited early\n".bright_red(),
-        "missing git functionality: `diff,` branches, conflict resolution, stashes, creation, setting origin\n"
-            .bright_yellow(),
-        "Add menu for adding to gitignore".bright_blue(),
-        "\n"
-            .bright_red(),
     );
 
-    let args = CliArguments::parse();
+    let is_repo = backend.is_repo().unwrap_or_else(|e| exit_with(e));
+    if !is_repo && !menus::git_no_repo_cli(backend, &config).unwrap_or_else(|e| exit_with(e)) {
+        return;
+    }
 
-    let mut config = settings::load_config().unwrap();
+    if args.tui || config.use_tui {
+        let result = tui::run(backend, &config).and(settings::save_config(config));
+        if let Err(e) = result {
+            exit_with(e);
+        }
+        return;
+    }
 
-    settings::check_for_convention_file(&config).unwrap();
+    loop {
+        let command = if config.enable_basic_menu {
+            menus::basic_menu()
+        } else {
+            menus::advanced_menu()
+        };
 
-    let do_loop: bool = true;
-    while do_loop {
-        let command;
-        match args.command.clone() {
-            Some(com) => {
-                command = match Commands::from_string(com) {
-                    Ok(value) => value,
-                    Err(_) => {
-                        panic!("Unknown Command: {:?}", args.command.unwrap().red())
-                    }
-                }
-            }
-            None => {
-                if config.enable_basic_menu {
-                    command = menus::basic_menu().unwrap();
-                } else {
-                    command = menus::advanced_menu().unwrap();
-                }
-            }
+        let result = match command {
+            Ok(Commands::Quit) => break,
+            Ok(command) => run_menu(backend, command, &config),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            report_error(e);
         }
+        println!();
+    }
 
-        let _result = match command {
-            Commands::Add => menus::git_add_cli(&config).unwrap(),
-            Commands::Reset => menus::git_reset_cli(&config).unwrap(),
-            Commands::Commit => menus::git_commit_cli(&config).unwrap(),
-            Commands::UndoCommit => menus::git_undo_commit_cli(&config).unwrap(),
-            Commands::Branches => menus::git_branches_cli(&config).unwrap(),
-            Commands::Push => menus::git_push_cli().unwrap(),
-            Commands::Pull => menus::git_pull_cli().unwrap(),
-            Commands::Remove => menus::git_remove_cli().unwrap(),
-            Commands::Quit => break,
-        };
-        println!("");
+    if let Err(e) = settings::save_config(config) {
+        exit_with(e);
+    }
+}
+
+/// Print an error from a menu, only leaving Gust if it can't carry on
+fn report_error(error: GustError) {
+    match error {
+        GustError::Cancelled => println!("{}", "Cancelled, returning to menu".bright_yellow()),
+        e if e.is_recoverable() => eprintln!("{}", e.to_string().bright_red()),
+        e => exit_with(e),
     }
+}
+
+fn exit_with(error: GustError) -> ! {
+    eprintln!("{}", error.to_string().bright_red());
+    std::process::exit(1);
+}
 
-    settings::save_config(config).unwrap();
+fn run_menu(
+    backend: &impl GitBackend,
+    command: Commands,
+    config: &Config,
+) -> Result<(), GustError> {
+    match command {
+        Commands::Add => menus::git_add_cli(backend, config),
+        Commands::Reset => menus::git_reset_cli(backend, config),
+        Commands::Commit => menus::git_commit_cli(backend, config),
+        Commands::Diff => menus::git_diff_cli(backend, config),
+        Commands::UndoCommit => menus::git_undo_commit_cli(backend, config),
+        Commands::Branches => menus::git_branches_cli(backend, config),
+        Commands::Conflicts => menus::git_conflicts_cli(backend, config),
+        Commands::Ignore => menus::git_ignore_cli(backend, config),
+        Commands::Push => menus::git_push_cli(backend),
+        Commands::Pull => menus::git_pull_cli(backend),
+        Commands::Remotes => menus::git_remotes_cli(backend, config),
+        Commands::Remove => menus::git_remove_cli(backend),
+        Commands::Stash => menus::git_stash_cli(backend, config),
+        Commands::Rebase => menus::git_rebase_cli(backend, config),
+        Commands::Tags => menus::git_tags_cli(backend, config),
+        Commands::Release => menus::git_release_cli(backend, config),
+        Commands::Quit => Ok(()),
+    }
 }
@@ -1,117 +1,145 @@
+use crate::backend::GitBackend;
 use crate::cli;
+use crate::error::GustError;
+use crate::menus;
 use crate::settings::Config;
-use crate::utils;
+use crate::status::{self, FileEntry, FileState};
 
 use colored::Colorize;
 
-pub fn git_add_cli(config: &Config) -> Result<(), String> {
+pub fn git_add_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
     let choice_add_prompt: String = String::from("Select files to add:");
 
-    let status_output: String;
-    match cli::git_status_short()? {
-        None => {
-            println!("{}", "Nothing staged, returning to menu".bright_yellow());
-            return Ok(());
-        }
-        Some(status_string) => status_output = status_string,
+    let actions = vec![
+        "Stage files".to_string(),
+        "Stage parts of a file".to_string(),
+        "Ignore untracked files".to_string(),
+    ];
+    match cli::choice_single(actions, String::from("Select action"), false, false)? {
+        cli::UserResponse::Some(1) => return menus::git_add_hunks_cli(backend),
+        cli::UserResponse::Some(2) => return menus::ignore_untracked_cli(backend),
+        _ => {}
     }
 
-    let mut choices = Vec::<String>::new();
-    for line in status_output.lines() {
-        //println!("debug line: {}", line);
-        match line.chars().nth(1).unwrap() {
-            // No changes from HEAD or unstaged changes
-            ' ' => {
-                continue;
-            }
-            // Not tracked
-            '?' => {
-                // Remove any quotation marks caused by spaces in filenames
-                if line.chars().nth(3).unwrap() == '"' {
-                    choices.push(line[4..line.len() - 1].bright_green().to_string());
-                } else {
-                    choices.push(line[3..].bright_green().to_string());
-                }
-            }
-            // Modified from head, but not staged
-            'M' => {
-                // https://www.tutorialspoint.com/what-is-the-short-status-in-git
-                match line.chars().nth(0).unwrap() {
-                    // No staged changes, Added
-                    ' ' | 'A' => {
-                        // Remove any quotation marks caused by spaces in filenames
-                        if line.chars().nth(3).unwrap() == '"' {
-                            choices.push(line[4..line.len() - 1].green().to_string());
-                        } else {
-                            choices.push(line[3..].green().to_string());
-                        }
-                    }
-                    // Modified, Deleted, Renamed, Updated but merged
-                    'M' | 'D' | 'R' | 'U' => {
-                        // Remove any quotation marks caused by spaces in filenames
-                        if line.chars().nth(3).unwrap() == '"' {
-                            choices.push(line[4..line.len() - 1].yellow().to_string());
-                        } else {
-                            choices.push(line[3..].yellow().to_string());
-                        }
-                    }
-                    // Git was empty, but not?
-                    _ => {
-                        return Err(format!(
-                            "git add cli menu recieved unknown first char: {}",
-                            line
-                        ))
-                    }
-                }
-            }
-            // Delete, Rename
-            'D' | 'R' => {
-                // Remove any quotation marks caused by spaces in filenames
-                if line.chars().nth(3).unwrap() == '"' {
-                    choices.push(line[4..line.len() - 1].bright_yellow().to_string());
-                } else {
-                    choices.push(line[3..].bright_yellow().to_string());
-                }
-            }
-            // Added
-            'A' => {
-                // Remove any quotation marks caused by spaces in filenames
-                if line.chars().nth(3).unwrap() == '"' {
-                    choices.push(line[4..line.len() - 1].bright_red().to_string());
-                } else {
-                    choices.push(line[3..].bright_red().to_string());
-                }
-            }
-            _ => {
-                println!("ding: {}", line);
-            }
-        }
+    let repo_status = backend.status()?;
+    // Conflicted files are staged by resolving them in the Conflicts menu
+    let (conflicted, entries): (Vec<&FileEntry>, Vec<&FileEntry>) = repo_status
+        .unstaged()
+        .into_iter()
+        .partition(|e| e.is_unmerged());
+
+    if !conflicted.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "{} conflicted file(s) hidden, resolve them from the Conflicts menu",
+                conflicted.len()
+            )
+            .bright_red()
+        );
     }
 
-    if choices.len() == 0 {
+    if entries.is_empty() {
         println!("{}", "Nothing staged, returning to menu".bright_yellow());
         return Ok(());
     }
 
+    let choices: Vec<String> = entries.iter().map(|e| color_entry(e)).collect();
+
     let usr_selected = cli::choice_no_limit(
-        choices.clone(),
+        choices,
         choice_add_prompt,
         true,
         config.show_all_in_add_menu,
     )?;
-    return match usr_selected {
+    match usr_selected {
         cli::UserResponse::None => {
             println!("None selected, returning");
             Ok(())
         }
         cli::UserResponse::Some(choice) => {
-            if choice.len() == 0 {
+            if choice.is_empty() {
                 println!("{}", "None selected".bright_yellow());
                 Ok(())
             } else {
-                cli::git_add(choice)
+                let selected = status::entries_from_labels(&entries, &choice);
+                let toplevel = backend.toplevel()?;
+                backend.add(selected.iter().map(|e| toplevel.join(&e.path)).collect())
             }
         }
-        cli::UserResponse::All => cli::git_add(utils::strip_vec_colors(choices)),
-    };
+        cli::UserResponse::All => {
+            let toplevel = backend.toplevel()?;
+            backend.add(entries.iter().map(|e| toplevel.join(&e.path)).collect())
+        }
+    }
+}
+
+fn color_entry(entry: &FileEntry) -> String {
+    let label = entry.label();
+    // https://git-scm.com/docs/git-status#_porcelain_format_version_2
+    match (entry.index, entry.worktree) {
+        // Not tracked
+        (_, FileState::Untracked) => label.bright_green().to_string(),
+        // Delete, Rename
+        (_, FileState::Deleted) | (_, FileState::Renamed) => label.bright_yellow().to_string(),
+        // Added with intent to add
+        (_, FileState::Added) => label.bright_red().to_string(),
+        // No staged changes, Added
+        (FileState::Unmodified, _) | (FileState::Added, _) => label.green().to_string(),
+        // Modified, Deleted, Renamed
+        _ => label.yellow().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::FakeBackend;
+    use crate::prompt::scripted::{script, Answer};
+
+    use std::path::{Path, PathBuf};
+
+    /// `tracked.txt` is modified and `new.txt` untracked
+    fn backend() -> FakeBackend {
+        let backend = FakeBackend::new(PathBuf::from("/repo"));
+        backend.commit_file("tracked.txt", "one\n", "Add tracked.txt");
+        backend.write("tracked.txt", Some("two\n"));
+        backend.write("new.txt", Some("new\n"));
+        backend
+    }
+
+    fn index(backend: &FakeBackend, path: &str) -> Option<String> {
+        backend.repo.borrow().files[Path::new(path)].index.clone()
+    }
+
+    #[test]
+    fn stages_the_selected_files() {
+        let backend = backend();
+        // "All", new.txt, tracked.txt, "None"
+        script(vec![Answer::Select(0), Answer::MultiSelect(vec![2])]);
+        git_add_cli(&backend, &Config::default()).unwrap();
+
+        assert_eq!(backend.calls(), vec!["git add -- /repo/tracked.txt"]);
+        assert_eq!(index(&backend, "tracked.txt").as_deref(), Some("two\n"));
+        assert_eq!(index(&backend, "new.txt"), None);
+    }
+
+    #[test]
+    fn stages_everything_with_all() {
+        let backend = backend();
+        script(vec![Answer::Select(0), Answer::MultiSelect(vec![0])]);
+        git_add_cli(&backend, &Config::default()).unwrap();
+
+        assert_eq!(index(&backend, "tracked.txt").as_deref(), Some("two\n"));
+        assert_eq!(index(&backend, "new.txt").as_deref(), Some("new\n"));
+    }
+
+    #[test]
+    fn stages_nothing_with_none() {
+        let backend = backend();
+        script(vec![Answer::Select(0), Answer::MultiSelect(vec![1, 3])]);
+        git_add_cli(&backend, &Config::default()).unwrap();
+
+        assert!(backend.calls().is_empty());
+    }
 }
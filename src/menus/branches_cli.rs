@@ -1,70 +1,63 @@
-use crate::cli::{self, UserResponse};
+use crate::backend::GitBackend;
+use crate::cli::{self, BranchInfo, UserResponse};
+use crate::error::GustError;
+use crate::menus;
 use crate::settings::Config;
+use crate::status::Operation;
 use crate::utils;
 
 use colored::Colorize;
-use console;
 
-pub fn git_branches_cli(_config: &Config) -> Result<(), String> {
+pub fn git_branches_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
     let stdout = console::Term::stdout();
 
     let choices = vec![
         "Switch HEAD".to_string(),
         "Create new branch".to_string(),
         "Delete a Branch".to_string(),
+        "Cherry-pick commits from a branch".to_string(),
+        "Merge a branch into the current one".to_string(),
+        "Rebase the current branch onto another".to_string(),
+        "Rename a branch".to_string(),
+        "Set or unset a branch's upstream".to_string(),
     ];
 
     let choice = cli::choice_single(choices, String::from("Select action"), false, false)?;
 
-    stdout.clear_last_lines(1).map_err(|e| e.to_string())?;
+    // Only a terminal shows the prompt that gets replaced
+    if stdout.is_term() {
+        stdout.clear_last_lines(1)?;
+    }
 
-    println!(
-        "{} {}: {}",
-        "✓".bright_green().to_string(),
-        "Select action".bold().to_string(),
-        {
-            match choice {
-                UserResponse::Some(val) => match val {
-                    0 => "Switch HEAD",
-                    1 => "Create New Branch",
-                    2 => "Delete a Branch",
-                    _ => "Invalid Input",
-                },
+    println!("{} {}: {}", "✓".bright_green(), "Select action".bold(), {
+        match choice {
+            UserResponse::Some(val) => match val {
+                0 => "Switch HEAD",
+                1 => "Create New Branch",
+                2 => "Delete a Branch",
+                3 => "Cherry-pick Commits",
+                4 => "Merge into Current",
+                5 => "Rebase Onto",
+                6 => "Rename a Branch",
+                7 => "Set Upstream",
                 _ => "Invalid Input",
-            }
+            },
+            _ => "Invalid Input",
         }
-    );
+    });
 
     match choice {
         UserResponse::Some(val) => match val {
-            0 => match cli::git_get_branches()? {
-                None => println!("{}", "You have no branches here".bright_red()),
-                Some(mut branches) => {
-                    let target = cli::choice_single(
-                        branches.clone(),
-                        "Select branch you wish to switch to"
-                            .bright_yellow()
-                            .to_string(),
-                        false,
-                        false,
-                    )?;
-
-                    match target {
-                        UserResponse::Some(index) => {
-                            let branch = utils::strip_colors(branches[index].clone());
-                            if branch.contains("remotes/") {
-                                cli::git_fetch()?;
-                            }
-
-                            branches.clear();
-                            branches.push(branch);
-                            cli::git_checkout(branches)?;
-                            return Ok(());
-                        }
-                        _ => return Err("How did you break that one!?".to_string()),
-                    }
-                }
-            },
+            0 => {
+                let branch = choose_branch(
+                    backend,
+                    "Select branch you wish to switch to"
+                        .bright_yellow()
+                        .to_string(),
+                )?;
+                backend.checkout(vec![branch])?;
+                return Ok(());
+            }
             1 => {
                 let specific_commit_prompt =
                     String::from("Would you like to start the branch on a specific commit?");
@@ -79,121 +72,1057 @@ pub fn git_branches_cli(_config: &Config) -> Result<(), String> {
                         .to_string();
 
                     // Get the branch that the commit is on (for ease of use)
-                    let branch = match cli::git_get_branches()? {
-                        // Make sure there is a response from `git branch`
-                        Some(branches) => {
-                            // get the user to choose a branch
-                            match cli::choice_single(
-                                branches.clone(),
-                                select_branch_prompt,
-                                false,
-                                false,
-                            )? {
-                                UserResponse::Some(index) => {
-                                    let branch = utils::strip_colors(branches[index].clone());
-                                    if branch.contains("remotes/") {
-                                        cli::git_fetch()?;
-                                    }
-
-                                    branch
-                                }
-                                _ => panic!("You should not be in this specfics place 121212"),
-                            }
-                        }
-                        None => {
-                            panic!("{}", "You have no branches here".bright_red());
-                        }
-                    };
-
-                    // Get the `git log` history on the chosen branch
-                    let choices = match cli::git_log(Some(branch))? {
-                        None => {
-                            println!("{}", "No commits found! returning to menu".bright_yellow());
-                            return Ok(());
-                        }
-                        Some(log_string) => {
-                            let mut output = Vec::<String>::new();
-                            for line in log_string.lines() {
-                                output.push(line.to_string());
-                            }
-                            output
-                        }
-                    };
+                    let branch = choose_branch(backend, select_branch_prompt)?;
 
                     // Have the user select the commit to base the branch on
-                    let usr_selected =
-                        cli::choice_single(choices.clone(), select_commit_prompt, false, true)?;
+                    if let Some(hash) =
+                        menus::choose_commit(backend, Some(branch), select_commit_prompt)?
+                    {
+                        println!("You have selected commit {}", hash);
 
-                    match usr_selected {
-                        UserResponse::None => {
-                            println!("'None' selected, returning to menu");
-                            return Ok(());
-                        }
-                        UserResponse::Some(choice) => {
-                            let hash = choices[choice][..7].to_string();
-                            println!("You have selected commit {}", hash);
-
-                            let name = cli::get_input("Enter new branch name: ".to_string())?;
+                        let name = cli::get_input("Enter new branch name: ".to_string())?;
 
-                            cli::git_create_branch(name, Some(hash))?;
-
-                            return Ok(());
-                        }
-                        UserResponse::All => panic!(
-                            "This code should be unreachable cli.rs - git-branch, usr_selected"
-                        ),
+                        backend.create_branch(name, Some(hash))?;
                     }
+                    return Ok(());
                 } else {
                     let name = cli::get_input("Enter new branch name: ".to_string())?;
-                    cli::git_create_branch(name, None)?;
+                    backend.create_branch(name, None)?;
                 }
             }
-            2 => match cli::git_get_branches()? {
-                None => println!("{}", "You have no branches here".bright_red()),
-                Some(branches) => {
-                    let target = cli::choice_single(
-                        branches.clone(),
-                        "Select the branch you wish to delete"
-                            .bright_yellow()
-                            .to_string(),
-                        false,
-                        false,
-                    )?;
-
-                    match target {
-                        UserResponse::Some(index) => {
-                            let branch = utils::strip_colors(branches[index].clone());
-
-                            match cli::ask_yes_no(
-                                format!("Are you sure you wish to delete this branch?: {}", branch),
-                                false,
-                            ) {
-                                Err(err) => {
-                                    println!("{}: {}", "There was an error getting user's consent to delete the branch".bright_red().to_string(), err.as_str());
+            2 => {
+                // Only local branches can be deleted, and never the checked out one
+                let branches: Vec<BranchInfo> = backend
+                    .branches()?
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|b| !b.remote && !b.current)
+                    .collect();
+                if branches.is_empty() {
+                    println!("{}", "You have no other branches here".bright_red());
+                    return Ok(());
+                }
+
+                let target = pick_branch(
+                    &branches,
+                    "Select the branch you wish to delete"
+                        .bright_yellow()
+                        .to_string(),
+                )?;
+
+                match target {
+                    Some(branch) => {
+                        match cli::ask_yes_no(
+                            format!(
+                                "Are you sure you wish to delete this branch?: {}",
+                                branch.name
+                            ),
+                            false,
+                        ) {
+                            Err(err) => {
+                                println!("{}: {}", "There was an error getting user's consent to delete the branch".bright_red(), err);
+                                return Ok(());
+                            }
+                            Ok(yes_bool) => {
+                                if yes_bool {
+                                    backend.delete_branch(branch.name)?;
+                                } else {
+                                    println!("User canceled the branch deletion");
                                     return Ok(());
                                 }
-                                Ok(yes_bool) => {
-                                    if yes_bool {
-                                        cli::git_branch_delete(branch)?;
-                                    } else {
-                                        println!("User canceled the branch deletion");
-                                        return Ok(());
-                                    }
-                                }
                             }
                         }
-                        _ => {
-                            return Err(
-                                "There was an error while user choosing the branch".to_string()
-                            )
-                        }
                     }
+                    None => return Err(GustError::Cancelled),
                 }
-            },
-            _ => return Err("Wow, I don't even know what to say...\n Goodbye".to_string()),
+            }
+            3 => return cherry_pick_cli(backend, config),
+            4 => return merge_cli(backend, config),
+            5 => return rebase_onto_cli(backend, config),
+            6 => return rename_cli(backend),
+            7 => return upstream_cli(backend),
+            _ => return Err(GustError::Cancelled),
         },
-        _ => return Err("You achieved the impossible".to_string()),
+        _ => return Err(GustError::Cancelled),
+    }
+
+    Ok(())
+}
+
+/// Have the user pick a branch, fetching first when it's a remote one
+fn choose_branch(backend: &impl GitBackend, prompt: String) -> Result<String, GustError> {
+    let branches = match backend.branches()? {
+        Some(branches) if !branches.is_empty() => branches,
+        _ => return Err("You have no branches here".into()),
+    };
+
+    match pick_branch(&branches, prompt)? {
+        Some(branch) => {
+            if branch.remote {
+                backend.fetch(None)?;
+            }
+            Ok(branch.name)
+        }
+        None => Err(GustError::Cancelled),
+    }
+}
+
+/// Show `branches` with their upstream and last commit, `None` if the user backed out
+fn pick_branch(branches: &[BranchInfo], prompt: String) -> Result<Option<BranchInfo>, GustError> {
+    let choices: Vec<String> = branches.iter().map(|b| b.label()).collect();
+    match cli::choice_single(choices, prompt, false, false)? {
+        UserResponse::Some(index) => Ok(branches.get(index).cloned()),
+        _ => Ok(None),
+    }
+}
+
+/// Have the user pick a remote-tracking branch to use as an upstream, e.g. `origin/main`
+pub fn choose_remote_branch(
+    backend: &impl GitBackend,
+    prompt: String,
+) -> Result<Option<String>, GustError> {
+    let branches: Vec<BranchInfo> = backend
+        .branches()?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|b| b.remote)
+        .collect();
+    if branches.is_empty() {
+        println!(
+            "{}",
+            "No remote branches found, fetch or push the branch first".bright_yellow()
+        );
+        return Ok(None);
+    }
+
+    let choices: Vec<String> = branches.iter().map(|b| b.label()).collect();
+    match cli::choice_single(choices, prompt, false, true)? {
+        UserResponse::Some(index) => Ok(branches[index]
+            .name
+            .strip_prefix("remotes/")
+            .map(|b| b.to_string())),
+        _ => {
+            println!("'None' selected, returning to menu");
+            Ok(None)
+        }
+    }
+}
+
+/// The local branches, for the actions that only make sense on those
+fn local_branches(backend: &impl GitBackend) -> Result<Vec<BranchInfo>, GustError> {
+    let branches: Vec<BranchInfo> = backend
+        .branches()?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|b| !b.remote)
+        .collect();
+    if branches.is_empty() {
+        return Err("You have no branches here".into());
+    }
+    Ok(branches)
+}
+
+fn rename_cli(backend: &impl GitBackend) -> Result<(), GustError> {
+    let branches = local_branches(backend)?;
+    let branch = match pick_branch(
+        &branches,
+        "Select the branch to rename".bright_yellow().to_string(),
+    )? {
+        Some(branch) => branch,
+        None => return Err(GustError::Cancelled),
+    };
+
+    let new_name = cli::get_input(format!("Enter the new name for '{}': ", branch.name))?;
+    let new_name = new_name.trim().to_string();
+    if new_name.is_empty() || new_name == branch.name {
+        println!("User canceled renaming the branch");
+        return Ok(());
+    }
+    if branches.iter().any(|b| b.name == new_name) {
+        return Err(format!("The branch {} already exists", new_name).into());
     }
 
+    backend.rename_branch(branch.name.clone(), new_name.clone())?;
+    println!(
+        "{}",
+        format!("Renamed '{}' to '{}'", branch.name, new_name).bright_green()
+    );
+    if let Some(upstream) = branch.upstream {
+        // The remote still has the branch under its old name
+        println!(
+            "'{}' still tracks '{}', push it under the new name and set the upstream to rename it there too",
+            new_name, upstream
+        );
+    }
     Ok(())
 }
+
+/// Pick which remote branch a local branch pulls from and pushes to, or stop tracking one
+fn upstream_cli(backend: &impl GitBackend) -> Result<(), GustError> {
+    let branches = local_branches(backend)?;
+    let branch = match pick_branch(
+        &branches,
+        "Select the branch to change the upstream of"
+            .bright_yellow()
+            .to_string(),
+    )? {
+        Some(branch) => branch,
+        None => return Err(GustError::Cancelled),
+    };
+
+    let unset = match &branch.upstream {
+        None => false,
+        Some(upstream) => {
+            let choices = vec![
+                "Track a different remote branch".to_string(),
+                format!("Stop tracking '{}'", upstream),
+            ];
+            match cli::choice_single(choices, String::from("Select action"), false, true)? {
+                UserResponse::Some(index) => index == 1,
+                _ => {
+                    println!("'None' selected, returning to menu");
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    if unset {
+        backend.unset_upstream(Some(branch.name.clone()))?;
+        println!(
+            "{}",
+            format!("'{}' no longer has an upstream", branch.name).bright_green()
+        );
+        return Ok(());
+    }
+
+    let prompt = format!("Select the upstream for '{}'", branch.name)
+        .bright_yellow()
+        .to_string();
+    if let Some(upstream) = choose_remote_branch(backend, prompt)? {
+        backend.set_upstream(Some(branch.name.clone()), upstream.clone())?;
+        println!(
+            "{}",
+            format!("'{}' now tracks '{}'", branch.name, upstream).bright_green()
+        );
+    }
+    Ok(())
+}
+
+/// Copy commits from another branch onto the current one, oldest first
+fn cherry_pick_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    let branch = choose_branch(
+        backend,
+        "Select the branch to take commits from:"
+            .bright_yellow()
+            .to_string(),
+    )?;
+
+    // Only the commits the current branch doesn't have yet
+    let choices: Vec<String> = match backend.log(Some(format!("HEAD..{}", branch)))? {
+        None => {
+            println!(
+                "{}",
+                "That branch has no commits the current branch is missing".bright_yellow()
+            );
+            return Ok(());
+        }
+        Some(log_string) => log_string.lines().map(|l| l.to_string()).collect(),
+    };
+
+    let selected = match cli::choice_no_limit(
+        choices.clone(),
+        "Select the commits to cherry-pick".to_string(),
+        true,
+        false,
+    )? {
+        UserResponse::Some(selected) if !selected.is_empty() => selected,
+        _ => {
+            println!("'None' selected, returning to menu");
+            return Ok(());
+        }
+    };
+
+    // The log is newest first, git should apply them oldest first
+    let commits: Vec<String> = choices
+        .iter()
+        .rev()
+        .filter(|c| selected.contains(&utils::strip_colors(c.to_string())))
+        .filter_map(|c| c.split_whitespace().next())
+        .map(|hash| hash.to_string())
+        .collect();
+
+    let record_origin = cli::ask_yes_no(
+        "Note where each commit came from in its message (-x)?".to_string(),
+        false,
+    )?;
+
+    match backend.cherry_pick(commits, record_origin) {
+        Ok(()) => {
+            println!("{}", "Commits cherry-picked!".bright_green());
+            Ok(())
+        }
+        Err(e) if backend.operation_in_progress()? == Some(Operation::CherryPick) => {
+            println!("{}", e.to_string().bright_red());
+            menus::guide_operation_cli(backend, config, Operation::CherryPick)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Merge another branch into the current one, after showing what it brings in
+fn merge_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    if let Some(operation) = backend.operation_in_progress()? {
+        return Err(format!("Finish the {} in progress before merging", operation).into());
+    }
+    let current = match backend.status()?.branch.head {
+        Some(current) => current,
+        None => return Err("HEAD is detached, switch to a branch to merge into first".into()),
+    };
+
+    let branch = choose_branch(
+        backend,
+        format!("Select the branch to merge into '{}':", current)
+            .bright_yellow()
+            .to_string(),
+    )?;
+    if branch == current {
+        return Err("A branch can't be merged into itself".into());
+    }
+
+    let incoming: Vec<String> = match backend.log(Some(format!("HEAD..{}", branch)))? {
+        Some(log) => log.lines().map(|l| l.to_string()).collect(),
+        None => {
+            println!(
+                "{}",
+                format!("'{}' is already up to date with '{}'", current, branch).bright_green()
+            );
+            return Ok(());
+        }
+    };
+    println!(
+        "{}",
+        format!(
+            "{} commit(s) will come in from '{}':",
+            incoming.len(),
+            branch
+        )
+        .bold()
+    );
+    for commit in &incoming {
+        println!("  {}", commit);
+    }
+    // Three dots: the changes on the branch since it split off, not the ones made here since
+    if let Some(stat) = backend.diff(
+        vec!["--stat".to_string(), format!("HEAD...{}", branch)],
+        Vec::<String>::new(),
+    )? {
+        print!("{}", stat);
+    }
+
+    // Nothing new on the current branch means it can simply move forward
+    let can_fast_forward = backend.log(Some(format!("{}..HEAD", branch)))?.is_none();
+    let mut strategies = vec![];
+    if can_fast_forward {
+        strategies.push(("Fast-forward only, no merge commit", "--ff-only"));
+    }
+    strategies.push(("Always create a merge commit (--no-ff)", "--no-ff"));
+    strategies.push(("Squash everything into one commit", "--squash"));
+
+    let choices = strategies
+        .iter()
+        .map(|(label, _)| label.to_string())
+        .collect();
+    let strategy =
+        match cli::choice_single(choices, String::from("Select how to merge"), false, true)? {
+            UserResponse::Some(index) => strategies[index].1,
+            _ => {
+                println!("'None' selected, returning to menu");
+                return Ok(());
+            }
+        };
+
+    // The incoming commits make a good start for the description
+    let suggestion = format!("merge branch '{}'", branch);
+    let body = incoming
+        .iter()
+        .map(|c| format!("- {}", c))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let message = if strategy == "--ff-only" || config.use_git_commit_message_dialog {
+        vec![]
+    } else {
+        menus::compose_message_cli(backend, config, &suggestion, &body)?
+            .into_iter()
+            .flat_map(|p| vec!["-m".to_string(), p])
+            .collect()
+    };
+
+    let mut args = vec![strategy.to_string()];
+    if strategy == "--no-ff" {
+        args.extend(message.clone());
+    }
+    args.push(branch.clone());
+
+    match backend.merge(args) {
+        Ok(()) if strategy == "--squash" => {
+            // A squash only stages the changes, the commit is made here
+            backend.commit(Some(message).filter(|m| !m.is_empty()), config)?;
+            println!(
+                "{}",
+                format!("Squashed '{}' into one commit!", branch).bright_green()
+            );
+            Ok(())
+        }
+        Ok(()) => {
+            println!("{}", format!("Merged '{}'!", branch).bright_green());
+            Ok(())
+        }
+        Err(e) if backend.operation_in_progress()? == Some(Operation::Merge) => {
+            println!("{}", e.to_string().bright_red());
+            menus::guide_operation_cli(backend, config, Operation::Merge)
+        }
+        Err(e) if strategy == "--squash" && has_conflicts(backend)? => {
+            println!("{}", e.to_string().bright_red());
+            squash_conflicts_cli(backend, config, message)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Replay the current branch's own commits on top of another branch
+fn rebase_onto_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    if let Some(operation) = backend.operation_in_progress()? {
+        return Err(format!("Finish the {} in progress before rebasing", operation).into());
+    }
+    let repo_status = backend.status()?;
+    let current = match repo_status.branch.head.clone() {
+        Some(current) => current,
+        None => return Err("HEAD is detached, switch to the branch to rebase first".into()),
+    };
+
+    let target = choose_branch(
+        backend,
+        format!("Select the branch to rebase '{}' onto:", current)
+            .bright_yellow()
+            .to_string(),
+    )?;
+    if target == current {
+        return Err("A branch can't be rebased onto itself".into());
+    }
+
+    if backend.log(Some(format!("HEAD..{}", target)))?.is_none() {
+        println!(
+            "{}",
+            format!("'{}' already includes everything on '{}'", current, target).bright_green()
+        );
+        return Ok(());
+    }
+    let replayed: Vec<String> = backend
+        .log(Some(format!("{}..HEAD", target)))?
+        .map(|log| log.lines().map(|l| l.to_string()).collect())
+        .unwrap_or_default();
+    match replayed.last() {
+        None => println!(
+            "'{}' has no commits of its own, it will fast-forward to '{}'",
+            current, target
+        ),
+        Some(oldest) => {
+            println!(
+                "{}",
+                format!(
+                    "{} commit(s) will be replayed onto '{}':",
+                    replayed.len(),
+                    target
+                )
+                .bold()
+            );
+            for commit in &replayed {
+                println!("  {}", commit);
+            }
+            let hash = oldest.split_whitespace().next().unwrap_or_default();
+            if !menus::confirm_rewrite(backend, hash)? {
+                return Err(GustError::Cancelled);
+            }
+        }
+    }
+
+    let mut args = vec![];
+    let has_changes = repo_status.entries.iter().any(|e| !e.is_untracked());
+    if has_changes
+        && cli::ask_yes_no(
+            "Stash your local changes during the rebase (--autostash)?".to_string(),
+            true,
+        )?
+    {
+        args.push("--autostash".to_string());
+    }
+    args.push(target.clone());
+
+    match backend.rebase(args, None) {
+        Ok(()) => {
+            println!(
+                "{}",
+                format!("Rebased '{}' onto '{}'!", current, target).bright_green()
+            );
+            Ok(())
+        }
+        Err(e) if backend.operation_in_progress()? == Some(Operation::Rebase) => {
+            println!("{}", e.to_string().bright_red());
+            menus::guide_operation_cli(backend, config, Operation::Rebase)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn has_conflicts(backend: &impl GitBackend) -> Result<bool, GustError> {
+    Ok(backend.status()?.entries.iter().any(|e| e.is_unmerged()))
+}
+
+/// A squash merge that stopped on conflicts isn't a merge in progress for git,
+/// so there is no `merge --continue` or `--abort`. Resolve and commit, or undo it.
+fn squash_conflicts_cli(
+    backend: &impl GitBackend,
+    config: &Config,
+    message: Vec<String>,
+) -> Result<(), GustError> {
+    loop {
+        let choices = vec![
+            "Resolve the conflicts".to_string(),
+            "Abort the squash".to_string(),
+            "Leave it for now".to_string(),
+        ];
+        match cli::choice_single(
+            choices,
+            String::from("The squash stopped on conflicts"),
+            false,
+            false,
+        )? {
+            UserResponse::Some(0) => {
+                menus::git_conflicts_cli(backend, config)?;
+                if !has_conflicts(backend)? {
+                    backend.commit(Some(message).filter(|m| !m.is_empty()), config)?;
+                    println!("{}", "Squash committed!".bright_green());
+                    return Ok(());
+                }
+            }
+            UserResponse::Some(1) => {
+                backend.reset_merge()?;
+                println!("{}", "The squash was aborted".bright_yellow());
+                return Ok(());
+            }
+            _ => {
+                println!(
+                    "{}",
+                    "Resolve the conflicts from the Conflicts menu, then commit".bright_yellow()
+                );
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::FakeBackend;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+    use crate::prompt::scripted::{script, Answer};
+
+    use std::path::PathBuf;
+
+    /// On `main`, with `feature` and `old` next to it and `origin/main` on the remote
+    fn backend() -> FakeBackend {
+        let backend = FakeBackend::new(PathBuf::from("/repo"));
+        backend.commit_file("a.txt", "a\n", "Add a.txt");
+        {
+            let mut repo = backend.repo.borrow_mut();
+            repo.branches
+                .extend(["feature".to_string(), "old".to_string()]);
+            repo.remote_branches.push("origin/main".to_string());
+        }
+        backend
+    }
+
+    fn branches(backend: &FakeBackend) -> Vec<String> {
+        backend.repo.borrow().branches.clone()
+    }
+
+    #[test]
+    fn switches_branches() {
+        let backend = backend();
+        script(vec![Answer::Select(0), Answer::Select(1)]);
+        git_branches_cli(&backend, &Config::default()).unwrap();
+
+        assert_eq!(backend.calls(), vec!["git checkout feature"]);
+        assert_eq!(
+            backend.repo.borrow().current_branch.as_deref(),
+            Some("feature")
+        );
+    }
+
+    #[test]
+    fn dismissing_a_question_cancels() {
+        let backend = backend();
+        script(vec![Answer::Select(0), Answer::Cancel]);
+        assert!(matches!(
+            git_branches_cli(&backend, &Config::default()),
+            Err(GustError::Cancelled)
+        ));
+        assert!(backend.calls().is_empty());
+    }
+
+    #[test]
+    fn creates_a_branch_at_head() {
+        let backend = backend();
+        script(vec![
+            Answer::Select(1),
+            Answer::Confirm(false),
+            Answer::Text("topic".to_string()),
+        ]);
+        git_branches_cli(&backend, &Config::default()).unwrap();
+
+        assert_eq!(backend.calls(), vec!["git branch topic"]);
+        assert_eq!(branches(&backend), vec!["main", "feature", "old", "topic"]);
+    }
+
+    #[test]
+    fn deletes_a_branch_once_confirmed() {
+        // The checked out branch isn't offered
+        let backend = backend();
+        script(vec![
+            Answer::Select(2),
+            Answer::Select(1),
+            Answer::Confirm(true),
+        ]);
+        git_branches_cli(&backend, &Config::default()).unwrap();
+        assert_eq!(branches(&backend), vec!["main", "feature"]);
+
+        script(vec![
+            Answer::Select(2),
+            Answer::Select(0),
+            Answer::Confirm(false),
+        ]);
+        git_branches_cli(&backend, &Config::default()).unwrap();
+        assert_eq!(branches(&backend), vec!["main", "feature"]);
+        assert_eq!(backend.calls(), vec!["git branch -D old"]);
+    }
+
+    #[test]
+    fn renames_a_branch() {
+        let backend = backend();
+        script(vec![
+            Answer::Select(6),
+            Answer::Select(0),
+            Answer::Text("trunk".to_string()),
+        ]);
+        git_branches_cli(&backend, &Config::default()).unwrap();
+
+        assert_eq!(branches(&backend), vec!["trunk", "feature", "old"]);
+        assert_eq!(
+            backend.repo.borrow().current_branch.as_deref(),
+            Some("trunk")
+        );
+    }
+
+    #[test]
+    fn refuses_to_rename_onto_an_existing_branch() {
+        let backend = backend();
+        script(vec![
+            Answer::Select(6),
+            Answer::Select(1),
+            Answer::Text("old".to_string()),
+        ]);
+        assert!(git_branches_cli(&backend, &Config::default()).is_err());
+        assert!(backend.calls().is_empty());
+    }
+
+    #[test]
+    fn sets_and_unsets_the_upstream() {
+        let backend = backend();
+        script(vec![
+            Answer::Select(7),
+            Answer::Select(0),
+            Answer::Select(0),
+        ]);
+        git_branches_cli(&backend, &Config::default()).unwrap();
+        assert_eq!(
+            backend.repo.borrow().upstream.as_deref(),
+            Some("origin/main")
+        );
+
+        // A branch with an upstream can also stop tracking it
+        script(vec![
+            Answer::Select(7),
+            Answer::Select(0),
+            Answer::Select(1),
+        ]);
+        git_branches_cli(&backend, &Config::default()).unwrap();
+        assert_eq!(backend.repo.borrow().upstream, None);
+        assert_eq!(
+            backend.calls(),
+            vec![
+                "git branch --set-upstream-to=origin/main",
+                "git branch --unset-upstream main",
+            ]
+        );
+    }
+
+    #[test]
+    fn creates_switches_and_deletes_branches_in_a_repository() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        let current = || repo.git(&["branch", "--show-current"]);
+
+        script(vec![
+            Answer::Select(1),
+            Answer::Confirm(false),
+            Answer::Text("topic".to_string()),
+        ]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(
+            repo.git(&["branch", "--format=%(refname:short)"]),
+            "main\ntopic"
+        );
+
+        // git lists the branches by name
+        script(vec![Answer::Select(0), Answer::Select(1)]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(current(), "topic");
+
+        script(vec![Answer::Select(0), Answer::Select(0)]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+        script(vec![
+            Answer::Select(2),
+            Answer::Select(0),
+            Answer::Confirm(true),
+        ]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(current(), "main");
+        assert_eq!(repo.git(&["branch", "--format=%(refname:short)"]), "main");
+    }
+
+    #[test]
+    fn renames_a_branch_in_a_repository() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+
+        script(vec![
+            Answer::Select(6),
+            Answer::Select(0),
+            Answer::Text("trunk".to_string()),
+        ]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(repo.git(&["branch", "--show-current"]), "trunk");
+    }
+
+    /// `main` with `a.txt`, and `topic` adding `b.txt`, `c.txt` and `d.txt` on top of it
+    fn topic_repo() -> TestRepo {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        repo.git(&["switch", "--quiet", "-c", "topic"]);
+        for name in ["b", "c", "d"] {
+            let path = format!("{}.txt", name);
+            repo.commit_file(&path, "topic\n", &format!("Add {}", path));
+        }
+        repo.git(&["switch", "--quiet", "main"]);
+        repo
+    }
+
+    #[test]
+    fn cherry_picks_commits_oldest_first() {
+        let repo = topic_repo();
+
+        // The log lists `topic` newest first: d, c, b
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(1),
+            Answer::MultiSelect(vec![0, 2]),
+            Answer::Confirm(false),
+        ]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(
+            repo.git(&["log", "--format=%s"]),
+            "Add d.txt\nAdd b.txt\nAdd a.txt"
+        );
+        assert!(!repo.work_tree.join("c.txt").exists());
+        assert!(!repo
+            .git(&["log", "-1", "--format=%B"])
+            .contains("cherry picked"));
+    }
+
+    #[test]
+    fn cherry_picks_noting_where_the_commit_came_from() {
+        let repo = topic_repo();
+        let picked = repo.git(&["rev-parse", "topic"]);
+
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(1),
+            Answer::MultiSelect(vec![0]),
+            Answer::Confirm(true),
+        ]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(
+            repo.git(&["log", "-1", "--format=%B"]),
+            format!("Add d.txt\n\n(cherry picked from commit {})", picked)
+        );
+    }
+
+    #[test]
+    fn hands_a_conflicting_cherry_pick_to_the_guide() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        repo.git(&["switch", "--quiet", "-c", "topic"]);
+        repo.commit_file("a.txt", "topic\n", "Change a.txt on topic");
+        repo.git(&["switch", "--quiet", "main"]);
+        repo.commit_file("a.txt", "main\n", "Change a.txt on main");
+        let head = repo.git(&["rev-parse", "HEAD"]);
+
+        // Leaving keeps the cherry-pick going
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(1),
+            Answer::MultiSelect(vec![0]),
+            Answer::Confirm(false),
+            Answer::Choose("Leave it for now".to_string()),
+        ]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(
+            GitCli.operation_in_progress().unwrap(),
+            Some(Operation::CherryPick)
+        );
+
+        // Aborting it from the guide puts everything back
+        repo.git(&["cherry-pick", "--abort"]);
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(1),
+            Answer::MultiSelect(vec![0]),
+            Answer::Confirm(false),
+            Answer::Choose("Abort the cherry-pick".to_string()),
+        ]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(GitCli.operation_in_progress().unwrap(), None);
+        assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+        assert_eq!(std::fs::read_to_string("a.txt").unwrap(), "main\n");
+    }
+
+    /// The answers describing a merge of `topic` as a `chore` without a scope
+    fn merge_message() -> Vec<Answer> {
+        vec![
+            Answer::Choose("chore".to_string()),
+            Answer::Choose("None".to_string()),
+            Answer::Confirm(false),
+            Answer::Text("chore: merge branch 'topic'".to_string()),
+            Answer::Edit(Some("Bring in the topic".to_string())),
+        ]
+    }
+
+    /// `main` and `topic` both changing `a.txt` since they split
+    fn conflicting_repo() -> TestRepo {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        repo.git(&["switch", "--quiet", "-c", "topic"]);
+        repo.commit_file("a.txt", "topic\n", "Change a.txt on topic");
+        repo.git(&["switch", "--quiet", "main"]);
+        repo.commit_file("a.txt", "main\n", "Change a.txt on main");
+        repo
+    }
+
+    #[test]
+    fn fast_forwards_a_merge() {
+        let repo = topic_repo();
+
+        script(vec![
+            Answer::Select(4),
+            Answer::Select(1),
+            Answer::Choose("Fast-forward only, no merge commit".to_string()),
+        ]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(
+            repo.git(&["rev-parse", "main"]),
+            repo.git(&["rev-parse", "topic"])
+        );
+    }
+
+    #[test]
+    fn merges_with_a_merge_commit() {
+        let repo = topic_repo();
+        let topic = repo.git(&["rev-parse", "topic"]);
+
+        let mut answers = vec![
+            Answer::Select(4),
+            Answer::Select(1),
+            Answer::Choose("Always create a merge commit (--no-ff)".to_string()),
+        ];
+        answers.extend(merge_message());
+        script(answers);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(repo.git(&["rev-parse", "HEAD^2"]), topic);
+        assert_eq!(
+            repo.git(&["log", "-1", "--format=%B"]),
+            "chore: merge branch 'topic'\n\nBring in the topic"
+        );
+    }
+
+    #[test]
+    fn squashes_a_merge_into_one_commit() {
+        let repo = topic_repo();
+        let before = repo.git(&["rev-parse", "HEAD"]);
+
+        let mut answers = vec![
+            Answer::Select(4),
+            Answer::Select(1),
+            Answer::Choose("Squash everything into one commit".to_string()),
+        ];
+        answers.extend(merge_message());
+        script(answers);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+
+        // One new commit with a single parent, holding all of the topic's files
+        assert_eq!(repo.git(&["rev-parse", "HEAD^"]), before);
+        assert_eq!(
+            repo.git(&["log", "-1", "--format=%B"]),
+            "chore: merge branch 'topic'\n\nBring in the topic"
+        );
+        assert_eq!(
+            repo.git(&["show", "--format=", "--name-only", "HEAD"]),
+            "b.txt\nc.txt\nd.txt"
+        );
+    }
+
+    #[test]
+    fn hands_a_conflicting_merge_to_the_guide() {
+        let repo = conflicting_repo();
+        let head = repo.git(&["rev-parse", "HEAD"]);
+
+        // Without a fast-forward, the merge commit comes first
+        let mut answers = vec![Answer::Select(4), Answer::Select(1), Answer::Select(0)];
+        answers.extend(merge_message());
+        answers.push(Answer::Choose("Leave it for now".to_string()));
+        script(answers);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(
+            GitCli.operation_in_progress().unwrap(),
+            Some(Operation::Merge)
+        );
+
+        // Another merge waits for this one, which the Conflicts menu can abort
+        script(vec![Answer::Select(4)]);
+        assert!(git_branches_cli(&GitCli, &Config::default()).is_err());
+        repo.git(&["merge", "--abort"]);
+
+        let mut answers = vec![Answer::Select(4), Answer::Select(1), Answer::Select(0)];
+        answers.extend(merge_message());
+        answers.push(Answer::Choose("Abort the merge".to_string()));
+        script(answers);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(GitCli.operation_in_progress().unwrap(), None);
+        assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+        assert_eq!(repo.git(&["status", "--porcelain"]), "");
+    }
+
+    #[test]
+    fn aborts_a_conflicting_squash() {
+        let repo = conflicting_repo();
+        let head = repo.git(&["rev-parse", "HEAD"]);
+
+        // git has no squash in progress to abort, the changes are reset instead
+        let mut answers = vec![
+            Answer::Select(4),
+            Answer::Select(1),
+            Answer::Choose("Squash everything into one commit".to_string()),
+        ];
+        answers.extend(merge_message());
+        answers.push(Answer::Choose("Abort the squash".to_string()));
+        script(answers);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(GitCli.operation_in_progress().unwrap(), None);
+        assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+        assert_eq!(repo.git(&["status", "--porcelain"]), "");
+        assert_eq!(std::fs::read_to_string("a.txt").unwrap(), "main\n");
+    }
+
+    /// On `topic`, two commits ahead of where it left `main`, which has moved on since
+    fn diverged_repo() -> TestRepo {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        repo.git(&["switch", "--quiet", "-c", "topic"]);
+        repo.commit_file("b.txt", "b\n", "Add b.txt");
+        repo.commit_file("c.txt", "c\n", "Add c.txt");
+        repo.git(&["switch", "--quiet", "main"]);
+        repo.commit_file("e.txt", "e\n", "Add e.txt");
+        repo.git(&["switch", "--quiet", "topic"]);
+        repo
+    }
+
+    #[test]
+    fn rebases_the_branch_commits_onto_another_branch() {
+        let repo = diverged_repo();
+
+        script(vec![Answer::Select(5), Answer::Select(0)]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(
+            repo.git(&["log", "--format=%s"]),
+            "Add c.txt\nAdd b.txt\nAdd e.txt\nAdd a.txt"
+        );
+        assert_eq!(repo.git(&["branch", "--show-current"]), "topic");
+    }
+
+    #[test]
+    fn rebases_with_local_changes_stashed() {
+        let repo = diverged_repo();
+        repo.write("a.txt", "changed\n");
+
+        script(vec![
+            Answer::Select(5),
+            Answer::Select(0),
+            Answer::Confirm(true),
+        ]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(
+            repo.git(&["merge-base", "main", "topic"]),
+            repo.git(&["rev-parse", "main"])
+        );
+        assert_eq!(repo.git(&["status", "--porcelain"]), " M a.txt");
+        assert_eq!(repo.git(&["stash", "list"]), "");
+    }
+
+    #[test]
+    fn asks_before_rebasing_pushed_commits() {
+        let repo = diverged_repo();
+        repo.git(&["update-ref", "refs/remotes/origin/topic", "topic"]);
+        let head = repo.git(&["rev-parse", "HEAD"]);
+
+        script(vec![
+            Answer::Select(5),
+            Answer::Select(0),
+            Answer::Confirm(false),
+        ]);
+        assert!(matches!(
+            git_branches_cli(&GitCli, &Config::default()),
+            Err(GustError::Cancelled)
+        ));
+        assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+
+        script(vec![
+            Answer::Select(5),
+            Answer::Select(0),
+            Answer::Confirm(true),
+        ]);
+        git_branches_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(
+            repo.git(&["merge-base", "main", "topic"]),
+            repo.git(&["rev-parse", "main"])
+        );
+    }
+}
@@ -1,35 +1,79 @@
+use crate::backend::GitBackend;
 use crate::cli;
 use crate::commands::{BasicCommands, Commands};
+use crate::error::GustError;
+use crate::menus;
 
-pub fn advanced_menu() -> Result<Commands, String> {
-    let command = match cli::filter_choice_cli(Commands::get_commands_vec(), false) {
-        Ok(com) => Commands::from_string(com.unwrap()),
-        Err(val) => Err(val),
-    };
+use colored::Colorize;
 
-    return command;
+pub fn advanced_menu() -> Result<Commands, GustError> {
+    let choice = cli::filter_choice_cli(Commands::get_commands_vec(), false)?;
+    Commands::from_string(choice.ok_or(GustError::Cancelled)?)
 }
 
-pub fn basic_menu() -> Result<Commands, String> {
+pub fn basic_menu() -> Result<Commands, GustError> {
     let choice = cli::filter_choice_cli(BasicCommands::get_commands_vec(), false)?;
-    let command = BasicCommands::from_string(choice.unwrap())?;
+    let command = BasicCommands::from_string(choice.ok_or(GustError::Cancelled)?)?;
 
-    let result = match command {
+    match command {
         BasicCommands::Add => Ok(Commands::Add),
         BasicCommands::Reset => Ok(Commands::Reset),
         BasicCommands::Commit => Ok(Commands::Commit),
         BasicCommands::AdvancedOptions => advanced_menu(),
         BasicCommands::Quit => Ok(Commands::Quit),
-    };
-    return result;
+    }
 }
 
-pub fn git_pull_cli() -> Result<(), String> {
-    cli::git_pull()?;
+pub fn git_pull_cli(backend: &impl GitBackend) -> Result<(), GustError> {
+    let branch = backend.status()?.branch;
+    if branch.head.is_some() && branch.upstream.is_none() {
+        println!(
+            "{}",
+            "This branch has no upstream to pull from, set one from the Remotes menu"
+                .bright_yellow()
+        );
+        return Ok(());
+    }
+
+    backend.pull()?;
     Ok(())
 }
 
-pub fn git_push_cli() -> Result<(), String> {
-    cli::git_push()?;
+pub fn git_push_cli(backend: &impl GitBackend) -> Result<(), GustError> {
+    let branch = backend.status()?.branch;
+
+    if let (Some(head), None) = (branch.head, branch.upstream) {
+        let remotes = match backend.remotes()? {
+            Some(remotes) => remotes,
+            None => {
+                println!("{}", "This repository has no remotes".bright_yellow());
+                if !cli::ask_yes_no("Would you like to add one?".to_string(), true)? {
+                    return Ok(());
+                }
+                menus::add_remote(backend, true)?;
+                backend.remotes()?.unwrap_or_default()
+            }
+        };
+        let names: Vec<String> = remotes.into_iter().map(|r| r.name).collect();
+
+        println!(
+            "{}",
+            format!("'{}' has no upstream branch yet", head).bright_yellow()
+        );
+        return match cli::choice_single(
+            names.clone(),
+            String::from("Select the remote to push to"),
+            false,
+            true,
+        )? {
+            cli::UserResponse::Some(index) => backend.push_set_upstream(names[index].clone(), head),
+            _ => {
+                println!("'None' selected, returning to menu");
+                Ok(())
+            }
+        };
+    }
+
+    backend.push()?;
     Ok(())
 }
@@ -1,133 +1,883 @@
-use crate::cli;
-use crate::settings::Config;
+use crate::backend::GitBackend;
+use crate::cli::{self, UserResponse};
+use crate::convention::{self, ConventionalSubject};
+use crate::error::GustError;
+use crate::settings::{Config, ConventionSettings};
+use crate::status::{Operation, RepoStatus};
 
 use crate::menus;
 
 use colored::Colorize;
-use console;
-use dialoguer::{theme::ColorfulTheme, Editor, Input};
 
-pub fn git_commit_cli(config: &Config) -> Result<(), String> {
+const CO_AUTHOR: &str = "Co-authored-by";
+const SOMEONE_ELSE: &str = "Someone else";
+
+pub fn git_commit_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
     println!(
         "{} {} {}",
         "Opening".green(),
         "Commit".bold().green(),
         "menu".green()
     );
-    let status_opt = cli::git_status_short()?;
+    let repo_status = backend.status()?;
+    println!("{}", repo_status.branch.summary().bright_blue());
 
-    let do_commit = match status_opt {
-        None => {
-            println!("{}", "No files changed since last commit".bright_yellow());
-            false
-        }
-        Some(status) => {
-            // Sort the return from git status
-            let mut choices = Vec::<String>::new();
-            for line in status.lines() {
-                match line.chars().nth(0).unwrap() {
-                    'M' | 'A' | 'C' | 'D' => {
-                        if line.chars().nth(3).unwrap() == '"' {
-                            choices.push(line[4..line.len() - 1].yellow().to_string());
-                        } else {
-                            choices.push(line[3..].yellow().to_string());
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            // check for staged changes
-            if choices.len() == 0 {
-                if cli::ask_choice_cli("No files staged, would you like to add some?".to_string())?
-                {
-                    menus::git_add_cli(&config)?;
-                    true
-                } else {
-                    false
-                }
+    // A repository without commits has nothing to amend or fix up
+    if repo_status.branch.oid.is_some() {
+        let choices = vec![
+            "New commit".to_string(),
+            "Amend the last commit".to_string(),
+            "Fixup a commit".to_string(),
+            "Squash into a commit".to_string(),
+            "Fold in fixup! and squash! commits".to_string(),
+        ];
+        match cli::choice_single(choices, String::from("Select action"), false, false)? {
+            UserResponse::Some(1) => return amend_cli(backend, config, &repo_status),
+            UserResponse::Some(2) => return fixup_cli(backend, config, &repo_status, false),
+            UserResponse::Some(3) => return fixup_cli(backend, config, &repo_status, true),
+            UserResponse::Some(4) => return autosquash_cli(backend, config),
+            _ => {}
+        }
+    }
+
+    let do_commit = if repo_status.entries.is_empty() {
+        println!("{}", "No files changed since last commit".bright_yellow());
+        false
+    } else {
+        let choices: Vec<String> = repo_status
+            .staged()
+            .iter()
+            .map(|e| e.label().yellow().to_string())
+            .collect();
+        // check for staged changes
+        if choices.is_empty() {
+            if cli::ask_choice_cli("No files staged, would you like to add some?".to_string())? {
+                menus::git_add_cli(backend, config)?;
+                true
             } else {
-                cli::ask_choice_cli(format!(
-                    "{}:\n{}",
-                    "Commit the following files?",
-                    choices.join("\n")
-                ))?
+                false
             }
+        } else {
+            cli::ask_choice_cli(format!(
+                "{}:\n{}",
+                "Commit the following files?",
+                choices.join("\n")
+            ))?
         }
     };
 
     if do_commit {
         if config.use_git_commit_message_dialog {
-            if do_commit {
-                // Get commit type
-                // Get commit message
-                // Get advanced description
-                cli::git_commit(None, config)?;
-            }
+            editor_commit_cli(backend, config)?;
         } else {
-            let conventions = crate::settings::load_convention(config.convention.clone());
-            let short_form: String = {
-                let mut commit_msg = Vec::<String>::new();
-                if config.use_types {
-                    commit_msg.push(
-                        cli::filter_choice_cli(conventions.types, true)?.unwrap_or("".to_string()),
-                    ); // The check against none is to skip all of the convention options
-                       // if the first is skipped
-                    if commit_msg[0] != "None" {
-                        if config.use_scope {
-                            let convention_scope =
-                                cli::filter_choice_cli(conventions.scopes, true)?
-                                    .unwrap_or("".to_string());
-                            if convention_scope != "None" {
-                                commit_msg.push(conventions.scope_delimeters.opening.clone());
-                                commit_msg.push(convention_scope);
-                                commit_msg.push(conventions.scope_delimeters.closing.clone());
-                            }
-                        }
-                        if config.use_important {
-                            if cli::ask_choice_cli(format!(
-                                "Flag this commit as important with: '{}'",
-                                conventions.important_symbol
-                            ))? {
-                                commit_msg.push(conventions.important_symbol);
-                            }
-                        }
-                        commit_msg.push(conventions.separator);
-                        commit_msg.push(" ".to_string());
+            let paragraphs =
+                compose_message_cli(backend, config, "", "Enter a commit description")?;
+
+            backend.commit(
+                Some(
+                    paragraphs
+                        .into_iter()
+                        .flat_map(|p| vec!["-m".to_string(), p])
+                        .collect(),
+                ),
+                config,
+            )?;
+        }
+    }
+    println!("{}", "Changes committed!".bright_green());
+    Ok(())
+}
+
+/// Write the whole message in the editor like `git commit` does, linting it before committing
+fn editor_commit_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    let comment = backend.comment_prefix()?;
+    let template = format!(
+        "\n{c} Please enter the commit message for your changes. Lines starting\n\
+         {c} with '{c}' will be ignored, and an empty message aborts the commit.\n",
+        c = comment
+    );
+    let message = match cli::edit_text(&template, false)? {
+        Some(edited) => convention::strip_comments(&edited, &comment),
+        None => return Err(GustError::Cancelled),
+    };
+    if message.trim().is_empty() {
+        println!(
+            "{}",
+            "Aborting commit due to empty commit message".bright_yellow()
+        );
+        return Err(GustError::Cancelled);
+    }
+
+    if !lint_before_commit(backend, config, &message)? {
+        return Err(GustError::Cancelled);
+    }
+
+    let file = backend.git_dir()?.join("GUST_EDITMSG");
+    std::fs::write(&file, format!("{}\n", message))?;
+    if let Err(err) = backend.commit(
+        Some(vec!["-F".to_string(), file.display().to_string()]),
+        config,
+    ) {
+        println!("The commit message was saved to {}", file.display());
+        return Err(err);
+    }
+    std::fs::remove_file(&file)?;
+    Ok(())
+}
+
+/// Build a commit message with the convention: the subject from the type, scope and important
+/// flag plus `suggestion`, a description edited from `body`, then the footers. Returns the
+/// message's paragraphs, each to be passed with its own `-m`.
+pub fn compose_message_cli(
+    backend: &impl GitBackend,
+    config: &Config,
+    suggestion: &str,
+    body: &str,
+) -> Result<Vec<String>, GustError> {
+    let conventions = crate::settings::load_convention(config.convention.clone())?;
+    let mut important = false;
+    let short_form: String = {
+        let mut commit_msg = Vec::<String>::new();
+        if config.use_types {
+            commit_msg.push(
+                cli::filter_choice_cli(conventions.types.clone(), true)?.unwrap_or("".to_string()),
+            ); // The check against none is to skip all of the convention options
+               // if the first is skipped
+            if commit_msg[0] != "None" {
+                if config.use_scope {
+                    let convention_scope =
+                        cli::filter_choice_cli(conventions.scopes.clone(), true)?
+                            .unwrap_or("".to_string());
+                    if convention_scope != "None" {
+                        commit_msg.push(conventions.scope_delimeters.opening.clone());
+                        commit_msg.push(convention_scope);
+                        commit_msg.push(conventions.scope_delimeters.closing.clone());
                     }
                 }
+                if config.use_important
+                    && cli::ask_choice_cli(format!(
+                        "Flag this commit as important with: '{}'",
+                        conventions.important_symbol
+                    ))?
+                {
+                    commit_msg.push(conventions.important_symbol.clone());
+                    important = true;
+                }
+                commit_msg.push(conventions.separator.clone());
+                commit_msg.push(" ".to_string());
+            }
+        }
+
+        commit_msg = commit_msg
+            .iter()
+            .filter(|x| x != &&String::from("None"))
+            .cloned()
+            .collect();
+
+        cli::get_input_initial(
+            " Enter Commit Message, Shouldn't exceed this ---> |\n".to_string(),
+            commit_msg.join("") + suggestion,
+        )?
+    };
+
+    let description: String = cli::edit_text(body, true)?
+        .ok_or_else(|| "No commit description was entered".to_string())?;
+
+    let mut paragraphs = vec![short_form, description];
+    if config.use_footers {
+        let footers = footers_cli(backend, &conventions, &paragraphs[0], important)?;
+        if !footers.is_empty() {
+            paragraphs.push(footers.join("\n"));
+        }
+    }
+
+    if !lint_before_commit(backend, config, &paragraphs.join("\n\n"))? {
+        return Err(GustError::Cancelled);
+    }
+    Ok(paragraphs)
+}
+
+/// Ask for the footers that end the commit message, one `Token: value` per line.
+/// An important commit always gets a `BREAKING CHANGE` footer.
+fn footers_cli(
+    backend: &impl GitBackend,
+    conventions: &ConventionSettings,
+    subject: &str,
+    important: bool,
+) -> Result<Vec<String>, GustError> {
+    let mut footers = Vec::<String>::new();
+
+    if important {
+        let description = ConventionalSubject::parse(subject, conventions)
+            .map(|parsed| parsed.description)
+            .unwrap_or_else(|| subject.to_string());
+        let breaking =
+            cli::get_input_default("Describe the breaking change".to_string(), description)?;
+        footers.push(format!("BREAKING CHANGE: {}", breaking));
+    }
+
+    let mut tokens: Vec<String> = conventions
+        .footers
+        .iter()
+        .filter(|token| !(important && token.starts_with("BREAKING CHANGE")))
+        .cloned()
+        .collect();
+    for token in [CO_AUTHOR, "Refs"] {
+        if !tokens.iter().any(|t| t == token) {
+            tokens.push(token.to_string());
+        }
+    }
 
-                commit_msg = commit_msg
-                    .iter()
-                    .filter(|x| x != &&String::from("None"))
-                    .cloned()
+    loop {
+        let mut choices = tokens.clone();
+        choices.push("Done".to_string());
+        let token = match cli::choice_single(choices, String::from("Add a footer"), false, false)? {
+            UserResponse::Some(index) if index < tokens.len() => tokens[index].clone(),
+            _ => return Ok(footers),
+        };
+
+        match token.as_str() {
+            CO_AUTHOR => footers.extend(
+                co_authors_cli(backend)?
+                    .into_iter()
+                    .map(|author| format!("{}: {}", CO_AUTHOR, author)),
+            ),
+            "Refs" => {
+                let issues =
+                    cli::get_input("Enter the issues, separated by spaces or commas".to_string())?;
+                let issues: Vec<String> = issues
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|i| !i.is_empty())
+                    .map(|i| {
+                        if i.chars().all(|c| c.is_ascii_digit()) {
+                            format!("#{}", i)
+                        } else {
+                            i.to_string()
+                        }
+                    })
                     .collect();
+                footers.push(format!("Refs: {}", issues.join(", ")));
+            }
+            _ => {
+                let value = cli::get_input(format!("Enter the value for '{}'", token))?;
+                footers.push(format!("{}: {}", token, value));
+            }
+        }
+    }
+}
 
-                let usr_selection: String = Input::with_theme(&ColorfulTheme::default())
-                    .allow_empty(false)
-                    .with_initial_text(commit_msg.join(""))
-                    .with_prompt(" Enter Commit Message, Shouldn't exceed this ---> |\n")
-                    .interact_text_on(&console::Term::stderr())
-                    .unwrap();
-
-                usr_selection
-            };
-
-            let description: String = Editor::new()
-                .edit("Enter a commit description")
-                .unwrap()
-                .unwrap();
-
-            cli::git_commit(
-                Some(vec![
-                    "-m".to_string(),
-                    short_form,
-                    "-m".to_string(),
-                    description,
-                ]),
-                config,
+/// Pick co-authors from the people who recently committed, or type one in
+fn co_authors_cli(backend: &impl GitBackend) -> Result<Vec<String>, GustError> {
+    let authors = backend.recent_authors()?;
+    if authors.is_empty() {
+        return Ok(vec![cli::get_input(
+            "Enter the co-author as 'Name <email>'".to_string(),
+        )?]);
+    }
+
+    let mut choices = authors;
+    choices.push(SOMEONE_ELSE.to_string());
+    let mut selected =
+        match cli::choice_no_limit(choices, String::from("Select the co-authors"), false, false)? {
+            UserResponse::Some(selected) => selected,
+            _ => return Ok(vec![]),
+        };
+
+    if let Some(index) = selected.iter().position(|s| s == SOMEONE_ELSE) {
+        selected[index] = cli::get_input("Enter the co-author as 'Name <email>'".to_string())?;
+    }
+    Ok(selected)
+}
+
+/// Rewrite the last commit's message, keeping its convention prefix, optionally adding the staged changes
+fn amend_cli(
+    backend: &impl GitBackend,
+    config: &Config,
+    repo_status: &RepoStatus,
+) -> Result<(), GustError> {
+    let with_staged = !repo_status.staged().is_empty()
+        && cli::ask_yes_no("Add the staged changes to the commit?".to_string(), true)?;
+
+    if !confirm_rewrite(backend, "HEAD")? {
+        return Ok(());
+    }
+
+    let message = backend.commit_message("HEAD".to_string())?;
+    let (subject, body) = match message.split_once("\n\n") {
+        Some((subject, body)) => (subject.to_string(), body.to_string()),
+        None => (message.clone(), String::new()),
+    };
+
+    let parsed = if config.use_commit_convention {
+        let conventions = crate::settings::load_convention(config.convention.clone())?;
+        ConventionalSubject::parse(&subject, &conventions)
+            .map(|parsed| (parsed.prefix(&conventions), parsed.description))
+    } else {
+        None
+    };
+
+    let subject = match parsed {
+        Some((prefix, description)) => {
+            println!("Keeping the prefix '{}'", prefix.trim_end().bright_blue());
+            let description = cli::get_input_initial(
+                " Enter Commit Message, Shouldn't exceed this ---> |\n".to_string(),
+                description,
             )?;
+            prefix + &description
         }
+        None => cli::get_input_initial(
+            " Enter Commit Message, Shouldn't exceed this ---> |\n".to_string(),
+            subject,
+        )?,
+    };
+    // Closing the editor without saving keeps the old description
+    let body = cli::edit_text(&body, true)?.unwrap_or(body);
+
+    if !lint_before_commit(backend, config, &format!("{}\n\n{}", subject, body))? {
+        return Err(GustError::Cancelled);
+    }
+
+    let mut options = vec!["--amend".to_string()];
+    if !with_staged {
+        options.push("--only".to_string());
     }
+    options.push("-m".to_string());
+    options.push(subject);
+    if !body.trim().is_empty() {
+        options.push("-m".to_string());
+        options.push(body);
+    }
+    backend.commit(Some(options), config)?;
+
+    println!("{}", "Commit amended!".bright_green());
+    Ok(())
+}
+
+/// Commit the staged changes as a `fixup!` (or `squash!`) of an earlier commit
+fn fixup_cli(
+    backend: &impl GitBackend,
+    config: &Config,
+    repo_status: &RepoStatus,
+    squash: bool,
+) -> Result<(), GustError> {
+    if repo_status.staged().is_empty() {
+        if cli::ask_choice_cli("No files staged, would you like to add some?".to_string())? {
+            menus::git_add_cli(backend, config)?;
+        } else {
+            return Ok(());
+        }
+    }
+
+    let prompt = if squash {
+        "Select the commit to squash into:"
+    } else {
+        "Select the commit to fix up:"
+    };
+    let hash = match menus::choose_commit(backend, None, prompt.to_string())? {
+        Some(hash) => hash,
+        None => return Ok(()),
+    };
+    if !confirm_rewrite(backend, &hash)? {
+        return Ok(());
+    }
+
+    let options = if squash {
+        match cli::get_input_optional(
+            "Enter a message to add to the squashed commit (optional)".to_string(),
+        )? {
+            Some(message) => vec![format!("--squash={}", hash), "-m".to_string(), message],
+            None => vec![format!("--squash={}", hash), "--no-edit".to_string()],
+        }
+    } else {
+        vec![format!("--fixup={}", hash)]
+    };
+    backend.commit(Some(options), config)?;
     println!("{}", "Changes committed!".bright_green());
+
+    if cli::ask_yes_no(
+        "Fold it into the commit now with an autosquash rebase?".to_string(),
+        false,
+    )? {
+        autosquash(backend, config, &hash)?;
+    }
     Ok(())
 }
+
+/// Rebase every `fixup!` and `squash!` commit on the branch into the commit it targets
+fn autosquash_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    // Full hashes and subjects, a decorated or abbreviated log can't be matched reliably
+    let commits: Vec<(String, String)> = backend
+        .log_commits("HEAD".to_string())?
+        .into_iter()
+        .map(|c| {
+            let subject = c.message.lines().next().unwrap_or_default().to_string();
+            (c.hash, subject)
+        })
+        .collect();
+
+    // Find the oldest commit a fixup targets, the log lists the newest first
+    let mut oldest: Option<usize> = None;
+    for (index, (_, subject)) in commits.iter().enumerate() {
+        let target = fixup_target(subject);
+        if target == subject {
+            continue;
+        }
+        let found = commits
+            .iter()
+            .skip(index + 1)
+            .position(|(hash, s)| s == target || is_hash_of(target, hash))
+            .map(|p| p + index + 1)
+            .ok_or_else(|| format!("Couldn't find the commit '{}' targets", subject))?;
+        oldest = oldest.max(Some(found));
+    }
+
+    match oldest {
+        None => {
+            println!("{}", "No fixup! or squash! commits found".bright_yellow());
+            Ok(())
+        }
+        Some(index) => {
+            let hash = commits[index].0.clone();
+            if confirm_rewrite(backend, &hash)? {
+                autosquash(backend, config, &hash)
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The subject a `fixup!`, `squash!` or `amend!` subject points at
+fn fixup_target(subject: &str) -> &str {
+    let mut target = subject;
+    while let Some(rest) = ["fixup! ", "squash! ", "amend! "]
+        .iter()
+        .find_map(|prefix| target.strip_prefix(prefix))
+    {
+        target = rest;
+    }
+    target
+}
+
+/// Whether `target` is `hash`, or an abbreviation of it, like `fixup! 1a2b3c4` refers to a commit
+fn is_hash_of(target: &str, hash: &str) -> bool {
+    target.len() >= 4 && target.chars().all(|c| c.is_ascii_hexdigit()) && hash.starts_with(target)
+}
+
+/// Run the autosquash rebase starting at `target`, accepting the todo list git generates
+fn autosquash(backend: &impl GitBackend, config: &Config, target: &str) -> Result<(), GustError> {
+    // The root commit has no parent to rebase onto
+    let base = match backend.rev_parse(&format!("{}~1", target)) {
+        Ok(parent) => parent,
+        Err(GustError::GitFailed { .. }) => "--root".to_string(),
+        Err(e) => return Err(e),
+    };
+
+    let args = vec![
+        "--interactive".to_string(),
+        "--autosquash".to_string(),
+        "--autostash".to_string(),
+        base,
+    ];
+    match backend.rebase(args, Some(":".to_string())) {
+        Ok(()) => {
+            println!("{}", "Fixups folded in!".bright_green());
+            Ok(())
+        }
+        Err(e) if backend.operation_in_progress()? == Some(Operation::Rebase) => {
+            println!("{}", e.to_string().bright_red());
+            menus::guide_operation_cli(backend, config, Operation::Rebase)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Show what doesn't follow the convention in `message`, and whether to commit anyway
+fn lint_before_commit(
+    backend: &impl GitBackend,
+    config: &Config,
+    message: &str,
+) -> Result<bool, GustError> {
+    let issues = convention::lint_with_config(message, config, &backend.comment_prefix()?)?;
+    if issues.is_empty() {
+        return Ok(true);
+    }
+
+    println!(
+        "{}",
+        "The commit message doesn't follow the convention:".bright_yellow()
+    );
+    for issue in &issues {
+        println!("  {}", issue);
+    }
+    cli::ask_yes_no("Commit anyway?".to_string(), false)
+}
+
+/// Warn when `commit` is already on a remote, rewriting it means others have to force pull
+pub fn confirm_rewrite(backend: &impl GitBackend, commit: &str) -> Result<bool, GustError> {
+    let pushed = backend.remote_branches_containing(commit.to_string())?;
+    if pushed.is_empty() {
+        return Ok(true);
+    }
+
+    println!(
+        "{} {}",
+        "The commit has already been pushed to:".bright_yellow(),
+        pushed.join(", ")
+    );
+    println!(
+        "{}",
+        "Rewriting it means force pushing, and anyone who pulled it will have to fix their history"
+            .bright_yellow()
+    );
+    cli::ask_yes_no("Rewrite it anyway?".to_string(), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+    use crate::prompt::scripted::{script, Answer};
+
+    /// The answers for a `feat` commit without a scope that isn't flagged as important
+    fn feat(description: &str, body: &str) -> Vec<Answer> {
+        vec![
+            Answer::Choose("feat".to_string()),
+            Answer::Choose("None".to_string()),
+            Answer::Confirm(false),
+            Answer::Text(format!("feat: {}", description)),
+            Answer::Edit(Some(body.to_string())),
+        ]
+    }
+
+    fn last_message(repo: &TestRepo) -> String {
+        repo.git(&["log", "-1", "--format=%B"])
+    }
+
+    #[test]
+    fn makes_the_first_commit() {
+        let repo = TestRepo::new();
+        repo.write("a.txt", "a\n");
+        repo.git(&["add", "a.txt"]);
+
+        // Without a HEAD there is nothing to amend, so the menu goes straight to committing
+        let mut answers = vec![Answer::Confirm(true)];
+        answers.extend(feat("add a.txt", "Explain why"));
+        script(answers);
+        git_commit_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(last_message(&repo), "feat: add a.txt\n\nExplain why");
+    }
+
+    #[test]
+    fn offers_to_stage_files_first() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "feat: add a.txt");
+        repo.write("a.txt", "changed\n");
+
+        // New commit, stage files, then a.txt between "All" and "None"
+        let mut answers = vec![
+            Answer::Select(0),
+            Answer::Confirm(true),
+            Answer::Select(0),
+            Answer::MultiSelect(vec![1]),
+        ];
+        answers.extend(feat("change a.txt", "Body"));
+        script(answers);
+        git_commit_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(last_message(&repo), "feat: change a.txt\n\nBody");
+        assert_eq!(repo.git(&["status", "--porcelain"]), "");
+    }
+
+    #[test]
+    fn amends_the_message_keeping_the_prefix() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "feat(ui): add a.txt\n\nThe body");
+
+        // Closing the editor without saving keeps the body
+        script(vec![
+            Answer::Select(1),
+            Answer::Text("add the a file".to_string()),
+            Answer::Edit(None),
+        ]);
+        git_commit_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(last_message(&repo), "feat(ui): add the a file\n\nThe body");
+        assert_eq!(repo.git(&["rev-list", "--count", "HEAD"]), "1");
+    }
+
+    #[test]
+    fn stops_when_the_message_breaks_the_convention() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "feat: add a.txt");
+        repo.write("a.txt", "changed\n");
+        repo.git(&["add", "a.txt"]);
+
+        // A subject without a type, then declining to commit anyway
+        script(vec![
+            Answer::Select(0),
+            Answer::Confirm(true),
+            Answer::Choose("None".to_string()),
+            Answer::Text("changed a.txt".to_string()),
+            Answer::Edit(Some(String::new())),
+            Answer::Confirm(false),
+        ]);
+        assert!(matches!(
+            git_commit_cli(&GitCli, &Config::default()),
+            Err(GustError::Cancelled)
+        ));
+        assert_eq!(repo.git(&["rev-list", "--count", "HEAD"]), "1");
+    }
+
+    /// Commits `a.txt`, then stages a change to it
+    fn with_staged_change() -> TestRepo {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "feat: add a.txt");
+        repo.write("a.txt", "changed\n");
+        repo.git(&["add", "a.txt"]);
+        repo
+    }
+
+    fn editor_config() -> Config {
+        Config {
+            use_git_commit_message_dialog: true,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn commits_the_message_written_in_the_editor() {
+        let repo = with_staged_change();
+        script(vec![
+            Answer::Select(0),
+            Answer::Confirm(true),
+            Answer::Edit(Some(
+                "fix: change a.txt\n\nBody\n# Please enter the commit message\n".to_string(),
+            )),
+        ]);
+        git_commit_cli(&GitCli, &editor_config()).unwrap();
+
+        assert_eq!(last_message(&repo), "fix: change a.txt\n\nBody");
+        assert!(!repo.work_tree.join(".git/GUST_EDITMSG").exists());
+    }
+
+    #[test]
+    fn lints_the_message_written_in_the_editor() {
+        let repo = with_staged_change();
+        script(vec![
+            Answer::Select(0),
+            Answer::Confirm(true),
+            Answer::Edit(Some("changed a.txt\n".to_string())),
+            Answer::Confirm(false),
+        ]);
+        assert!(matches!(
+            git_commit_cli(&GitCli, &editor_config()),
+            Err(GustError::Cancelled)
+        ));
+        assert_eq!(repo.git(&["rev-list", "--count", "HEAD"]), "1");
+    }
+
+    #[test]
+    fn comments_start_with_the_configured_character() {
+        let repo = with_staged_change();
+        repo.git(&["config", "core.commentChar", ";"]);
+        script(vec![
+            Answer::Select(0),
+            Answer::Confirm(true),
+            Answer::Edit(Some(
+                "fix: change a.txt\n\n#12 was wrong\n; Please enter the commit message\n"
+                    .to_string(),
+            )),
+        ]);
+        git_commit_cli(&GitCli, &editor_config()).unwrap();
+
+        assert_eq!(last_message(&repo), "fix: change a.txt\n\n#12 was wrong");
+    }
+
+    /// `feat: add a.txt` then `feat: add b.txt`, with a change to a.txt staged
+    fn with_fixup_to_make() -> TestRepo {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "feat: add a.txt");
+        repo.commit_file("b.txt", "b\n", "feat: add b.txt");
+        repo.write("a.txt", "fixed\n");
+        repo.git(&["add", "a.txt"]);
+        repo
+    }
+
+    fn subjects(repo: &TestRepo) -> String {
+        repo.git(&["log", "--format=%s"])
+    }
+
+    #[test]
+    fn fixes_up_a_commit_and_folds_it_in() {
+        let repo = with_fixup_to_make();
+        // The log lists b.txt's commit first
+        script(vec![
+            Answer::Select(2),
+            Answer::Select(1),
+            Answer::Confirm(true),
+        ]);
+        git_commit_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(subjects(&repo), "feat: add b.txt\nfeat: add a.txt");
+        assert_eq!(repo.git(&["show", "HEAD~1:a.txt"]), "fixed");
+        assert_eq!(repo.git(&["status", "--porcelain"]), "");
+    }
+
+    #[test]
+    fn squashes_with_a_message_to_fold_in_later() {
+        let repo = with_fixup_to_make();
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(1),
+            Answer::Text("Also fix a.txt".to_string()),
+            Answer::Confirm(false),
+        ]);
+        git_commit_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(
+            last_message(&repo),
+            "squash! feat: add a.txt\n\nAlso fix a.txt"
+        );
+
+        script(vec![Answer::Select(4)]);
+        git_commit_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(subjects(&repo), "feat: add b.txt\nfeat: add a.txt");
+        assert_eq!(
+            repo.git(&["log", "-1", "--format=%B", "HEAD~1"]),
+            "feat: add a.txt\n\nAlso fix a.txt"
+        );
+    }
+
+    #[test]
+    fn autosquash_finds_fixups_that_name_a_hash() {
+        let repo = with_fixup_to_make();
+        repo.git(&["config", "log.decorate", "full"]);
+        let target = repo.git(&["rev-parse", "--short", "HEAD~1"]);
+        repo.git(&["commit", "--quiet", "-m", &format!("fixup! {}", target)]);
+        repo.commit_file("c.txt", "c\n", "feat: add c.txt");
+
+        script(vec![Answer::Select(4)]);
+        git_commit_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(
+            subjects(&repo),
+            "feat: add c.txt\nfeat: add b.txt\nfeat: add a.txt"
+        );
+        assert_eq!(repo.git(&["show", "HEAD~2:a.txt"]), "fixed");
+    }
+
+    #[test]
+    fn autosquash_without_fixups_changes_nothing() {
+        let repo = with_fixup_to_make();
+        let head = repo.git(&["rev-parse", "HEAD"]);
+        script(vec![Answer::Select(4)]);
+        git_commit_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+    }
+
+    fn footers_config() -> Config {
+        Config {
+            use_footers: true,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn adds_co_authors_and_issue_references() {
+        let repo = TestRepo::new();
+        repo.git(&[
+            "-c",
+            "user.name=Ada Lovelace",
+            "-c",
+            "user.email=ada@example.com",
+            "commit",
+            "--quiet",
+            "--allow-empty",
+            "-m",
+            "feat: start",
+        ]);
+        repo.write("a.txt", "a\n");
+        repo.git(&["add", "a.txt"]);
+
+        let mut answers = vec![Answer::Select(0), Answer::Confirm(true)];
+        answers.extend(feat("add a.txt", "Body"));
+        answers.extend([
+            // Ada from the log, and someone who hasn't committed yet
+            Answer::Choose(CO_AUTHOR.to_string()),
+            Answer::MultiSelect(vec![0, 1]),
+            Answer::Text("Grace Hopper <grace@example.com>".to_string()),
+            Answer::Choose("Refs".to_string()),
+            Answer::Text("12, GH-3 7".to_string()),
+            Answer::Choose("Done".to_string()),
+        ]);
+        script(answers);
+        git_commit_cli(&GitCli, &footers_config()).unwrap();
+
+        assert_eq!(
+            last_message(&repo),
+            "feat: add a.txt\n\nBody\n\n\
+             Co-authored-by: Ada Lovelace <ada@example.com>\n\
+             Co-authored-by: Grace Hopper <grace@example.com>\n\
+             Refs: #12, GH-3, #7"
+        );
+    }
+
+    #[test]
+    fn asks_for_a_co_author_when_nobody_else_committed() {
+        let repo = with_staged_change();
+        let mut answers = vec![Answer::Select(0), Answer::Confirm(true)];
+        answers.extend(feat("change a.txt", "Body"));
+        answers.extend([
+            Answer::Choose(CO_AUTHOR.to_string()),
+            Answer::Text("Grace Hopper <grace@example.com>".to_string()),
+            Answer::Choose("Done".to_string()),
+        ]);
+        script(answers);
+        git_commit_cli(&GitCli, &footers_config()).unwrap();
+
+        assert_eq!(
+            last_message(&repo),
+            "feat: change a.txt\n\nBody\n\nCo-authored-by: Grace Hopper <grace@example.com>"
+        );
+    }
+
+    #[test]
+    fn important_commits_describe_the_breaking_change() {
+        let repo = with_staged_change();
+        script(vec![
+            Answer::Select(0),
+            Answer::Confirm(true),
+            Answer::Choose("feat".to_string()),
+            Answer::Choose("None".to_string()),
+            Answer::Confirm(true),
+            Answer::Text("feat!: rename a.txt".to_string()),
+            Answer::Edit(Some("Body".to_string())),
+            Answer::Text("a.txt holds something else".to_string()),
+            Answer::Choose("Refs".to_string()),
+            Answer::Text("4".to_string()),
+            Answer::Choose("Done".to_string()),
+        ]);
+        git_commit_cli(&GitCli, &footers_config()).unwrap();
+
+        assert_eq!(
+            last_message(&repo),
+            "feat!: rename a.txt\n\nBody\n\n\
+             BREAKING CHANGE: a.txt holds something else\n\
+             Refs: #4"
+        );
+    }
+
+    #[test]
+    fn footers_are_left_out_when_none_are_picked() {
+        let repo = with_staged_change();
+        let mut answers = vec![Answer::Select(0), Answer::Confirm(true)];
+        answers.extend(feat("change a.txt", "Body"));
+        answers.push(Answer::Choose("Done".to_string()));
+        script(answers);
+        git_commit_cli(&GitCli, &footers_config()).unwrap();
+
+        assert_eq!(last_message(&repo), "feat: change a.txt\n\nBody");
+    }
+}
@@ -0,0 +1,403 @@
+use crate::backend::GitBackend;
+use crate::cli::{self, UserResponse};
+use crate::conflicts::{self, Resolution, Segment};
+use crate::error::GustError;
+use crate::ignore;
+use crate::menus;
+use crate::settings::Config;
+use crate::status::{FileEntry, Operation};
+
+use colored::Colorize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+pub fn git_conflicts_cli(backend: &impl GitBackend, _config: &Config) -> Result<(), GustError> {
+    println!(
+        "{} {} {}",
+        "Opening".green(),
+        "Conflicts".bold().green(),
+        "menu".green()
+    );
+
+    loop {
+        let repo_status = backend.status()?;
+        let conflicted: Vec<&FileEntry> = repo_status
+            .entries
+            .iter()
+            .filter(|e| e.is_unmerged())
+            .collect();
+
+        if conflicted.is_empty() {
+            println!("{}", "No conflicted files".bright_green());
+            return offer_continue(backend);
+        }
+
+        let choices: Vec<String> = conflicted
+            .iter()
+            .map(|e| conflict_label(e).bright_red().to_string())
+            .collect();
+
+        let entry = match cli::choice_single(
+            choices,
+            String::from("Select a file to resolve"),
+            false,
+            true,
+        )? {
+            UserResponse::Some(index) => conflicted[index],
+            _ => {
+                println!("'None' selected, returning to menu");
+                return Ok(());
+            }
+        };
+
+        resolve_file(backend, entry)?;
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Step {
+    Resolve,
+    Commit,
+    Continue,
+    Skip,
+    Abort,
+    Leave,
+}
+
+/// Walk the user through a rebase, cherry-pick, merge or revert that stopped,
+/// until it finishes or they leave it
+pub fn guide_operation_cli(
+    backend: &impl GitBackend,
+    config: &Config,
+    operation: Operation,
+) -> Result<(), GustError> {
+    loop {
+        if backend.operation_in_progress()? != Some(operation) {
+            println!("{}", format!("The {} is done!", operation).bright_green());
+            return Ok(());
+        }
+
+        let conflicted = backend.status()?.entries.iter().any(|e| e.is_unmerged());
+        let mut steps = vec![];
+        if conflicted {
+            println!(
+                "{}",
+                format!(
+                    "The {} stopped on conflicts, resolve them then continue",
+                    operation
+                )
+                .bright_yellow()
+            );
+            steps.push(("Resolve the conflicts".to_string(), Step::Resolve));
+        } else if operation == Operation::Rebase {
+            println!(
+                "{} {}",
+                "The rebase stopped at".bright_yellow(),
+                backend
+                    .log(Some("-1".to_string()))?
+                    .unwrap_or_default()
+                    .trim()
+            );
+            println!("  Change and stage files, or amend the commit from the Commit menu,");
+            println!("  then continue the rebase");
+            steps.push(("Open the Commit menu".to_string(), Step::Commit));
+        } else if operation == Operation::Merge {
+            println!(
+                "{}",
+                "The conflicts are resolved, continue to commit the merge".bright_yellow()
+            );
+        } else {
+            println!(
+                "{}",
+                format!(
+                    "The {} stopped, the commit may have no changes left to apply",
+                    operation
+                )
+                .bright_yellow()
+            );
+        }
+        steps.push((format!("Continue the {}", operation), Step::Continue));
+        // A merge is a single step, there is nothing to skip
+        if operation != Operation::Merge {
+            steps.push(("Skip this commit".to_string(), Step::Skip));
+        }
+        steps.push((format!("Abort the {}", operation), Step::Abort));
+        steps.push(("Leave it for now".to_string(), Step::Leave));
+
+        let labels = steps.iter().map(|(label, _)| label.clone()).collect();
+        let step = match cli::choice_single(labels, String::from("Select action"), false, false)? {
+            UserResponse::Some(index) => steps[index].1,
+            _ => Step::Leave,
+        };
+
+        let result = match step {
+            Step::Resolve => git_conflicts_cli(backend, config),
+            Step::Commit => menus::git_commit_cli(backend, config),
+            Step::Continue => backend.operation(operation, "continue"),
+            Step::Skip => backend.operation(operation, "skip"),
+            Step::Abort => {
+                backend.operation(operation, "abort")?;
+                println!(
+                    "{}",
+                    format!("The {} was aborted", operation).bright_yellow()
+                );
+                return Ok(());
+            }
+            Step::Leave => {
+                let menu = if operation == Operation::Rebase {
+                    "Rebase"
+                } else {
+                    "Conflicts"
+                };
+                println!(
+                    "{}",
+                    format!("Finish the {} later from the {} menu", operation, menu)
+                        .bright_yellow()
+                );
+                return Ok(());
+            }
+        };
+
+        // Stopping again is part of the operation, anything else ends it
+        match result {
+            Ok(()) | Err(GustError::Cancelled) => {}
+            Err(e) if backend.operation_in_progress()? == Some(operation) => {
+                println!("{}", e.to_string().bright_red());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Offer to resolve conflicts, for menus whose git command just stopped on them
+pub fn resolve_conflicts_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    if cli::ask_yes_no("Resolve the conflicts now?".to_string(), true)? {
+        git_conflicts_cli(backend, config)
+    } else {
+        println!(
+            "{}",
+            "Resolve the conflicts later from the Conflicts menu".bright_yellow()
+        );
+        Ok(())
+    }
+}
+
+fn conflict_label(entry: &FileEntry) -> String {
+    match entry.conflict {
+        Some(kind) => format!("{} ({})", entry.label(), kind),
+        None => entry.label(),
+    }
+}
+
+fn resolve_file(backend: &impl GitBackend, entry: &FileEntry) -> Result<(), GustError> {
+    let kind = match entry.conflict {
+        Some(kind) => kind,
+        None => return Ok(()),
+    };
+    println!("{}", conflict_label(entry).bold());
+
+    let mut actions = vec![
+        if kind.ours_exists() {
+            "Take our version".to_string()
+        } else {
+            "Take our version (delete the file)".to_string()
+        },
+        if kind.theirs_exists() {
+            "Take their version".to_string()
+        } else {
+            "Take their version (delete the file)".to_string()
+        },
+    ];
+    // Only files both sides still have contain conflict markers
+    let has_markers = kind.ours_exists() && kind.theirs_exists();
+    if has_markers {
+        actions.push("Walk through each conflict".to_string());
+        actions.push("Open in editor".to_string());
+        actions.push("Mark as resolved".to_string());
+    }
+
+    // The entry's path is relative to the top of the work tree, not to where Gust was started
+    let file = backend.toplevel()?.join(&entry.path);
+    let path: OsString = file.clone().into();
+
+    match cli::choice_single(actions, String::from("Select action"), false, true)? {
+        UserResponse::Some(0) => take_side(backend, path, true, kind.ours_exists()),
+        UserResponse::Some(1) => take_side(backend, path, false, kind.theirs_exists()),
+        UserResponse::Some(2) => {
+            walk_conflicts(&file)?;
+            offer_mark_resolved(backend, &file)
+        }
+        UserResponse::Some(3) => {
+            edit_file(&file)?;
+            offer_mark_resolved(backend, &file)
+        }
+        UserResponse::Some(4) => mark_resolved(backend, &file),
+        _ => Ok(()),
+    }
+}
+
+/// Resolve each conflicted file in `paths` with our or their version, without prompting
+pub fn take_side_for_paths(
+    backend: &impl GitBackend,
+    paths: Vec<String>,
+    ours: bool,
+) -> Result<(), GustError> {
+    let repo_status = backend.status()?;
+    let toplevel = backend.toplevel()?;
+    // The paths are typed relative to the current directory, git status lists them from the top
+    let prefix = PathBuf::from(backend.rev_parse("--show-prefix")?);
+    for path in paths {
+        let from_top = ignore::root_relative(&prefix, Path::new(&path));
+        let entry = repo_status
+            .entries
+            .iter()
+            .find(|e| e.path == from_top)
+            .ok_or_else(|| format!("'{}' has no conflicts", path))?;
+        let kind = entry
+            .conflict
+            .ok_or_else(|| format!("'{}' has no conflicts", path))?;
+        let side_exists = if ours {
+            kind.ours_exists()
+        } else {
+            kind.theirs_exists()
+        };
+        take_side(
+            backend,
+            toplevel.join(&entry.path).into_os_string(),
+            ours,
+            side_exists,
+        )?;
+        println!("{}", format!("Resolved {}", path).bright_green());
+    }
+    Ok(())
+}
+
+fn take_side(
+    backend: &impl GitBackend,
+    path: OsString,
+    ours: bool,
+    side_exists: bool,
+) -> Result<(), GustError> {
+    if side_exists {
+        let side = if ours { "--ours" } else { "--theirs" };
+        backend.checkout(vec![
+            OsString::from(side),
+            OsString::from("--"),
+            path.clone(),
+        ])?;
+        backend.add(vec![path])
+    } else {
+        backend.rm(vec![path], false)
+    }
+}
+
+fn read_file(file: &Path) -> Result<String, GustError> {
+    std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e).into())
+}
+
+fn walk_conflicts(file: &Path) -> Result<(), GustError> {
+    let mut segments = conflicts::parse_conflicts(&read_file(file)?)?;
+    let total = segments
+        .iter()
+        .filter(|s| matches!(s, Segment::Conflict(_)))
+        .count();
+
+    if total == 0 {
+        println!("{}", "No conflict markers were found".bright_yellow());
+        return Ok(());
+    }
+
+    let mut number = 0;
+    for segment in segments.iter_mut() {
+        let hunk = match segment {
+            Segment::Conflict(hunk) => hunk,
+            Segment::Text(_) => continue,
+        };
+        number += 1;
+
+        println!("\n{}", format!("Conflict {}/{}", number, total).bold());
+        hunk.print();
+
+        let choices = vec![
+            "Keep ours".to_string(),
+            "Keep theirs".to_string(),
+            "Keep both, ours first".to_string(),
+            "Keep both, theirs first".to_string(),
+            "Leave unresolved".to_string(),
+        ];
+        hunk.resolution =
+            match cli::choice_single(choices, String::from("Select side"), false, false)? {
+                UserResponse::Some(0) => Some(Resolution::Ours),
+                UserResponse::Some(1) => Some(Resolution::Theirs),
+                UserResponse::Some(2) => Some(Resolution::OursThenTheirs),
+                UserResponse::Some(3) => Some(Resolution::TheirsThenOurs),
+                _ => None,
+            };
+    }
+
+    std::fs::write(file, conflicts::render_segments(&segments))
+        .map_err(|e| format!("{}: {}", file.display(), e).into())
+}
+
+fn edit_file(file: &Path) -> Result<(), GustError> {
+    let content = read_file(file)?;
+    // `None` means the editor was closed without saving
+    if let Some(edited) = cli::edit_text(&content, false)? {
+        std::fs::write(file, edited).map_err(|e| format!("{}: {}", file.display(), e))?;
+    }
+    Ok(())
+}
+
+fn offer_mark_resolved(backend: &impl GitBackend, file: &Path) -> Result<(), GustError> {
+    if conflicts::has_conflict_markers(&read_file(file)?) {
+        println!(
+            "{}",
+            "The file still has unresolved conflicts".bright_yellow()
+        );
+        Ok(())
+    } else if cli::ask_yes_no(
+        "All conflicts resolved, mark the file as resolved?".to_string(),
+        true,
+    )? {
+        mark_resolved(backend, file)
+    } else {
+        Ok(())
+    }
+}
+
+fn mark_resolved(backend: &impl GitBackend, file: &Path) -> Result<(), GustError> {
+    if conflicts::has_conflict_markers(&read_file(file)?)
+        && !cli::ask_yes_no(
+            "The file still has conflict markers, mark it as resolved anyway?".to_string(),
+            false,
+        )?
+    {
+        return Ok(());
+    }
+    backend.add(vec![file.to_path_buf()])
+}
+
+/// Offer to continue (or abort) a merge, rebase, cherry-pick or revert once nothing is conflicted
+fn offer_continue(backend: &impl GitBackend) -> Result<(), GustError> {
+    let operation = match backend.operation_in_progress()? {
+        Some(operation) => operation,
+        None => return Ok(()),
+    };
+
+    let choices = vec![
+        format!("Continue the {}", operation),
+        format!("Abort the {}", operation),
+        "Leave it for now".to_string(),
+    ];
+
+    match cli::choice_single(
+        choices,
+        format!("A {} is in progress", operation),
+        false,
+        false,
+    )? {
+        UserResponse::Some(0) => backend.operation(operation, "continue"),
+        UserResponse::Some(1) => backend.operation(operation, "abort"),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,240 @@
+use crate::backend::GitBackend;
+use crate::cli::{self, UserResponse};
+use crate::diff;
+use crate::error::GustError;
+use crate::menus;
+use crate::settings::Config;
+use crate::status::FileEntry;
+
+use colored::Colorize;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DiffMode {
+    Unified,
+    Word,
+    SideBySide,
+}
+
+pub fn git_diff_cli(backend: &impl GitBackend, _config: &Config) -> Result<(), GustError> {
+    println!(
+        "{} {} {}",
+        "Opening".green(),
+        "Diff".bold().green(),
+        "menu".green()
+    );
+
+    let choices = vec![
+        "Unstaged changes".to_string(),
+        "Staged changes".to_string(),
+        "Compare two commits".to_string(),
+    ];
+
+    // The options given to `git diff` to select what is being compared, and the files
+    // that have changed between the two sides, relative to the top of the work tree
+    let (source, files): (Vec<String>, Vec<String>) =
+        match cli::choice_single(choices, String::from("Select what to diff"), false, false)? {
+            UserResponse::Some(0) => {
+                let repo_status = backend.status()?;
+                // git shows conflicts as combined diffs, which the Conflicts menu deals with
+                let (conflicted, changed): (Vec<&FileEntry>, Vec<&FileEntry>) = repo_status
+                    .unstaged()
+                    .into_iter()
+                    .filter(|e| !e.is_untracked())
+                    .partition(|e| e.is_unmerged());
+                if !conflicted.is_empty() {
+                    println!(
+                        "{}",
+                        format!(
+                        "Leaving out {} conflicted file(s), resolve them from the Conflicts menu",
+                        conflicted.len()
+                    )
+                        .bright_yellow()
+                    );
+                }
+                let files = changed
+                    .iter()
+                    .map(|e| e.path.display().to_string())
+                    .collect();
+                (vec![], files)
+            }
+            UserResponse::Some(1) => {
+                let repo_status = backend.status()?;
+                let files = repo_status
+                    .staged()
+                    .iter()
+                    .flat_map(|e| {
+                        let mut paths = vec![e.path.clone()];
+                        paths.extend(e.orig_path.clone());
+                        paths
+                    })
+                    .map(|p| p.display().to_string())
+                    .collect();
+                (vec!["--cached".to_string()], files)
+            }
+            UserResponse::Some(2) => {
+                let base = match menus::choose_commit(
+                    backend,
+                    None,
+                    "Select the older commit:".to_string(),
+                )? {
+                    Some(hash) => hash,
+                    None => return Ok(()),
+                };
+                let target = match menus::choose_commit(
+                    backend,
+                    None,
+                    "Select the newer commit:".to_string(),
+                )? {
+                    Some(hash) => hash,
+                    None => return Ok(()),
+                };
+                let source = vec![base, target];
+
+                let mut options = vec!["--name-only".to_string()];
+                options.extend(source.clone());
+                let files = match backend.diff(options, Vec::<String>::new())? {
+                    Some(names) => names.lines().map(|l| l.to_string()).collect(),
+                    None => vec![],
+                };
+                (source, files)
+            }
+            _ => return Err("No diff source was selected".into()),
+        };
+
+    if files.is_empty() {
+        println!("{}", "No differences found".bright_yellow());
+        return Ok(());
+    }
+
+    // git is run from wherever Gust was started, so the files are handed over in full
+    let toplevel = backend.toplevel()?;
+    let mut mode = DiffMode::Unified;
+    // `None` shows every file at once
+    let mut current: Option<usize> = None;
+
+    loop {
+        let paths = match current {
+            Some(index) => vec![toplevel.join(&files[index])],
+            None => files.iter().map(|f| toplevel.join(f)).collect(),
+        };
+        cli::page_output(render(backend, &source, paths, mode)?)?;
+
+        println!(
+            "{}",
+            match current {
+                Some(index) => format!("File {}/{}: {}", index + 1, files.len(), files[index]),
+                None => format!("All {} files", files.len()),
+            }
+            .bold()
+        );
+
+        let actions = vec![
+            "Next file".to_string(),
+            "Previous file".to_string(),
+            "Choose a file".to_string(),
+            "Show all files".to_string(),
+            toggle_label("word diff", mode == DiffMode::Word),
+            toggle_label("side-by-side", mode == DiffMode::SideBySide),
+            "Back to menu".to_string(),
+        ];
+
+        match cli::choice_single(actions, String::from("Select action"), false, false)? {
+            UserResponse::Some(0) => {
+                current = Some(current.map_or(0, |i| (i + 1) % files.len()));
+            }
+            UserResponse::Some(1) => {
+                current =
+                    Some(current.map_or(files.len() - 1, |i| (i + files.len() - 1) % files.len()));
+            }
+            UserResponse::Some(2) => {
+                if let UserResponse::Some(index) =
+                    cli::choice_single(files.clone(), String::from("Select a file"), false, false)?
+                {
+                    current = Some(index);
+                }
+            }
+            UserResponse::Some(3) => current = None,
+            UserResponse::Some(4) => {
+                mode = if mode == DiffMode::Word {
+                    DiffMode::Unified
+                } else {
+                    DiffMode::Word
+                };
+            }
+            UserResponse::Some(5) => {
+                mode = if mode == DiffMode::SideBySide {
+                    DiffMode::Unified
+                } else {
+                    DiffMode::SideBySide
+                };
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+fn toggle_label(name: &str, enabled: bool) -> String {
+    if enabled {
+        format!("Turn off {}", name)
+    } else {
+        format!("Turn on {}", name)
+    }
+}
+
+fn render(
+    backend: &impl GitBackend,
+    source: &[String],
+    paths: Vec<PathBuf>,
+    mode: DiffMode,
+) -> Result<String, GustError> {
+    let mut options = vec!["--no-color".to_string(), "--no-ext-diff".to_string()];
+    if mode == DiffMode::Word {
+        options.push("--word-diff=plain".to_string());
+    }
+    options.extend(source.iter().cloned());
+
+    let diff_output = match backend.diff(options, paths)? {
+        Some(output) => output,
+        None => return Ok("No differences found".bright_yellow().to_string()),
+    };
+
+    match mode {
+        DiffMode::Word => Ok(diff::render_word_diff(&diff_output)),
+        DiffMode::Unified => Ok(diff::render_unified(&diff::parse_diff(&diff_output)?)),
+        DiffMode::SideBySide => {
+            let (_, width) = console::Term::stdout().size();
+            Ok(diff::render_side_by_side(
+                &diff::parse_diff(&diff_output)?,
+                width as usize,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+    use crate::prompt::scripted::{script, Answer};
+
+    #[test]
+    fn leaves_conflicted_files_out_of_unstaged_changes() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        repo.git(&["checkout", "--quiet", "-b", "other"]);
+        repo.commit_file("a.txt", "theirs\n", "Change a.txt on other");
+        repo.git(&["checkout", "--quiet", "main"]);
+        repo.commit_file("a.txt", "ours\n", "Change a.txt on main");
+        assert!(std::process::Command::new("git")
+            .args(["merge", "--quiet", "other"])
+            .output()
+            .map(|o| !o.status.success())
+            .unwrap());
+
+        // With only the conflict changed there's nothing left to page through
+        script(vec![Answer::Select(0)]);
+        git_diff_cli(&GitCli, &Config::default()).unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+use crate::backend::GitBackend;
+use crate::cli::{self, UserResponse};
+use crate::diff::{self, FilePatch};
+use crate::error::GustError;
+use crate::status::{FileEntry, FileState};
+
+use colored::Colorize;
+use std::ops::Range;
+
+pub fn git_add_hunks_cli(backend: &impl GitBackend) -> Result<(), GustError> {
+    let repo_status = backend.status()?;
+    let entries: Vec<&FileEntry> = repo_status
+        .unstaged()
+        .into_iter()
+        .filter(|e| e.worktree == FileState::Modified && !e.is_unmerged())
+        .collect();
+
+    let patch = match choose_file_patch(backend, &entries, vec![])? {
+        Some(patch) => patch,
+        None => return Ok(()),
+    };
+
+    match pick_hunks(patch, "Stage")?.to_patch(false) {
+        None => println!("{}", "Nothing selected".bright_yellow()),
+        Some(selected) => {
+            backend.apply(selected, vec!["--cached".to_string()])?;
+            println!("{}", "Hunks Staged!".bright_green());
+        }
+    }
+    Ok(())
+}
+
+pub fn git_reset_hunks_cli(backend: &impl GitBackend) -> Result<(), GustError> {
+    let repo_status = backend.status()?;
+    let entries: Vec<&FileEntry> = repo_status
+        .staged()
+        .into_iter()
+        .filter(|e| e.index == FileState::Modified)
+        .collect();
+
+    let patch = match choose_file_patch(backend, &entries, vec!["--cached".to_string()])? {
+        Some(patch) => patch,
+        None => return Ok(()),
+    };
+
+    match pick_hunks(patch, "Unstage")?.to_patch(true) {
+        None => println!("{}", "Nothing selected".bright_yellow()),
+        Some(selected) => {
+            backend.apply(
+                selected,
+                vec!["--cached".to_string(), "--reverse".to_string()],
+            )?;
+            println!("{}", "Hunks Unstaged!".bright_green());
+        }
+    }
+    Ok(())
+}
+
+/// Have the user pick one of `entries` and return its parsed diff
+fn choose_file_patch(
+    backend: &impl GitBackend,
+    entries: &[&FileEntry],
+    diff_options: Vec<String>,
+) -> Result<Option<FilePatch>, GustError> {
+    if entries.is_empty() {
+        println!("{}", "No modified files, returning to menu".bright_yellow());
+        return Ok(None);
+    }
+
+    let choices: Vec<String> = entries
+        .iter()
+        .map(|e| e.label().yellow().to_string())
+        .collect();
+    let entry = match cli::choice_single(choices, "Select a file:".to_string(), false, true)? {
+        UserResponse::Some(index) => entries[index],
+        _ => {
+            println!("'None' selected, returning to menu");
+            return Ok(None);
+        }
+    };
+
+    let mut options = vec!["--no-color".to_string(), "--no-ext-diff".to_string()];
+    options.extend(diff_options);
+
+    let path = backend.toplevel()?.join(&entry.path);
+    let diff_output = match backend.diff(options, vec![path])? {
+        Some(output) => output,
+        None => {
+            println!("{}", "No changes found in that file".bright_yellow());
+            return Ok(None);
+        }
+    };
+
+    match diff::parse_diff(&diff_output)?.into_iter().next() {
+        Some(patch) if !patch.hunks.is_empty() => Ok(Some(patch)),
+        _ => {
+            println!(
+                "{}",
+                "That file has no hunks to pick from (binary?)".bright_yellow()
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// Walk through every hunk of `patch` letting the user choose which changes to select
+fn pick_hunks(mut patch: FilePatch, verb: &str) -> Result<FilePatch, GustError> {
+    // Every item is a hunk index and the range of its lines the item covers
+    let mut items: Vec<(usize, Range<usize>)> = patch
+        .hunks
+        .iter()
+        .enumerate()
+        .map(|(i, h)| (i, 0..h.lines.len()))
+        .collect();
+
+    let path = patch.path().unwrap_or_default();
+    let mut current = 0;
+    while current < items.len() {
+        let (hunk_index, range) = items[current].clone();
+        let hunk = &mut patch.hunks[hunk_index];
+
+        println!(
+            "\n{}",
+            format!("({}/{}) {}", current + 1, items.len(), path).bold()
+        );
+        hunk.print_range(range.clone());
+
+        let groups: Vec<Range<usize>> = hunk
+            .change_groups()
+            .into_iter()
+            .filter(|g| g.start >= range.start && g.end <= range.end)
+            .collect();
+
+        let mut actions = vec![
+            format!("{} this hunk", verb),
+            "Skip this hunk".to_string(),
+            "Pick individual lines".to_string(),
+        ];
+        if groups.len() > 1 {
+            actions.push("Split into smaller hunks".to_string());
+        }
+        actions.push("Done".to_string());
+        let done_index = actions.len() - 1;
+
+        match cli::choice_single(actions, "Select action".to_string(), false, false)? {
+            UserResponse::Some(0) => {
+                hunk.set_selected(range, true);
+                current += 1;
+            }
+            UserResponse::Some(1) => {
+                hunk.set_selected(range, false);
+                current += 1;
+            }
+            UserResponse::Some(2) => {
+                pick_lines(hunk, range, verb)?;
+                current += 1;
+            }
+            UserResponse::Some(index) if index == done_index => break,
+            UserResponse::Some(_) => {
+                // Replace this item by one item per change group, then show the first
+                items.splice(
+                    current..current + 1,
+                    groups.into_iter().map(|g| (hunk_index, g)),
+                );
+            }
+            _ => return Err("No action was selected".into()),
+        }
+    }
+
+    Ok(patch)
+}
+
+fn pick_lines(hunk: &mut diff::Hunk, range: Range<usize>, verb: &str) -> Result<(), GustError> {
+    let changes: Vec<usize> = range.filter(|i| hunk.lines[*i].is_change()).collect();
+    // Number the lines so identical lines can be told apart
+    let choices: Vec<String> = changes
+        .iter()
+        .enumerate()
+        .map(|(n, i)| format!("{:>3} {}", n + 1, hunk.lines[*i].colored()))
+        .collect();
+
+    let selected: Vec<usize> = match cli::choice_no_limit(
+        choices,
+        format!("Select lines to {}:", verb.to_lowercase()),
+        true,
+        true,
+    )? {
+        UserResponse::All => changes.clone(),
+        UserResponse::None => vec![],
+        UserResponse::Some(picked) => picked
+            .iter()
+            .filter_map(|p| p.split_whitespace().next()?.parse::<usize>().ok())
+            .map(|n| changes[n - 1])
+            .collect(),
+    };
+
+    for i in changes {
+        hunk.lines[i].selected = selected.contains(&i);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+    use crate::prompt::scripted::{script, Answer};
+
+    #[test]
+    fn stages_picked_lines_of_a_crlf_file() {
+        let repo = TestRepo::new();
+        repo.git(&["config", "core.autocrlf", "false"]);
+        repo.commit_file("a.txt", "a\r\nb\r\nc\r\n", "Add a.txt");
+        repo.write("a.txt", "a\r\nB\r\nc\r\nd\r\n");
+
+        // The file, pick lines, then "-b" and "+B" out of "All", -b, +B, +d, "None"
+        script(vec![
+            Answer::Select(0),
+            Answer::Select(2),
+            Answer::MultiSelect(vec![1, 2]),
+        ]);
+        git_add_hunks_cli(&GitCli).unwrap();
+
+        assert_eq!(repo.git(&["show", ":a.txt"]), "a\r\nB\r\nc\r");
+    }
+
+    #[test]
+    fn unstages_a_hunk() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\nb\n", "Add a.txt");
+        repo.write("a.txt", "a\nB\n");
+        repo.git(&["add", "a.txt"]);
+
+        script(vec![Answer::Select(0), Answer::Select(0)]);
+        git_reset_hunks_cli(&GitCli).unwrap();
+
+        assert_eq!(repo.git(&["diff", "--cached", "--name-only"]), "");
+        assert_eq!(repo.git(&["diff", "--name-only"]), "a.txt");
+    }
+}
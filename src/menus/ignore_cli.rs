@@ -0,0 +1,238 @@
+use crate::backend::GitBackend;
+use crate::cli::{self, UserResponse};
+use crate::error::GustError;
+use crate::ignore::{self, IgnoreMatch, IgnoreRule, IgnoreScope};
+use crate::settings::Config;
+use crate::status::FileEntry;
+
+use colored::Colorize;
+use std::path::Path;
+
+pub fn git_ignore_cli(backend: &impl GitBackend, _config: &Config) -> Result<(), GustError> {
+    println!(
+        "{} {} {}",
+        "Opening".green(),
+        "Ignore".bold().green(),
+        "menu".green()
+    );
+
+    let choices = vec![
+        "Ignore untracked files".to_string(),
+        "Add a template".to_string(),
+        "Why is this file ignored?".to_string(),
+    ];
+
+    match cli::choice_single(choices, String::from("Select action"), false, true)? {
+        UserResponse::Some(0) => ignore_untracked_cli(backend),
+        UserResponse::Some(1) => {
+            let file = backend.toplevel()?.join(".gitignore");
+            add_template_cli(&file)
+        }
+        UserResponse::Some(2) => explain_ignored(backend),
+        _ => {
+            println!("'None' selected, returning to menu");
+            Ok(())
+        }
+    }
+}
+
+/// Pick an untracked file and add a pattern ignoring it, its extension or its directory
+pub fn ignore_untracked_cli(backend: &impl GitBackend) -> Result<(), GustError> {
+    let repo_status = backend.status()?;
+    let entries: Vec<&FileEntry> = repo_status
+        .entries
+        .iter()
+        .filter(|e| e.is_untracked())
+        .collect();
+
+    if entries.is_empty() {
+        println!(
+            "{}",
+            "No untracked files, returning to menu".bright_yellow()
+        );
+        return Ok(());
+    }
+
+    let choices: Vec<String> = entries
+        .iter()
+        .map(|e| e.label().bright_green().to_string())
+        .collect();
+    let entry = match cli::choice_single(
+        choices,
+        String::from("Select a file to ignore"),
+        false,
+        true,
+    )? {
+        UserResponse::Some(index) => entries[index],
+        _ => {
+            println!("'None' selected, returning to menu");
+            return Ok(());
+        }
+    };
+
+    let is_dir = entry.path.to_string_lossy().ends_with('/');
+
+    // Offer only the scopes that make sense for this path
+    let mut rules = Vec::<IgnoreRule>::new();
+    let mut labels = Vec::<String>::new();
+    for (scope, label) in [
+        (
+            IgnoreScope::File,
+            if is_dir {
+                "This directory"
+            } else {
+                "This file"
+            },
+        ),
+        (IgnoreScope::Extension, "Every file with this extension"),
+        (IgnoreScope::Directory, "The directory it is in"),
+    ] {
+        if scope == IgnoreScope::Extension && is_dir {
+            continue;
+        }
+        if let Some(rule) = IgnoreRule::new(&entry.path, scope) {
+            labels.push(format!("{} ({})", label, rule.root_pattern));
+            rules.push(rule);
+        }
+    }
+
+    let rule =
+        match cli::choice_single(labels, String::from("What should be ignored?"), false, true)? {
+            UserResponse::Some(index) => rules[index].clone(),
+            _ => {
+                println!("'None' selected, returning to menu");
+                return Ok(());
+            }
+        };
+
+    let toplevel = backend.toplevel()?;
+    let mut targets = vec![(
+        toplevel.join(".gitignore"),
+        rule.root_pattern.clone(),
+        ".gitignore at the repository root".to_string(),
+    )];
+    if !rule.nested_dir.as_os_str().is_empty() {
+        let nested = rule.nested_dir.join(".gitignore");
+        targets.push((
+            toplevel.join(&nested),
+            rule.nested_pattern.clone(),
+            format!("{} (only applies inside that directory)", nested.display()),
+        ));
+    }
+    targets.push((
+        backend.git_dir()?.join("info").join("exclude"),
+        rule.root_pattern.clone(),
+        ".git/info/exclude (only ignored for you, not shared)".to_string(),
+    ));
+
+    let target_labels: Vec<String> = targets.iter().map(|t| t.2.clone()).collect();
+    let (file, pattern, _) = match cli::choice_single(
+        target_labels,
+        String::from("Where should the pattern go?"),
+        false,
+        true,
+    )? {
+        UserResponse::Some(index) => targets[index].clone(),
+        _ => {
+            println!("'None' selected, returning to menu");
+            return Ok(());
+        }
+    };
+
+    report_added(&file, ignore::append_patterns(&file, &[pattern], None)?);
+    Ok(())
+}
+
+/// Merge one of the bundled templates into the ignore file at `file`
+pub fn add_template_cli(file: &Path) -> Result<(), GustError> {
+    let names: Vec<String> = ignore::TEMPLATES
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    match cli::choice_single(
+        names.clone(),
+        String::from("Select a template"),
+        false,
+        true,
+    )? {
+        UserResponse::Some(index) => {
+            report_added(file, ignore::merge_template(file, &names[index])?);
+            Ok(())
+        }
+        _ => {
+            println!("'None' selected, returning to menu");
+            Ok(())
+        }
+    }
+}
+
+fn report_added(file: &Path, added: Vec<String>) {
+    if added.is_empty() {
+        println!(
+            "{}",
+            format!("{} already had those patterns", file.display()).bright_yellow()
+        );
+    } else {
+        println!(
+            "{}\n{}",
+            format!("Added to {}:", file.display()).bright_green(),
+            added.join("\n")
+        );
+    }
+}
+
+fn explain_ignored(backend: &impl GitBackend) -> Result<(), GustError> {
+    let path = cli::get_input("Enter the path of the file: ".to_string())?;
+
+    match backend.check_ignore(path.clone())? {
+        None => println!("{}", format!("'{}' is not ignored", path).bright_green()),
+        Some(IgnoreMatch {
+            source,
+            line,
+            pattern,
+        }) => {
+            if let Some(negated) = pattern.strip_prefix('!') {
+                println!(
+                    "{}",
+                    format!(
+                        "'{}' is un-ignored by '!{}' on line {} of {}",
+                        path, negated, line, source
+                    )
+                    .bright_green()
+                );
+            } else {
+                println!(
+                    "{}",
+                    format!(
+                        "'{}' is ignored by '{}' on line {} of {}",
+                        path, pattern, line, source
+                    )
+                    .bright_yellow()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::{GitBackend, GitCli};
+
+    #[test]
+    fn explains_patterns_from_files_with_colons_in_their_path() {
+        let repo = TestRepo::new();
+        let excludes = repo.outside("ex:cludes");
+        std::fs::write(&excludes, "# logs\n*.log\n").unwrap();
+        repo.git(&["config", "core.excludesFile", excludes.to_str().unwrap()]);
+
+        let found = GitCli.check_ignore("a:b.log".to_string()).unwrap().unwrap();
+        assert_eq!(found.source, excludes.display().to_string());
+        assert_eq!(found.line, "2");
+        assert_eq!(found.pattern, "*.log");
+
+        assert!(GitCli.check_ignore("a.txt".to_string()).unwrap().is_none());
+    }
+}
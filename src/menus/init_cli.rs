@@ -0,0 +1,204 @@
+use crate::backend::GitBackend;
+use crate::cli::{self, UserResponse};
+use crate::error::GustError;
+use crate::menus;
+use crate::settings::Config;
+
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+
+/// Shown when Gust isn't started inside a repository. Returns `true` once the
+/// current directory is a repository Gust can work in.
+pub fn git_no_repo_cli(backend: &impl GitBackend, config: &Config) -> Result<bool, GustError> {
+    println!("{}", "No git repository was found here".bright_yellow());
+
+    let choices = vec![
+        "Create a new repository".to_string(),
+        "Clone a repository".to_string(),
+        "Quit".to_string(),
+    ];
+
+    match cli::choice_single(choices, String::from("Select action"), false, false)? {
+        UserResponse::Some(0) => git_init_cli(backend, config)?,
+        UserResponse::Some(1) => git_clone_cli(backend)?,
+        _ => return Ok(false),
+    }
+
+    backend.is_repo()
+}
+
+fn git_init_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    let directory = choose_directory("Directory to create the repository in", ".")?;
+    let branch = cli::get_input_default("Initial branch name".to_string(), "main".to_string())?;
+
+    std::fs::create_dir_all(&directory)?;
+    backend.init(directory.clone(), branch)?;
+    std::env::set_current_dir(&directory)?;
+
+    if cli::ask_yes_no("Add a starter .gitignore?".to_string(), true)? {
+        menus::add_template_cli(Path::new(".gitignore"))?;
+    }
+
+    if cli::ask_yes_no(
+        "Stage every file and make the first commit?".to_string(),
+        true,
+    )? {
+        backend.add(vec!["."])?;
+        let message =
+            cli::get_input_default("Commit message".to_string(), "Initial commit".to_string())?;
+        backend.commit(Some(vec!["-m".to_string(), message]), config)?;
+    }
+
+    println!("{}", "Repository created!".bright_green());
+    Ok(())
+}
+
+fn git_clone_cli(backend: &impl GitBackend) -> Result<(), GustError> {
+    let source = cli::get_input("Enter the url or path of the repository: ".to_string())?;
+
+    // Suggest the last part of the url without `.git`, like `git clone` does
+    let default_dir = source
+        .trim_end_matches('/')
+        .rsplit(['/', '\\', ':'])
+        .next()
+        .unwrap_or("repository")
+        .trim_end_matches(".git")
+        .to_string();
+    let directory = choose_directory("Directory to clone into", &default_dir)?;
+
+    let depth = match cli::get_input_optional(
+        "History depth (leave empty for the full history)".to_string(),
+    )? {
+        Some(depth) => Some(
+            depth
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| format!("'{}' isn't a valid depth", depth))?,
+        ),
+        None => None,
+    };
+    let branch =
+        cli::get_input_optional("Branch to check out (leave empty for the default)".to_string())?;
+
+    backend.clone_repo(source, directory.clone(), depth, branch)?;
+    std::env::set_current_dir(&directory)?;
+    Ok(())
+}
+
+fn choose_directory(prompt: &str, default: &str) -> Result<PathBuf, GustError> {
+    let directory = cli::get_input_default(prompt.to_string(), default.to_string())?;
+    Ok(PathBuf::from(directory.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+    use crate::prompt::scripted::{script, Answer};
+
+    use std::process::Command;
+
+    fn git_in(directory: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(directory)
+            .args(args)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn creates_a_repository_with_a_first_commit() {
+        let repo = TestRepo::new();
+        let directory = repo.outside("new");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("a.txt"), "a\n").unwrap();
+
+        script(vec![
+            Answer::Select(0),
+            Answer::Text(directory.display().to_string()),
+            Answer::Text("trunk".to_string()),
+            Answer::Confirm(false),
+            Answer::Confirm(true),
+            Answer::Text("Initial commit".to_string()),
+        ]);
+        assert!(git_no_repo_cli(&GitCli, &Config::default()).unwrap());
+
+        assert_eq!(
+            std::env::current_dir().unwrap().canonicalize().unwrap(),
+            directory.canonicalize().unwrap()
+        );
+        assert_eq!(git_in(&directory, &["branch", "--show-current"]), "trunk");
+        assert_eq!(
+            git_in(&directory, &["log", "--format=%s"]),
+            "Initial commit"
+        );
+        assert_eq!(git_in(&directory, &["ls-files"]), "a.txt");
+    }
+
+    #[test]
+    fn clones_a_branch_of_a_local_repository() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        repo.git(&["checkout", "--quiet", "-b", "topic"]);
+        repo.commit_file("b.txt", "b\n", "Add b.txt");
+        repo.git(&["checkout", "--quiet", "main"]);
+        let directory = repo.outside("copy");
+
+        script(vec![
+            Answer::Select(1),
+            Answer::Text(repo.work_tree.display().to_string()),
+            Answer::Text(directory.display().to_string()),
+            Answer::Text(String::new()),
+            Answer::Text("topic".to_string()),
+        ]);
+        assert!(git_no_repo_cli(&GitCli, &Config::default()).unwrap());
+
+        assert_eq!(git_in(&directory, &["branch", "--show-current"]), "topic");
+        assert_eq!(
+            git_in(&directory, &["log", "--format=%s"]),
+            "Add b.txt\nAdd a.txt"
+        );
+    }
+
+    #[test]
+    fn clones_a_shallow_copy_of_a_local_repository() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        repo.commit_file("b.txt", "b\n", "Add b.txt");
+        let directory = repo.outside("shallow");
+
+        script(vec![
+            Answer::Select(1),
+            Answer::Text(repo.work_tree.display().to_string()),
+            Answer::Text(directory.display().to_string()),
+            Answer::Text("1".to_string()),
+            Answer::Text(String::new()),
+        ]);
+        assert!(git_no_repo_cli(&GitCli, &Config::default()).unwrap());
+
+        assert_eq!(git_in(&directory, &["log", "--format=%s"]), "Add b.txt");
+        assert_eq!(
+            git_in(&directory, &["rev-parse", "--is-shallow-repository"]),
+            "true"
+        );
+    }
+
+    #[test]
+    fn rejects_a_depth_that_isnt_a_number() {
+        let repo = TestRepo::new();
+        script(vec![
+            Answer::Select(1),
+            Answer::Text(repo.work_tree.display().to_string()),
+            Answer::Text(repo.outside("copy").display().to_string()),
+            Answer::Text("all".to_string()),
+        ]);
+        let error = git_no_repo_cli(&GitCli, &Config::default()).unwrap_err();
+        assert_eq!(error.to_string(), "'all' isn't a valid depth");
+        assert!(!repo.outside("copy").exists());
+    }
+}
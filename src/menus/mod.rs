@@ -1,19 +1,72 @@
 mod add_cli;
 mod branches_cli;
 mod commit_cli;
-mod main_menu;
+mod conflicts_cli;
+mod diff_cli;
+mod hunks_cli;
+mod ignore_cli;
+mod init_cli;
+mod rebase_cli;
+mod release_cli;
+mod remotes_cli;
 mod remove_cli;
 mod reset_cli;
+mod stash_cli;
+mod tags_cli;
 mod undo_commit_cli;
 
 mod cli_menus;
 
+use crate::backend::GitBackend;
+use crate::cli::{self, UserResponse};
+use crate::error::GustError;
+
+use colored::Colorize;
+
 pub use add_cli::git_add_cli;
-pub use branches_cli::git_branches_cli;
-pub use commit_cli::git_commit_cli;
-pub use main_menu::main_menu;
+pub use branches_cli::{choose_remote_branch, git_branches_cli};
+pub use commit_cli::{compose_message_cli, confirm_rewrite, git_commit_cli};
+pub use conflicts_cli::{
+    git_conflicts_cli, guide_operation_cli, resolve_conflicts_cli, take_side_for_paths,
+};
+pub use diff_cli::git_diff_cli;
+pub use hunks_cli::{git_add_hunks_cli, git_reset_hunks_cli};
+pub use ignore_cli::{add_template_cli, git_ignore_cli, ignore_untracked_cli};
+pub use init_cli::git_no_repo_cli;
+pub use rebase_cli::git_rebase_cli;
+pub use release_cli::git_release_cli;
+pub use remotes_cli::{add_remote, choose_remote, git_remotes_cli};
 pub use remove_cli::git_remove_cli;
 pub use reset_cli::git_reset_cli;
+pub use stash_cli::git_stash_cli;
+pub use tags_cli::git_tags_cli;
 pub use undo_commit_cli::git_undo_commit_cli;
 
 pub use cli_menus::{advanced_menu, basic_menu, git_pull_cli, git_push_cli};
+
+/// Have the user pick a commit from the log of `revision` (HEAD if `None`), returning its hash.
+/// `None` if there are no commits or the user picked 'None'.
+pub fn choose_commit(
+    backend: &impl GitBackend,
+    revision: Option<String>,
+    prompt: String,
+) -> Result<Option<String>, GustError> {
+    let choices: Vec<String> = match backend.log(revision)? {
+        None => {
+            println!("{}", "No commits found! returning to menu".bright_yellow());
+            return Ok(None);
+        }
+        Some(log) => log.lines().map(|l| l.to_string()).collect(),
+    };
+
+    match cli::choice_single(choices.clone(), prompt, false, true)? {
+        UserResponse::Some(index) => Ok(choices[index]
+            .split_whitespace()
+            .next()
+            .map(|hash| hash.to_string())),
+        _ => {
+            println!("'None' selected, returning to menu");
+            Ok(None)
+        }
+    }
+}
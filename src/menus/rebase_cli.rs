@@ -0,0 +1,258 @@
+use crate::backend::GitBackend;
+use crate::cli::{self, UserResponse};
+use crate::error::GustError;
+use crate::git;
+use crate::menus;
+use crate::rebase::{self, TodoAction, TodoItem};
+use crate::settings::Config;
+use crate::status::Operation;
+
+use colored::Colorize;
+use strum::IntoEnumIterator;
+
+pub fn git_rebase_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    println!(
+        "{} {} {}",
+        "Opening".green(),
+        "Rebase".bold().green(),
+        "menu".green()
+    );
+
+    if backend.operation_in_progress()? == Some(Operation::Rebase) {
+        println!("{}", "A rebase is already in progress".bright_yellow());
+        return menus::guide_operation_cli(backend, config, Operation::Rebase);
+    }
+
+    let prompt = "Select the last commit to keep as it is, the ones above it can be changed"
+        .bright_yellow()
+        .to_string();
+    let base = match menus::choose_commit(backend, None, prompt)? {
+        Some(base) => base,
+        None => return Ok(()),
+    };
+    let commits = backend.log_commits(format!("{}..HEAD", base))?;
+    let mut todo = rebase::todo_from_commits(&commits);
+    if todo.is_empty() {
+        println!("{}", "There are no commits after that one".bright_yellow());
+        return Ok(());
+    }
+    if commits.iter().any(|c| c.merge) {
+        println!(
+            "{}",
+            "Merge commits are left out, the rebase puts their changes in a straight line"
+                .bright_yellow()
+        );
+    }
+
+    // Before any reordering: a remote branch with any of the commits has the oldest one
+    if let Some(oldest) = commits.last() {
+        if !menus::confirm_rewrite(backend, &oldest.hash)? {
+            return Err(GustError::Cancelled);
+        }
+    }
+    if !edit_todo(&mut todo)? {
+        println!("User canceled the rebase");
+        return Ok(());
+    }
+
+    run_rebase(backend, config, &base, &todo)
+}
+
+/// Let the user change the todo list, `false` if they gave up on the rebase
+fn edit_todo(todo: &mut [TodoItem]) -> Result<bool, GustError> {
+    loop {
+        println!("{}", "The rebase will, from oldest to newest:".bold());
+        for (index, item) in todo.iter().enumerate() {
+            println!("  {}. {}", index + 1, todo_label(item));
+        }
+
+        let mut choices: Vec<String> = todo.iter().map(todo_label).collect();
+        choices.push("Start the rebase".bright_green().to_string());
+        choices.push("Cancel".to_string());
+
+        let index = match cli::choice_single(
+            choices,
+            String::from("Select a commit to change"),
+            false,
+            false,
+        )? {
+            UserResponse::Some(index) => index,
+            _ => return Ok(false),
+        };
+
+        if index == todo.len() {
+            match rebase::check_todo(todo) {
+                Some(problem) => println!("{}", problem.bright_red()),
+                None => return Ok(true),
+            }
+        } else if index > todo.len() {
+            return Ok(false);
+        } else {
+            edit_item(todo, index)?;
+        }
+    }
+}
+
+fn edit_item(todo: &mut [TodoItem], index: usize) -> Result<(), GustError> {
+    let actions: Vec<TodoAction> = TodoAction::iter().collect();
+    let mut choices: Vec<String> = actions
+        .iter()
+        .map(|a| format!("{} - {}", a, a.description()))
+        .collect();
+    choices.push("Move up".to_string());
+    choices.push("Move down".to_string());
+
+    match cli::choice_single(
+        choices,
+        format!("What should happen to '{}'?", todo[index].subject),
+        false,
+        true,
+    )? {
+        UserResponse::Some(choice) if choice < actions.len() => {
+            todo[index].action = actions[choice];
+        }
+        UserResponse::Some(choice) if choice == actions.len() && index > 0 => {
+            todo.swap(index, index - 1);
+        }
+        UserResponse::Some(choice) if choice > actions.len() && index + 1 < todo.len() => {
+            todo.swap(index, index + 1);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn todo_label(item: &TodoItem) -> String {
+    let action = format!("{:<6}", item.action);
+    let action = match item.action {
+        TodoAction::Pick => action.normal(),
+        TodoAction::Drop => action.bright_red(),
+        _ => action.bright_yellow(),
+    };
+    format!(
+        "{} {} {}",
+        action,
+        &item.hash[..item.hash.len().min(7)],
+        item.subject
+    )
+}
+
+fn run_rebase(
+    backend: &impl GitBackend,
+    config: &Config,
+    base: &str,
+    todo: &[TodoItem],
+) -> Result<(), GustError> {
+    // git opens GIT_SEQUENCE_EDITOR on its own todo list, copying ours over it
+    let todo_file = backend.git_dir()?.join("gust-rebase-todo");
+    std::fs::write(&todo_file, rebase::render_todo(todo))?;
+    let editor = format!("cp {}", git::shell_quote(&todo_file.to_string_lossy()));
+
+    let args = vec![
+        "--interactive".to_string(),
+        "--autostash".to_string(),
+        base.to_string(),
+    ];
+    let result = backend.rebase(args, Some(editor));
+    std::fs::remove_file(&todo_file)?;
+
+    match result {
+        Ok(()) => menus::guide_operation_cli(backend, config, Operation::Rebase),
+        Err(e) if backend.operation_in_progress()? == Some(Operation::Rebase) => {
+            println!("{}", e.to_string().bright_red());
+            menus::guide_operation_cli(backend, config, Operation::Rebase)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+    use crate::prompt::scripted::{script, Answer};
+
+    /// Three commits, the middle one pushed to `origin/main`
+    fn partly_pushed() -> TestRepo {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        repo.commit_file("b.txt", "b\n", "Add b.txt");
+        repo.git(&["update-ref", "refs/remotes/origin/main", "HEAD"]);
+        repo.commit_file("c.txt", "c\n", "Add c.txt");
+        repo
+    }
+
+    #[test]
+    fn asks_before_rewriting_pushed_commits() {
+        let repo = partly_pushed();
+        let head = repo.git(&["rev-parse", "HEAD"]);
+
+        // The log is newest first, the base is the first commit
+        script(vec![Answer::Select(2), Answer::Confirm(false)]);
+        assert!(matches!(
+            git_rebase_cli(&GitCli, &Config::default()),
+            Err(GustError::Cancelled)
+        ));
+        assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+    }
+
+    #[test]
+    fn unpushed_commits_go_straight_to_the_todo_list() {
+        let repo = partly_pushed();
+        let head = repo.git(&["rev-parse", "HEAD"]);
+
+        // The todo list has one commit, then "Start the rebase" and "Cancel"
+        script(vec![Answer::Select(1), Answer::Select(2)]);
+        git_rebase_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+    }
+
+    #[test]
+    fn reorders_squashes_and_drops_commits() {
+        let repo = TestRepo::new();
+        for name in ["a", "b", "c", "d"] {
+            repo.commit_file(&format!("{}.txt", name), "\n", &format!("Add {}.txt", name));
+        }
+
+        // The todo list is b, c, d. Move d above c, squash c into it and drop b.
+        // Each commit's actions are pick, reword, edit, squash, fixup, drop, up and down.
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(2),
+            Answer::Select(6),
+            Answer::Select(2),
+            Answer::Select(3),
+            Answer::Select(0),
+            Answer::Select(5),
+            Answer::Select(3),
+        ]);
+        git_rebase_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(repo.git(&["log", "--format=%s"]), "Add d.txt\nAdd a.txt");
+        assert_eq!(
+            repo.git(&["log", "-1", "--format=%B"]),
+            "Add d.txt\n\nAdd c.txt"
+        );
+        assert_eq!(repo.git(&["ls-files"]), "a.txt\nc.txt\nd.txt");
+        assert!(!repo.work_tree.join(".git/gust-rebase-todo").exists());
+    }
+
+    #[test]
+    fn wont_start_with_a_leading_squash() {
+        let repo = partly_pushed();
+        repo.commit_file("d.txt", "d\n", "Add d.txt");
+        let head = repo.git(&["rev-parse", "HEAD"]);
+
+        // Squash d.txt's commit, the only one after c.txt's, then cancel once it's refused
+        script(vec![
+            Answer::Select(1),
+            Answer::Select(0),
+            Answer::Select(3),
+            Answer::Select(1),
+            Answer::Select(2),
+        ]);
+        git_rebase_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+    }
+}
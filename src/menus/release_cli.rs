@@ -0,0 +1,257 @@
+use crate::backend::GitBackend;
+use crate::changelog;
+use crate::cli::{self, UserResponse};
+use crate::error::GustError;
+use crate::git;
+use crate::release::{self, Bump, Version};
+use crate::settings::{self, Config, ConventionSettings};
+
+use colored::Colorize;
+use std::path::PathBuf;
+
+/// The files Gust knows how to set the version in
+const VERSION_FILES: [&str; 2] = ["Cargo.toml", "package.json"];
+
+pub fn git_release_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    println!(
+        "{} {} {}",
+        "Opening".green(),
+        "Release".bold().green(),
+        "menu".green()
+    );
+
+    if !backend.status()?.staged().is_empty() {
+        return Err(
+            "There are staged changes, commit or unstage them before making a release".into(),
+        );
+    }
+
+    let conventions = settings::load_convention(config.convention.clone())?;
+    let latest = release::latest_version_tag(&backend.tags()?);
+    let changelog = changelog::collect(
+        backend,
+        &conventions,
+        latest.as_ref().map(|(tag, _)| tag.clone()),
+        "HEAD",
+        String::new(),
+    )?;
+
+    match &latest {
+        Some((tag, _)) => println!("The latest release is {}", tag.bold()),
+        None => println!("{}", "No release has been tagged yet".bright_yellow()),
+    }
+    if changelog.is_empty() {
+        println!(
+            "{}",
+            "There are no commits since the last release".bright_yellow()
+        );
+        return Err(GustError::Cancelled);
+    }
+
+    let tag = choose_tag(&latest, release::next_bump(&changelog, &conventions))?;
+    if backend.tags()?.contains(&tag) {
+        return Err(format!("The tag {} already exists", tag).into());
+    }
+
+    let changelog = changelog::Changelog {
+        title: tag.clone(),
+        ..changelog
+    };
+    let notes = changelog.to_markdown(&conventions);
+    println!("\n{}", notes);
+
+    let version = Version::parse(&tag).ok_or("The tag isn't a version")?;
+    let updates = version_updates(choose_version_files(backend, &version)?, &version)?;
+    let write_changelog =
+        cli::ask_yes_no("Add the release notes to CHANGELOG.md?".to_string(), true)?;
+
+    // Nothing is written until the release is confirmed
+    if !cli::ask_yes_no(format!("Release {}?", tag), true)? {
+        return Err(GustError::Cancelled);
+    }
+
+    let mut changed = Vec::new();
+    for (path, content) in updates {
+        if git::is_dry_run() {
+            println!("dry run: would set the version in {}", path.display());
+        } else {
+            std::fs::write(&path, content)?;
+        }
+        changed.push(path);
+    }
+    if write_changelog {
+        let file = backend.toplevel()?.join("CHANGELOG.md");
+        if git::is_dry_run() {
+            println!("dry run: would add the release notes to {}", file.display());
+        } else {
+            changelog::prepend_to_file(&file, &notes)?;
+        }
+        changed.push(file);
+    }
+
+    if !changed.is_empty() {
+        backend.add(changed)?;
+        backend.commit(
+            Some(vec!["-m".to_string(), release_subject(&conventions, &tag)]),
+            config,
+        )?;
+    }
+
+    // The tag message is the notes without their heading, git shows the tag name above it
+    let body = notes
+        .split_once('\n')
+        .map(|(_, body)| body)
+        .unwrap_or_default();
+    backend.create_tag(
+        tag.clone(),
+        None,
+        Some(format!("{}\n\n{}", tag, body.trim())),
+    )?;
+
+    println!(
+        "{} {}",
+        format!("Tagged {}, publish it with", tag).bright_green(),
+        "git push --follow-tags".bold()
+    );
+    Ok(())
+}
+
+/// Suggest the next version and let the user change it, returning the tag to create
+fn choose_tag(latest: &Option<(String, Version)>, bump: Option<Bump>) -> Result<String, GustError> {
+    // Keep the style of the existing tags, `v1.2.3` unless they have no `v`
+    let (prefix, suggested) = match latest {
+        Some((tag, version)) => {
+            let prefix = if tag.starts_with('v') { "v" } else { "" };
+            (prefix, version.bump(bump.unwrap_or(Bump::Patch)))
+        }
+        None => ("v", Version::parse("0.1.0").unwrap()),
+    };
+    match bump {
+        Some(bump) => println!("The commits call for a {:?} release", bump),
+        None => println!("No commit calls for a new version, suggesting a patch release"),
+    }
+
+    loop {
+        let tag = cli::get_input_default(
+            "Version to release".to_string(),
+            format!("{}{}", prefix, suggested),
+        )?;
+        let tag = tag.trim().to_string();
+        match Version::parse(&tag) {
+            Some(version) if latest.as_ref().is_none_or(|(_, v)| version > *v) => return Ok(tag),
+            Some(_) => println!(
+                "{}",
+                "The version must be newer than the latest release".bright_red()
+            ),
+            None => println!(
+                "{}",
+                format!("'{}' isn't a version like 1.2.3", tag).bright_red()
+            ),
+        }
+    }
+}
+
+/// Let the user pick which version files to update
+fn choose_version_files(
+    backend: &impl GitBackend,
+    version: &Version,
+) -> Result<Vec<PathBuf>, GustError> {
+    let root = backend.toplevel()?;
+    let found: Vec<String> = VERSION_FILES
+        .iter()
+        .filter(|file| root.join(file).exists())
+        .map(|file| file.to_string())
+        .collect();
+    if found.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let chosen = match cli::choice_no_limit(
+        found.clone(),
+        format!("Set the version to {} in", version),
+        true,
+        false,
+    )? {
+        UserResponse::Some(chosen) => chosen,
+        UserResponse::All => found,
+        UserResponse::None => return Ok(vec![]),
+    };
+    Ok(chosen.into_iter().map(|file| root.join(file)).collect())
+}
+
+/// The new contents of `files`, and the files that have to change with them, with `version` set
+fn version_updates(
+    files: Vec<PathBuf>,
+    version: &Version,
+) -> Result<Vec<(PathBuf, String)>, GustError> {
+    let mut updates = Vec::new();
+    for path in files {
+        updates.extend(release::version_updates(&path, version)?);
+    }
+    Ok(updates)
+}
+
+/// `chore: release v1.2.3` when the convention has a chore type, otherwise `Release v1.2.3`
+fn release_subject(conventions: &ConventionSettings, tag: &str) -> String {
+    if conventions.types.iter().any(|t| t == "chore") {
+        format!("chore{} release {}", conventions.separator, tag)
+    } else {
+        format!("Release {}", tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+    use crate::prompt::scripted::{script, Answer};
+
+    const MANIFEST: &str = "[package]\nname = \"demo\"\nversion = \"0.0.1\"\n";
+
+    fn with_features() -> TestRepo {
+        let repo = TestRepo::new();
+        repo.commit_file("Cargo.toml", MANIFEST, "chore: add the manifest");
+        repo.commit_file("a.txt", "a\n", "feat: add a.txt");
+        repo
+    }
+
+    /// Release v0.1.0, setting it in Cargo.toml and adding the notes to CHANGELOG.md
+    fn release(confirm: bool) -> Result<(), GustError> {
+        script(vec![
+            Answer::Text("v0.1.0".to_string()),
+            Answer::MultiSelect(vec![0]),
+            Answer::Confirm(true),
+            Answer::Confirm(confirm),
+        ]);
+        git_release_cli(&GitCli, &Config::default())
+    }
+
+    #[test]
+    fn writes_nothing_until_the_release_is_confirmed() {
+        let repo = with_features();
+        assert!(matches!(release(false), Err(GustError::Cancelled)));
+
+        assert_eq!(repo.git(&["status", "--porcelain"]), "");
+        assert!(!repo.work_tree.join("CHANGELOG.md").exists());
+        assert_eq!(repo.git(&["tag"]), "");
+    }
+
+    #[test]
+    fn commits_the_version_and_notes_then_tags_them() {
+        let repo = with_features();
+        release(true).unwrap();
+
+        assert_eq!(repo.git(&["status", "--porcelain"]), "");
+        assert_eq!(
+            repo.git(&["log", "-1", "--format=%s"]),
+            "chore: release v0.1.0"
+        );
+        assert_eq!(
+            repo.git(&["show", "v0.1.0:Cargo.toml"]),
+            MANIFEST.replace("0.0.1", "0.1.0").trim_end()
+        );
+        let changelog = repo.git(&["show", "v0.1.0:CHANGELOG.md"]);
+        assert!(changelog.contains("add a.txt"), "{}", changelog);
+    }
+}
@@ -0,0 +1,290 @@
+use crate::backend::GitBackend;
+use crate::cli::{self, Remote, UserResponse};
+use crate::error::GustError;
+use crate::menus;
+use crate::settings::Config;
+
+use colored::Colorize;
+
+pub fn git_remotes_cli(backend: &impl GitBackend, _config: &Config) -> Result<(), GustError> {
+    println!(
+        "{} {} {}",
+        "Opening".green(),
+        "Remotes".bold().green(),
+        "menu".green()
+    );
+
+    let remotes = backend.remotes()?.unwrap_or_default();
+    if remotes.is_empty() {
+        println!("{}", "This repository has no remotes".bright_yellow());
+    }
+    for remote in &remotes {
+        if remote.fetch_url == remote.push_url {
+            println!("{}\t{}", remote.name.bold(), remote.fetch_url);
+        } else {
+            println!(
+                "{}\t{} (fetch)\n\t{} (push)",
+                remote.name.bold(),
+                remote.fetch_url,
+                remote.push_url
+            );
+        }
+    }
+
+    let choices = vec![
+        "Add a remote".to_string(),
+        "Rename a remote".to_string(),
+        "Remove a remote".to_string(),
+        "Change a remote's url".to_string(),
+        "Prune stale remote branches".to_string(),
+        "Set the current branch's upstream".to_string(),
+    ];
+
+    match cli::choice_single(choices, String::from("Select action"), false, true)? {
+        UserResponse::Some(0) => add_remote(backend, remotes.is_empty()),
+        UserResponse::Some(1) => {
+            if let Some(remote) = choose_remote(&remotes, "Select the remote to rename")? {
+                let name = cli::get_input("Enter the new name: ".to_string())?;
+                backend.remote_rename(remote.name, name)?;
+            }
+            Ok(())
+        }
+        UserResponse::Some(2) => {
+            if let Some(remote) = choose_remote(&remotes, "Select the remote to remove")? {
+                if cli::ask_yes_no(
+                    format!(
+                        "Are you sure you wish to remove this remote?: {}",
+                        remote.name
+                    ),
+                    false,
+                )? {
+                    backend.remote_remove(remote.name)?;
+                } else {
+                    println!("User canceled removing the remote");
+                }
+            }
+            Ok(())
+        }
+        UserResponse::Some(3) => {
+            if let Some(remote) = choose_remote(&remotes, "Select the remote to change")? {
+                let url = cli::get_input(format!("Enter the new url for {}: ", remote.name))?;
+                let push_only =
+                    cli::ask_yes_no("Only change the url used for pushing?".to_string(), false)?;
+                backend.remote_set_url(remote.name, url, push_only)?;
+            }
+            Ok(())
+        }
+        UserResponse::Some(4) => {
+            if let Some(remote) = choose_remote(&remotes, "Select the remote to prune")? {
+                backend.remote_prune(remote.name)?;
+            }
+            Ok(())
+        }
+        UserResponse::Some(5) => set_upstream(backend),
+        _ => {
+            println!("'None' selected, returning to menu");
+            Ok(())
+        }
+    }
+}
+
+/// Ask for a name and url and add the remote, `origin` is suggested for the first remote
+pub fn add_remote(backend: &impl GitBackend, is_first: bool) -> Result<(), GustError> {
+    let name = if is_first && cli::ask_yes_no("Name the remote 'origin'?".to_string(), true)? {
+        "origin".to_string()
+    } else {
+        cli::get_input("Enter the remote's name: ".to_string())?
+    };
+    let url = cli::get_input("Enter the remote's url: ".to_string())?;
+
+    backend.remote_add(name.clone(), url)?;
+
+    if cli::ask_yes_no(format!("Fetch from '{}' now?", name), true)? {
+        backend.fetch(Some(name))?;
+    }
+    Ok(())
+}
+
+/// Have the user pick one of `remotes`, `None` when there are none or 'None' was picked
+pub fn choose_remote(remotes: &[Remote], prompt: &str) -> Result<Option<Remote>, GustError> {
+    if remotes.is_empty() {
+        return Ok(None);
+    }
+
+    let choices: Vec<String> = remotes.iter().map(|r| r.name.clone()).collect();
+    match cli::choice_single(choices, prompt.bright_yellow().to_string(), false, true)? {
+        UserResponse::Some(index) => Ok(Some(remotes[index].clone())),
+        _ => {
+            println!("'None' selected, returning to menu");
+            Ok(None)
+        }
+    }
+}
+
+fn set_upstream(backend: &impl GitBackend) -> Result<(), GustError> {
+    let branch = match backend.status()?.branch.head {
+        Some(branch) => branch,
+        None => {
+            println!(
+                "{}",
+                "HEAD is detached, switch to a branch first".bright_red()
+            );
+            return Ok(());
+        }
+    };
+
+    let prompt = format!("Select the upstream for '{}'", branch)
+        .bright_yellow()
+        .to_string();
+    match menus::choose_remote_branch(backend, prompt)? {
+        Some(upstream) => backend.set_upstream(Some(branch), upstream),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+    use crate::prompt::scripted::{script, Answer};
+
+    use std::path::PathBuf;
+
+    /// A repository with one commit and `origin` pointing at a bare clone of it
+    fn with_origin() -> (TestRepo, PathBuf) {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        let origin = repo.bare_clone("origin.git");
+        repo.git(&["remote", "add", "origin", origin.to_str().unwrap()]);
+        repo.git(&["fetch", "--quiet", "origin"]);
+        (repo, origin)
+    }
+
+    #[test]
+    fn adds_a_remote_and_fetches_only_from_it() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        let other = repo.bare_clone("other.git");
+        repo.git(&["remote", "add", "other", other.to_str().unwrap()]);
+        let origin = repo.bare_clone("origin.git");
+
+        script(vec![
+            Answer::Select(0),
+            Answer::Text("origin".to_string()),
+            Answer::Text(origin.display().to_string()),
+            Answer::Confirm(true),
+        ]);
+        git_remotes_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(
+            repo.git(&["remote", "get-url", "origin"]),
+            origin.display().to_string()
+        );
+        assert_eq!(repo.git(&["branch", "--remotes"]), "  origin/main");
+    }
+
+    #[test]
+    fn suggests_origin_for_the_first_remote() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        let origin = repo.bare_clone("origin.git");
+
+        script(vec![
+            Answer::Select(0),
+            Answer::Confirm(true),
+            Answer::Text(origin.display().to_string()),
+            Answer::Confirm(false),
+        ]);
+        git_remotes_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(repo.git(&["remote"]), "origin");
+        assert_eq!(repo.git(&["branch", "--remotes"]), "");
+    }
+
+    #[test]
+    fn renames_a_remote() {
+        let (repo, _) = with_origin();
+        script(vec![
+            Answer::Select(1),
+            Answer::Select(0),
+            Answer::Text("upstream".to_string()),
+        ]);
+        git_remotes_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(repo.git(&["remote"]), "upstream");
+        assert_eq!(repo.git(&["branch", "--remotes"]), "  upstream/main");
+    }
+
+    #[test]
+    fn removes_a_remote_once_confirmed() {
+        let (repo, _) = with_origin();
+        script(vec![
+            Answer::Select(2),
+            Answer::Select(0),
+            Answer::Confirm(false),
+        ]);
+        git_remotes_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(repo.git(&["remote"]), "origin");
+
+        script(vec![
+            Answer::Select(2),
+            Answer::Select(0),
+            Answer::Confirm(true),
+        ]);
+        git_remotes_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(repo.git(&["remote"]), "");
+    }
+
+    #[test]
+    fn changes_the_fetch_and_push_urls() {
+        let (repo, origin) = with_origin();
+        let moved = repo.bare_clone("moved.git");
+
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(0),
+            Answer::Text(moved.display().to_string()),
+            Answer::Confirm(true),
+        ]);
+        git_remotes_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(
+            repo.git(&["remote", "get-url", "origin"]),
+            origin.display().to_string()
+        );
+        assert_eq!(
+            repo.git(&["remote", "get-url", "--push", "origin"]),
+            moved.display().to_string()
+        );
+
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(0),
+            Answer::Text(moved.display().to_string()),
+            Answer::Confirm(false),
+        ]);
+        git_remotes_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(
+            repo.git(&["remote", "get-url", "origin"]),
+            moved.display().to_string()
+        );
+    }
+
+    #[test]
+    fn prunes_branches_deleted_from_the_remote() {
+        let (repo, origin) = with_origin();
+        repo.git(&["push", "--quiet", "origin", "main:topic"]);
+        repo.git(&["fetch", "--quiet", "origin"]);
+        repo.git(&[
+            "--git-dir",
+            origin.to_str().unwrap(),
+            "branch",
+            "--delete",
+            "topic",
+        ]);
+
+        script(vec![Answer::Select(4), Answer::Select(0)]);
+        git_remotes_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(repo.git(&["branch", "--remotes"]), "  origin/main");
+    }
+}
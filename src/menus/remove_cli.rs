@@ -1,12 +1,14 @@
+use crate::backend::GitBackend;
 use crate::cli;
+use crate::error::GustError;
 use crate::utils;
 
 use colored::Colorize;
 
-pub fn git_remove_cli() -> Result<(), String> {
+pub fn git_remove_cli(backend: &impl GitBackend) -> Result<(), GustError> {
     let choice_remove_prompt: String = String::from("Select files to remove from tracking:");
 
-    match cli::git_ls_tree()? {
+    match backend.ls_tree()? {
         None => println!(
             "{}",
             "There were no files found in the git repo".bright_green()
@@ -29,10 +31,10 @@ pub fn git_remove_cli() -> Result<(), String> {
                     cli::UserResponse::None => vec![],
                 };
 
-            if user_choices.len() == 0 {
+            if user_choices.is_empty() {
                 println!("{}", "Nothing selected".bright_yellow());
             } else {
-                cli::git_rm(user_choices, as_cached)?;
+                backend.rm(user_choices, as_cached)?;
             }
         }
     }
@@ -1,9 +1,13 @@
+use crate::backend::GitBackend;
 use crate::cli;
+use crate::error::GustError;
+use crate::menus;
 use crate::settings::Config;
+use crate::status;
 
 use colored::Colorize;
 
-pub fn git_reset_cli(_config: &Config) -> Result<(), String> {
+pub fn git_reset_cli(backend: &impl GitBackend, _config: &Config) -> Result<(), GustError> {
     let choice_reset_prompt: String = String::from("Select files to reset:");
 
     println!(
@@ -12,36 +16,71 @@ pub fn git_reset_cli(_config: &Config) -> Result<(), String> {
         "Reset".bold().green(),
         "menu".green()
     );
-    let status_opt = cli::git_status_short()?;
 
-    return match status_opt {
-        None => {
-            println!("{}", "No files staged".bright_green());
+    let actions = vec![
+        "Unstage files".to_string(),
+        "Unstage parts of a file".to_string(),
+    ];
+    if let cli::UserResponse::Some(1) =
+        cli::choice_single(actions, String::from("Select action"), false, false)?
+    {
+        return menus::git_reset_hunks_cli(backend);
+    }
+
+    let repo_status = backend.status()?;
+    let entries = repo_status.staged();
+
+    if entries.is_empty() {
+        println!("{}", "No files staged".bright_green());
+        return Ok(());
+    }
+
+    let choices: Vec<String> = entries
+        .iter()
+        .map(|e| e.label().yellow().to_string())
+        .collect();
+
+    match cli::choice_no_limit(choices, choice_reset_prompt, true, false)? {
+        cli::UserResponse::None => {
+            println!("None selected, returning");
             Ok(())
         }
-        Some(status) => {
-            let mut choices = Vec::<String>::new();
-            for line in status.lines() {
-                match line.chars().nth(0).unwrap() {
-                    'M' | 'A' | 'C' | 'D' => {
-                        // remove the parenthesis from long file names
-                        if line.chars().nth(3).unwrap() == '"' {
-                            choices.push(line[4..line.len() - 1].yellow().to_string());
-                        } else {
-                            choices.push(line[3..].yellow().to_string());
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            return match cli::choice_no_limit(choices, choice_reset_prompt, true, false)? {
-                cli::UserResponse::None => {
-                    println!("None selected, returning");
-                    Ok(())
-                }
-                cli::UserResponse::Some(choice) => return cli::git_reset(choice),
-                _ => Err("User shouldn't have been able to select 'all' in 'reset'".to_string()),
-            };
+        cli::UserResponse::Some(choice) => {
+            let selected = status::entries_from_labels(&entries, &choice);
+            // Renames need both sides reset to fully unstage them
+            let toplevel = backend.toplevel()?;
+            backend.reset(selected.iter().flat_map(|e| e.paths(&toplevel)).collect())
         }
-    };
+        _ => Err("User shouldn't have been able to select 'all' in 'reset'".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::FakeBackend;
+    use crate::prompt::scripted::{script, Answer};
+
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn unstages_the_selected_files() {
+        let backend = FakeBackend::new(PathBuf::from("/repo"));
+        backend.commit_file("a.txt", "a\n", "Add a.txt");
+        backend.commit_file("b.txt", "b\n", "Add b.txt");
+        backend.write("a.txt", Some("changed a\n"));
+        backend.write("b.txt", Some("changed b\n"));
+        backend.add(vec!["a.txt", "b.txt"]).unwrap();
+
+        script(vec![Answer::Select(0), Answer::MultiSelect(vec![1])]);
+        git_reset_cli(&backend, &Config::default()).unwrap();
+
+        assert_eq!(backend.calls().last().unwrap(), "git reset -- /repo/b.txt");
+        let repo = backend.repo.borrow();
+        assert_eq!(
+            repo.files[Path::new("a.txt")].index.as_deref(),
+            Some("changed a\n")
+        );
+        assert_eq!(repo.files[Path::new("b.txt")].index.as_deref(), Some("b\n"));
+    }
 }
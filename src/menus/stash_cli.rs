@@ -0,0 +1,292 @@
+use crate::backend::GitBackend;
+use crate::cli::{self, UserResponse};
+use crate::error::GustError;
+use crate::menus;
+use crate::settings::Config;
+use crate::status::{FileEntry, FileState};
+
+use colored::Colorize;
+
+pub fn git_stash_cli(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    println!(
+        "{} {} {}",
+        "Opening".green(),
+        "Stash".bold().green(),
+        "menu".green()
+    );
+
+    let choices = vec![
+        "Stash changes".to_string(),
+        "Apply a stash".to_string(),
+        "Pop a stash".to_string(),
+        "Drop a stash".to_string(),
+        "Create a branch from a stash".to_string(),
+    ];
+
+    match cli::choice_single(choices, String::from("Select action"), false, false)? {
+        UserResponse::Some(0) => stash_changes(backend),
+        UserResponse::Some(1) => apply_stash(backend, config, false),
+        UserResponse::Some(2) => apply_stash(backend, config, true),
+        UserResponse::Some(3) => drop_stash(backend),
+        UserResponse::Some(4) => branch_from_stash(backend),
+        _ => Err("No stash action was selected".into()),
+    }
+}
+
+fn stash_changes(backend: &impl GitBackend) -> Result<(), GustError> {
+    let repo_status = backend.status()?;
+    let (untracked, tracked): (Vec<&FileEntry>, Vec<&FileEntry>) = repo_status
+        .entries
+        .iter()
+        .filter(|e| e.worktree != FileState::Ignored)
+        .partition(|e| e.is_untracked());
+    if tracked.is_empty() && untracked.is_empty() {
+        println!("{}", "No local changes to stash".bright_yellow());
+        return Ok(());
+    }
+
+    let message = cli::get_input_optional("Stash message (leave empty for none)".to_string())?;
+    let include_untracked =
+        !untracked.is_empty() && cli::ask_yes_no("Include untracked files?".to_string(), false)?;
+    // git only stashes untracked files when asked to
+    if tracked.is_empty() && !include_untracked {
+        println!(
+            "{}",
+            "Only untracked files changed, include them to stash them".bright_yellow()
+        );
+        return Ok(());
+    }
+    let keep_index = cli::ask_yes_no("Keep staged changes in the index?".to_string(), false)?;
+
+    backend.stash_push(message, include_untracked, keep_index)
+}
+
+/// Have the user fuzzy search for a stash, each listed with the summary of its diffstat,
+/// and show the whole diffstat once chosen. Returns the stash ref, e.g. `stash@{0}`
+fn choose_stash(backend: &impl GitBackend, prompt: &str) -> Result<Option<String>, GustError> {
+    let stashes = match backend.stash_list()? {
+        Some(stashes) => stashes,
+        None => {
+            println!("{}", "There are no stashes".bright_yellow());
+            return Ok(None);
+        }
+    };
+
+    let mut choices = Vec::<String>::with_capacity(stashes.len());
+    for stash in stashes {
+        let name = stash.split(':').next().unwrap_or_default().to_string();
+        // The last line of `--stat` is the `N files changed, ...` summary
+        let stat = backend.stash_show(name)?;
+        match stat.lines().last() {
+            Some(summary) => choices.push(format!("{}  ({})", stash, summary.trim())),
+            None => choices.push(stash),
+        }
+    }
+
+    println!("{}", prompt.bright_yellow());
+    let chosen = match cli::filter_choice_cli(choices, true)? {
+        Some(chosen) if chosen != "None" => chosen,
+        _ => {
+            println!("'None' selected, returning to menu");
+            return Ok(None);
+        }
+    };
+
+    let stash = match chosen.split_once(':') {
+        Some((stash, _)) => stash.to_string(),
+        None => return Err(format!("Couldn't read the stash name from: {}", chosen).into()),
+    };
+
+    println!("{}", chosen.bold());
+    print!("{}", backend.stash_show(stash.clone())?);
+    Ok(Some(stash))
+}
+
+fn apply_stash(backend: &impl GitBackend, config: &Config, pop: bool) -> Result<(), GustError> {
+    let stash = match choose_stash(
+        backend,
+        if pop {
+            "Select the stash to pop"
+        } else {
+            "Select the stash to apply"
+        },
+    )? {
+        Some(stash) => stash,
+        None => return Ok(()),
+    };
+
+    if let Err(err) = backend.stash_apply(stash.clone(), pop) {
+        let conflicts: Vec<String> = backend
+            .status()?
+            .entries
+            .iter()
+            .filter(|e| e.is_unmerged())
+            .map(|e| e.label())
+            .collect();
+
+        if conflicts.is_empty() {
+            return Err(err);
+        }
+
+        println!(
+            "{}:\n{}",
+            "Applying the stash caused conflicts in".bright_red(),
+            conflicts.join("\n").yellow()
+        );
+        if pop {
+            // git keeps the stash around when popping it conflicts
+            println!(
+                "{}",
+                format!(
+                    "'{}' was kept, drop it once the conflicts are resolved",
+                    stash
+                )
+                .bright_yellow()
+            );
+        }
+        return menus::resolve_conflicts_cli(backend, config);
+    }
+
+    println!(
+        "{}",
+        format!("'{}' {}", stash, if pop { "popped" } else { "applied" }).bright_green()
+    );
+    Ok(())
+}
+
+fn drop_stash(backend: &impl GitBackend) -> Result<(), GustError> {
+    let stash = match choose_stash(backend, "Select the stash to drop")? {
+        Some(stash) => stash,
+        None => return Ok(()),
+    };
+
+    if cli::ask_yes_no(
+        format!("Are you sure you wish to drop this stash?: {}", stash),
+        false,
+    )? {
+        backend.stash_drop(stash)?;
+    } else {
+        println!("User canceled the stash drop");
+    }
+    Ok(())
+}
+
+fn branch_from_stash(backend: &impl GitBackend) -> Result<(), GustError> {
+    let stash = match choose_stash(backend, "Select the stash to create a branch from")? {
+        Some(stash) => stash,
+        None => return Ok(()),
+    };
+
+    let name = cli::get_input("Enter new branch name: ".to_string())?;
+    backend.stash_branch(name, stash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::FakeBackend;
+    use crate::prompt::scripted::{script, Answer};
+
+    use std::path::{Path, PathBuf};
+
+    /// `a.txt` has a change that was stashed with the message `wip`
+    fn stashed() -> FakeBackend {
+        let backend = FakeBackend::new(PathBuf::from("/repo"));
+        backend.commit_file("a.txt", "a\n", "Add a.txt");
+        backend.write("a.txt", Some("changed\n"));
+
+        script(vec![
+            Answer::Select(0),
+            Answer::Text("wip".to_string()),
+            Answer::Confirm(false),
+        ]);
+        git_stash_cli(&backend, &Config::default()).unwrap();
+        backend
+    }
+
+    fn worktree(backend: &FakeBackend) -> Option<String> {
+        backend.repo.borrow().files[Path::new("a.txt")]
+            .worktree
+            .clone()
+    }
+
+    #[test]
+    fn stashes_changes_with_a_message() {
+        let backend = stashed();
+        assert_eq!(worktree(&backend).as_deref(), Some("a\n"));
+        assert_eq!(backend.repo.borrow().stashes[0].description, "On main: wip");
+    }
+
+    #[test]
+    fn untracked_files_are_only_stashed_when_included() {
+        let backend = FakeBackend::new(PathBuf::from("/repo"));
+        backend.commit_file("a.txt", "a\n", "Add a.txt");
+        backend.write("new.txt", Some("new\n"));
+
+        script(vec![
+            Answer::Select(0),
+            Answer::Text(String::new()),
+            Answer::Confirm(false),
+        ]);
+        git_stash_cli(&backend, &Config::default()).unwrap();
+        assert!(backend.repo.borrow().stashes.is_empty());
+        assert!(!backend.calls().iter().any(|c| c == "git stash push"));
+
+        script(vec![
+            Answer::Select(0),
+            Answer::Text(String::new()),
+            Answer::Confirm(true),
+            Answer::Confirm(false),
+        ]);
+        git_stash_cli(&backend, &Config::default()).unwrap();
+        assert_eq!(backend.repo.borrow().stashes.len(), 1);
+        assert!(!backend
+            .repo
+            .borrow()
+            .files
+            .contains_key(Path::new("new.txt")));
+    }
+
+    #[test]
+    fn lists_stashes_with_their_diffstat() {
+        let backend = stashed();
+        script(vec![
+            Answer::Select(1),
+            Answer::Choose("stash@{0}: On main: wip  (1 file(s) changed)".to_string()),
+        ]);
+        git_stash_cli(&backend, &Config::default()).unwrap();
+        assert_eq!(backend.calls().last().unwrap(), "git stash apply stash@{0}");
+    }
+
+    #[test]
+    fn pops_the_chosen_stash() {
+        let backend = stashed();
+        script(vec![Answer::Select(2), Answer::Select(0)]);
+        git_stash_cli(&backend, &Config::default()).unwrap();
+
+        assert_eq!(worktree(&backend).as_deref(), Some("changed\n"));
+        assert!(backend.repo.borrow().stashes.is_empty());
+        assert_eq!(backend.calls().last().unwrap(), "git stash pop stash@{0}");
+    }
+
+    #[test]
+    fn drops_a_stash_once_confirmed() {
+        let backend = stashed();
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(0),
+            Answer::Confirm(false),
+        ]);
+        git_stash_cli(&backend, &Config::default()).unwrap();
+        assert_eq!(backend.repo.borrow().stashes.len(), 1);
+
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(0),
+            Answer::Confirm(true),
+        ]);
+        git_stash_cli(&backend, &Config::default()).unwrap();
+        assert!(backend.repo.borrow().stashes.is_empty());
+        assert_eq!(worktree(&backend).as_deref(), Some("a\n"));
+    }
+}
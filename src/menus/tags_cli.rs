@@ -0,0 +1,375 @@
+use crate::backend::GitBackend;
+use crate::cli::{self, TagInfo, UserResponse};
+use crate::error::GustError;
+use crate::menus;
+use crate::settings::Config;
+
+use colored::Colorize;
+
+pub fn git_tags_cli(backend: &impl GitBackend, _config: &Config) -> Result<(), GustError> {
+    println!(
+        "{} {} {}",
+        "Opening".green(),
+        "Tags".bold().green(),
+        "menu".green()
+    );
+
+    let tags = backend.tag_list()?;
+    if tags.is_empty() {
+        println!("{}", "This repository has no tags".bright_yellow());
+    }
+    for tag in &tags {
+        println!("{}", tag_label(tag));
+    }
+
+    let choices = vec![
+        "Create a tag".to_string(),
+        "Delete a tag".to_string(),
+        "Push tags".to_string(),
+        "Check out a tag".to_string(),
+    ];
+
+    match cli::choice_single(choices, String::from("Select action"), false, true)? {
+        UserResponse::Some(0) => create_tag(backend, &tags),
+        UserResponse::Some(1) => delete_tag(backend, &tags),
+        UserResponse::Some(2) => push_tags(backend, &tags),
+        UserResponse::Some(3) => checkout_tag(backend, &tags),
+        _ => {
+            println!("'None' selected, returning to menu");
+            Ok(())
+        }
+    }
+}
+
+/// `v1.0.0  1a2b3c4  2024-01-01  subject`, annotated tags are bold
+fn tag_label(tag: &TagInfo) -> String {
+    let name = if tag.annotated {
+        tag.name.bold().to_string()
+    } else {
+        tag.name.clone()
+    };
+    format!(
+        "{}  {}  {}  {}",
+        name,
+        tag.commit.bright_yellow(),
+        tag.date,
+        tag.subject
+    )
+}
+
+fn choose_tag(tags: &[TagInfo], prompt: &str) -> Result<Option<String>, GustError> {
+    if tags.is_empty() {
+        println!("{}", "There are no tags".bright_yellow());
+        return Ok(None);
+    }
+
+    let choices: Vec<String> = tags.iter().map(tag_label).collect();
+    match cli::choice_single(choices, prompt.bright_yellow().to_string(), false, true)? {
+        UserResponse::Some(index) => Ok(Some(tags[index].name.clone())),
+        _ => {
+            println!("'None' selected, returning to menu");
+            Ok(None)
+        }
+    }
+}
+
+fn create_tag(backend: &impl GitBackend, tags: &[TagInfo]) -> Result<(), GustError> {
+    let commit = if cli::ask_yes_no("Tag a commit other than HEAD?".to_string(), false)? {
+        let prompt = "Select the commit to tag".bright_yellow().to_string();
+        match menus::choose_commit(backend, None, prompt)? {
+            Some(hash) => Some(hash),
+            None => return Ok(()),
+        }
+    } else {
+        None
+    };
+
+    let name = cli::get_input("Enter the tag's name: ".to_string())?;
+    let name = name.trim().to_string();
+    if tags.iter().any(|t| t.name == name) {
+        return Err(format!("The tag {} already exists", name).into());
+    }
+
+    // Annotated tags record who made them and when, `git describe` only uses those by default
+    let message = if cli::ask_yes_no("Make an annotated tag with a message?".to_string(), true)? {
+        Some(cli::get_input("Enter the tag message: ".to_string())?)
+    } else {
+        None
+    };
+
+    backend.create_tag(name.clone(), commit, message)?;
+    println!("{}", format!("Created the tag {}", name).bright_green());
+    Ok(())
+}
+
+fn delete_tag(backend: &impl GitBackend, tags: &[TagInfo]) -> Result<(), GustError> {
+    let name = match choose_tag(tags, "Select the tag to delete")? {
+        Some(name) => name,
+        None => return Ok(()),
+    };
+
+    if !cli::ask_yes_no(
+        format!("Are you sure you wish to delete this tag?: {}", name),
+        false,
+    )? {
+        println!("User canceled deleting the tag");
+        return Ok(());
+    }
+    backend.delete_tag(name.clone(), None)?;
+
+    let remotes = backend.remotes()?.unwrap_or_default();
+    if !remotes.is_empty() && cli::ask_yes_no("Also delete it from a remote?".to_string(), false)? {
+        if let Some(remote) = menus::choose_remote(&remotes, "Select the remote")? {
+            backend.delete_tag(name, Some(remote.name))?;
+        }
+    }
+    Ok(())
+}
+
+fn push_tags(backend: &impl GitBackend, tags: &[TagInfo]) -> Result<(), GustError> {
+    let remotes = backend.remotes()?.unwrap_or_default();
+    if remotes.is_empty() {
+        return Err("This repository has no remotes to push to".into());
+    }
+    let remote = match menus::choose_remote(&remotes, "Select the remote to push to")? {
+        Some(remote) => remote.name,
+        None => return Ok(()),
+    };
+
+    let choices = vec!["Push one tag".to_string(), "Push every tag".to_string()];
+    match cli::choice_single(choices, String::from("Select action"), false, false)? {
+        UserResponse::Some(0) => match choose_tag(tags, "Select the tag to push")? {
+            Some(name) => backend.push_tags(remote, Some(name)),
+            None => Ok(()),
+        },
+        UserResponse::Some(1) => backend.push_tags(remote, None),
+        _ => Err(GustError::Cancelled),
+    }
+}
+
+fn checkout_tag(backend: &impl GitBackend, tags: &[TagInfo]) -> Result<(), GustError> {
+    let name = match choose_tag(tags, "Select the tag to check out")? {
+        Some(name) => name,
+        None => return Ok(()),
+    };
+
+    println!(
+        "{}",
+        "A tag isn't a branch, checking it out leaves HEAD detached:".bright_yellow()
+    );
+    println!("  You can look around and build the tagged version, but new commits");
+    println!("  belong to no branch and are lost once you switch away.");
+    println!("  Create a branch at the tag if you want to keep working from it.");
+
+    let choices = vec![
+        "Check out the tag (detached HEAD)".to_string(),
+        "Create a branch at the tag".to_string(),
+    ];
+    match cli::choice_single(choices, String::from("Select action"), false, true)? {
+        UserResponse::Some(0) => backend.checkout(vec![name]),
+        UserResponse::Some(1) => {
+            let branch = cli::get_input("Enter new branch name: ".to_string())?;
+            backend.checkout(vec!["-b".to_string(), branch, name])
+        }
+        _ => {
+            println!("'None' selected, returning to menu");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::FakeBackend;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+    use crate::prompt::scripted::{script, Answer};
+
+    use std::path::{Path, PathBuf};
+
+    fn backend() -> FakeBackend {
+        let backend = FakeBackend::new(PathBuf::from("/repo"));
+        backend.commit_file("a.txt", "a\n", "Add a.txt");
+        backend.commit_file("b.txt", "b\n", "Add b.txt");
+        backend
+    }
+
+    fn tags(backend: &FakeBackend) -> Vec<(String, String)> {
+        backend.repo.borrow().tags.clone()
+    }
+
+    #[test]
+    fn tags_head() {
+        let backend = backend();
+        script(vec![
+            Answer::Select(0),
+            Answer::Confirm(false),
+            Answer::Text("v1.0.0".to_string()),
+            Answer::Confirm(true),
+            Answer::Text("First release".to_string()),
+        ]);
+        git_tags_cli(&backend, &Config::default()).unwrap();
+
+        let head = backend.repo.borrow().commits[1].0.clone();
+        assert_eq!(tags(&backend), vec![("v1.0.0".to_string(), head)]);
+        assert_eq!(backend.calls(), vec!["git tag First release v1.0.0"]);
+    }
+
+    #[test]
+    fn tags_an_older_commit() {
+        let backend = backend();
+        // The commits are listed newest first
+        script(vec![
+            Answer::Select(0),
+            Answer::Confirm(true),
+            Answer::Select(1),
+            Answer::Text("v0.1.0".to_string()),
+            Answer::Confirm(false),
+        ]);
+        git_tags_cli(&backend, &Config::default()).unwrap();
+
+        let first = backend.repo.borrow().commits[0].0.clone();
+        assert_eq!(tags(&backend), vec![("v0.1.0".to_string(), first)]);
+    }
+
+    #[test]
+    fn refuses_a_tag_that_exists() {
+        let backend = backend();
+        backend
+            .create_tag("v1.0.0".to_string(), None, None)
+            .unwrap();
+        script(vec![
+            Answer::Select(0),
+            Answer::Confirm(false),
+            Answer::Text("v1.0.0".to_string()),
+        ]);
+        assert!(git_tags_cli(&backend, &Config::default()).is_err());
+        assert_eq!(tags(&backend).len(), 1);
+    }
+
+    #[test]
+    fn deletes_a_tag_once_confirmed() {
+        let backend = backend();
+        backend
+            .create_tag("v1.0.0".to_string(), None, None)
+            .unwrap();
+        script(vec![
+            Answer::Select(1),
+            Answer::Select(0),
+            Answer::Confirm(true),
+        ]);
+        git_tags_cli(&backend, &Config::default()).unwrap();
+
+        assert!(tags(&backend).is_empty());
+    }
+
+    /// Two commits, `v1.0.0` annotated on the first and `v1.1.0` on the second,
+    /// with `origin` a bare clone from before the tags were made
+    fn tagged_repo() -> (TestRepo, PathBuf) {
+        let repo = TestRepo::new();
+        // Tags made in the same second would be listed by name
+        std::env::set_var("GIT_COMMITTER_DATE", "2024-01-01T12:00:00Z");
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        let origin = repo.bare_clone("origin.git");
+        repo.git(&["tag", "-a", "v1.0.0", "-m", "First release"]);
+        std::env::remove_var("GIT_COMMITTER_DATE");
+
+        repo.commit_file("b.txt", "b\n", "Add b.txt");
+        repo.git(&["tag", "v1.1.0"]);
+        repo.git(&["remote", "add", "origin", origin.to_str().unwrap()]);
+        (repo, origin)
+    }
+
+    fn remote_tags(origin: &Path) -> String {
+        let output = std::process::Command::new("git")
+            .arg("--git-dir")
+            .arg(origin)
+            .arg("tag")
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn lists_tags_newest_first() {
+        let (_repo, _) = tagged_repo();
+        let tags = GitCli.tag_list().unwrap();
+
+        let names: Vec<&str> = tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["v1.1.0", "v1.0.0"]);
+        assert!(!tags[0].annotated && tags[1].annotated);
+        assert_eq!(tags[0].subject, "Add b.txt");
+        assert_eq!(tags[1].subject, "First release");
+    }
+
+    #[test]
+    fn pushes_one_tag_or_all_of_them() {
+        let (_repo, origin) = tagged_repo();
+        // v1.0.0 is listed second
+        script(vec![
+            Answer::Select(2),
+            Answer::Select(0),
+            Answer::Select(0),
+            Answer::Select(1),
+        ]);
+        git_tags_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(remote_tags(&origin), "v1.0.0");
+
+        script(vec![
+            Answer::Select(2),
+            Answer::Select(0),
+            Answer::Select(1),
+        ]);
+        git_tags_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(remote_tags(&origin), "v1.0.0\nv1.1.0");
+    }
+
+    #[test]
+    fn deletes_a_tag_here_and_from_the_remote() {
+        let (repo, origin) = tagged_repo();
+        repo.git(&["push", "--quiet", "origin", "--tags"]);
+
+        script(vec![
+            Answer::Select(1),
+            Answer::Select(0),
+            Answer::Confirm(true),
+            Answer::Confirm(true),
+            Answer::Select(0),
+        ]);
+        git_tags_cli(&GitCli, &Config::default()).unwrap();
+
+        assert_eq!(repo.git(&["tag"]), "v1.0.0");
+        assert_eq!(remote_tags(&origin), "v1.0.0");
+    }
+
+    #[test]
+    fn checks_out_a_tag_detached_or_on_a_new_branch() {
+        let (repo, _) = tagged_repo();
+        let tagged = repo.git(&["rev-parse", "v1.0.0^{commit}"]);
+
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(1),
+            Answer::Select(1),
+            Answer::Text("hotfix".to_string()),
+        ]);
+        git_tags_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(repo.git(&["branch", "--show-current"]), "hotfix");
+        assert_eq!(repo.git(&["rev-parse", "HEAD"]), tagged);
+
+        script(vec![
+            Answer::Select(3),
+            Answer::Select(0),
+            Answer::Select(0),
+        ]);
+        git_tags_cli(&GitCli, &Config::default()).unwrap();
+        assert_eq!(repo.git(&["branch", "--show-current"]), "");
+        assert_eq!(
+            repo.git(&["rev-parse", "HEAD"]),
+            repo.git(&["rev-parse", "v1.1.0"])
+        );
+    }
+}
@@ -1,44 +1,47 @@
-use crate::cli;
+use crate::backend::GitBackend;
+use crate::error::GustError;
+use crate::menus;
 use crate::settings::Config;
 
 use colored::Colorize;
 
-pub fn git_undo_commit_cli(_config: &Config) -> Result<(), String> {
+pub fn git_undo_commit_cli(backend: &impl GitBackend, _config: &Config) -> Result<(), GustError> {
     let choice_undo_prompt: String = String::from("Select a commit to revert:");
 
-    let log_output: String;
-    match cli::git_log(None)? {
-        None => {
-            println!("{}", "No commits found! returning to menu".bright_yellow());
-            return Ok(());
+    match menus::choose_commit(backend, None, choice_undo_prompt)? {
+        None => Ok(()),
+        Some(hash) => {
+            backend.revert(hash.clone())?;
+            println!(
+                "{}",
+                format!("Commit '{}' reverted. Returning to menu", hash).bright_green()
+            );
+            Ok(())
         }
-        Some(log_string) => log_output = log_string,
     }
+}
 
-    let mut choices = Vec::<String>::new();
-    for line in log_output.lines() {
-        choices.push(line.to_string());
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::FakeBackend;
+    use crate::prompt::scripted::{script, Answer};
 
-    let usr_selected = cli::choice_single(choices.clone(), choice_undo_prompt, false, true)?;
+    use std::path::PathBuf;
 
-    match usr_selected {
-        cli::UserResponse::None => {
-            println!("'None' selected, returning to menu");
-            return Ok(());
-        }
-        cli::UserResponse::Some(choice) => {
-            println!("{}", choice);
-            let hash = choices[choice][..7].to_string();
-            cli::git_revert(hash.clone())?;
-            println!(
-                "{}",
-                format!("Commit '{}' reverted. Returning to menu", hash)
-                    .bright_green()
-                    .to_string()
-            );
-            return Ok(());
-        }
-        _ => panic!("This is impossible git undo commit"),
+    #[test]
+    fn reverts_the_chosen_commit() {
+        let backend = FakeBackend::new(PathBuf::from("/repo"));
+        backend.commit_file("a.txt", "a\n", "Add a.txt");
+        backend.commit_file("b.txt", "b\n", "Add b.txt");
+        let first = backend.repo.borrow().commits[0].0.clone();
+
+        // Newest first
+        script(vec![Answer::Select(1)]);
+        git_undo_commit_cli(&backend, &Config::default()).unwrap();
+
+        assert_eq!(backend.calls(), vec![format!("git revert {}", first)]);
+        let repo = backend.repo.borrow();
+        assert_eq!(repo.commits.last().unwrap().1, "Revert \"Add a.txt\"");
     }
 }
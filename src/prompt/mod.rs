@@ -0,0 +1,149 @@
+use crate::error::GustError;
+#[cfg(test)]
+pub mod scripted;
+
+use dialoguer::{theme, Confirm, Editor, FuzzySelect, Input, MultiSelect, Select};
+use std::cell::RefCell;
+
+/// Every question Gust asks goes through a `Prompter`, so the menus can be
+/// driven by a script instead of a terminal.
+pub trait Prompter {
+    /// Ask for a line of text. `initial` is editable text already in the field,
+    /// `default` is used when nothing is entered.
+    fn input(
+        &self,
+        prompt: &str,
+        initial: Option<&str>,
+        default: Option<&str>,
+        allow_empty: bool,
+    ) -> Result<String, GustError>;
+
+    /// Ask a yes or no question. Without a default the user has to pick one,
+    /// `None` means the question was dismissed.
+    fn confirm(&self, prompt: &str, default: Option<bool>) -> Result<Option<bool>, GustError>;
+
+    /// Pick one item, `None` means the question was dismissed
+    fn select(
+        &self,
+        prompt: &str,
+        items: &[String],
+        fuzzy: bool,
+        default: usize,
+    ) -> Result<Option<usize>, GustError>;
+
+    /// Pick any number of items, `None` means the question was dismissed
+    fn multi_select(&self, prompt: &str, items: &[String])
+        -> Result<Option<Vec<usize>>, GustError>;
+
+    /// Open `text` in the user's editor, `None` means it was closed without saving
+    fn edit(&self, text: &str, trim_newlines: bool) -> Result<Option<String>, GustError>;
+}
+
+thread_local! {
+    static PROMPTER: RefCell<Box<dyn Prompter>> = RefCell::new(Box::new(TerminalPrompter));
+}
+
+/// Run `f` with the prompter questions are currently asked through
+pub fn with_prompter<T>(f: impl FnOnce(&dyn Prompter) -> T) -> T {
+    PROMPTER.with(|prompter| f(prompter.borrow().as_ref()))
+}
+
+/// Replace the prompter for the current thread
+#[cfg(test)]
+pub fn set_prompter(prompter: Box<dyn Prompter>) {
+    PROMPTER.with(|current| *current.borrow_mut() = prompter);
+}
+
+/// Asks on the terminal with `dialoguer`
+pub struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn input(
+        &self,
+        prompt: &str,
+        initial: Option<&str>,
+        default: Option<&str>,
+        allow_empty: bool,
+    ) -> Result<String, GustError> {
+        let theme = theme::ColorfulTheme::default();
+        let mut input = if initial.is_some() {
+            Input::<String>::with_theme(&theme)
+        } else {
+            Input::<String>::new()
+        };
+        input.with_prompt(prompt).allow_empty(allow_empty);
+        if let Some(initial) = initial {
+            input.with_initial_text(initial);
+        }
+        if let Some(default) = default {
+            input.default(default.to_string());
+        }
+        input
+            .interact_text_on(&console::Term::stderr())
+            .map_err(GustError::from)
+    }
+
+    fn confirm(&self, prompt: &str, default: Option<bool>) -> Result<Option<bool>, GustError> {
+        match default {
+            Some(default_yes) => {
+                let default = if default_yes { "Y" } else { "N" };
+                let user_input: String = Input::new()
+                    .with_prompt(format!("{} (y/n)", prompt))
+                    .default(default.into())
+                    .interact_text_on(&console::Term::stderr())
+                    .map_err(|e| {
+                        GustError::Message(format!("Couldn't ask a yes or no question: {}", e))
+                    })?;
+
+                Ok(Some(matches!(user_input.chars().next(), Some('y' | 'Y'))))
+            }
+            None => Confirm::new()
+                .with_prompt(prompt)
+                .interact_on_opt(&console::Term::stderr())
+                .map_err(|e| format!("Couldn't ask for confirmation: {}", e).into()),
+        }
+    }
+
+    fn select(
+        &self,
+        prompt: &str,
+        items: &[String],
+        fuzzy: bool,
+        default: usize,
+    ) -> Result<Option<usize>, GustError> {
+        if fuzzy {
+            FuzzySelect::with_theme(&theme::ColorfulTheme::default())
+                .items(items)
+                .with_prompt(prompt)
+                .default(default)
+                .interact_on_opt(&console::Term::stderr())
+                .map_err(|e| format!("Couldn't fuzzy search: {}", e).into())
+        } else {
+            Select::new()
+                .items(items)
+                .with_prompt(prompt)
+                .default(default)
+                .interact_on_opt(&console::Term::stderr())
+                .map_err(|e| format!("Couldn't start `select`: {}", e).into())
+        }
+    }
+
+    fn multi_select(
+        &self,
+        prompt: &str,
+        items: &[String],
+    ) -> Result<Option<Vec<usize>>, GustError> {
+        MultiSelect::new()
+            .items(items)
+            .with_prompt(prompt)
+            .interact_on_opt(&console::Term::stderr())
+            .map_err(|e| format!("Couldn't make a choice: {}", e).into())
+    }
+
+    fn edit(&self, text: &str, trim_newlines: bool) -> Result<Option<String>, GustError> {
+        Editor::new()
+            .trim_newlines(trim_newlines)
+            .edit(text)
+            .map_err(GustError::from)
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::GustError;
+use crate::prompt::{self, Prompter};
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+/// An answer for `ScriptedPrompter` to give
+#[derive(Debug, Clone)]
+pub enum Answer {
+    Text(String),
+    Confirm(bool),
+    /// Pick the item at this index
+    Select(usize),
+    /// Pick the item with this label, ignoring colors
+    Choose(String),
+    MultiSelect(Vec<usize>),
+    Edit(Option<String>),
+    /// Dismiss the question, like pressing escape
+    Cancel,
+}
+
+/// Answer the questions asked on the current thread with `answers`, in order
+pub fn script(answers: Vec<Answer>) {
+    prompt::set_prompter(Box::new(ScriptedPrompter::new(answers)));
+}
+
+/// Replays a queue of answers, failing when a question doesn't get the kind of answer it expects
+#[derive(Default)]
+pub struct ScriptedPrompter {
+    answers: RefCell<VecDeque<Answer>>,
+    /// Every prompt that was asked, in order
+    pub asked: RefCell<Vec<String>>,
+}
+
+impl ScriptedPrompter {
+    pub fn new(answers: Vec<Answer>) -> Self {
+        ScriptedPrompter {
+            answers: RefCell::new(answers.into()),
+            asked: RefCell::new(vec![]),
+        }
+    }
+
+    fn next(&self, prompt: &str) -> Result<Answer, GustError> {
+        self.asked.borrow_mut().push(prompt.to_string());
+        self.answers
+            .borrow_mut()
+            .pop_front()
+            .ok_or_else(|| format!("No scripted answer left for '{}'", prompt).into())
+    }
+
+    fn unexpected(prompt: &str, answer: Answer) -> GustError {
+        format!("Scripted answer {:?} doesn't fit '{}'", answer, prompt).into()
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn input(
+        &self,
+        prompt: &str,
+        _initial: Option<&str>,
+        default: Option<&str>,
+        allow_empty: bool,
+    ) -> Result<String, GustError> {
+        match self.next(prompt)? {
+            Answer::Text(text) if text.is_empty() && default.is_some() => {
+                Ok(default.unwrap_or_default().to_string())
+            }
+            Answer::Text(text) if text.is_empty() && !allow_empty => {
+                Err(format!("'{}' doesn't accept an empty answer", prompt).into())
+            }
+            Answer::Text(text) => Ok(text),
+            answer => Err(Self::unexpected(prompt, answer)),
+        }
+    }
+
+    fn confirm(&self, prompt: &str, _default: Option<bool>) -> Result<Option<bool>, GustError> {
+        match self.next(prompt)? {
+            Answer::Confirm(choice) => Ok(Some(choice)),
+            Answer::Cancel => Ok(None),
+            answer => Err(Self::unexpected(prompt, answer)),
+        }
+    }
+
+    fn select(
+        &self,
+        prompt: &str,
+        items: &[String],
+        _fuzzy: bool,
+        _default: usize,
+    ) -> Result<Option<usize>, GustError> {
+        match self.next(prompt)? {
+            Answer::Select(index) if index < items.len() => Ok(Some(index)),
+            Answer::Choose(label) => items
+                .iter()
+                .position(|i| console::strip_ansi_codes(i) == label)
+                .map(Some)
+                .ok_or_else(|| {
+                    format!("'{}' isn't one of the choices for '{}'", label, prompt).into()
+                }),
+            Answer::Cancel => Ok(None),
+            answer => Err(Self::unexpected(prompt, answer)),
+        }
+    }
+
+    fn multi_select(
+        &self,
+        prompt: &str,
+        items: &[String],
+    ) -> Result<Option<Vec<usize>>, GustError> {
+        match self.next(prompt)? {
+            Answer::MultiSelect(indexes) if indexes.iter().all(|i| *i < items.len()) => {
+                Ok(Some(indexes))
+            }
+            Answer::Cancel => Ok(None),
+            answer => Err(Self::unexpected(prompt, answer)),
+        }
+    }
+
+    fn edit(&self, text: &str, _trim_newlines: bool) -> Result<Option<String>, GustError> {
+        match self.next(text)? {
+            Answer::Edit(edited) => Ok(edited),
+            answer => Err(Self::unexpected(text, answer)),
+        }
+    }
+}
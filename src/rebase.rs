@@ -0,0 +1,185 @@
+use crate::cli::LogCommit;
+
+use strum_macros::EnumIter;
+
+/// What `git rebase -i` does with a commit, named as in the todo list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum TodoAction {
+    Pick,
+    Reword,
+    Edit,
+    Squash,
+    Fixup,
+    Drop,
+}
+
+impl TodoAction {
+    pub fn description(&self) -> &'static str {
+        match self {
+            TodoAction::Pick => "keep the commit",
+            TodoAction::Reword => "keep the commit, but change its message",
+            TodoAction::Edit => "stop at the commit to change it",
+            TodoAction::Squash => "meld into the commit above, combining the messages",
+            TodoAction::Fixup => "meld into the commit above, dropping this message",
+            TodoAction::Drop => "remove the commit",
+        }
+    }
+
+    /// Whether the commit is folded into the one before it
+    pub fn melds(&self) -> bool {
+        matches!(self, TodoAction::Squash | TodoAction::Fixup)
+    }
+}
+
+impl std::fmt::Display for TodoAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TodoAction::Pick => "pick",
+            TodoAction::Reword => "reword",
+            TodoAction::Edit => "edit",
+            TodoAction::Squash => "squash",
+            TodoAction::Fixup => "fixup",
+            TodoAction::Drop => "drop",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoItem {
+    pub action: TodoAction,
+    pub hash: String,
+    pub subject: String,
+}
+
+impl std::fmt::Display for TodoItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.action,
+            &self.hash[..self.hash.len().min(7)],
+            self.subject
+        )
+    }
+}
+
+/// A todo list picking every commit, oldest first like git writes it.
+/// Merges are left out, `git rebase -i` flattens them unless asked to keep them.
+pub fn todo_from_commits(commits: &[LogCommit]) -> Vec<TodoItem> {
+    commits
+        .iter()
+        .rev()
+        .filter(|c| !c.merge)
+        .map(|c| TodoItem {
+            action: TodoAction::Pick,
+            hash: c.hash.clone(),
+            subject: c.message.lines().next().unwrap_or_default().to_string(),
+        })
+        .collect()
+}
+
+/// Why git would refuse `todo`, `None` if it can be run
+pub fn check_todo(todo: &[TodoItem]) -> Option<String> {
+    // A squash or fixup needs a commit above it that is kept
+    match todo.iter().find(|i| i.action != TodoAction::Drop) {
+        Some(first) if first.action.melds() => Some(format!(
+            "'{}' can't {} into anything, there is no kept commit above it",
+            first.subject, first.action
+        )),
+        _ => None,
+    }
+}
+
+/// The todo file to hand to git
+pub fn render_todo(todo: &[TodoItem]) -> String {
+    todo.iter()
+        .map(|item| format!("{} {} {}\n", item.action, item.hash, item.subject))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(hash: &str, message: &str, merge: bool) -> LogCommit {
+        LogCommit {
+            hash: hash.to_string(),
+            date: "2024-01-01".to_string(),
+            merge,
+            message: message.to_string(),
+        }
+    }
+
+    fn item(action: TodoAction, hash: &str) -> TodoItem {
+        TodoItem {
+            action,
+            hash: hash.to_string(),
+            subject: format!("Commit {}", hash),
+        }
+    }
+
+    #[test]
+    fn picks_commits_oldest_first_without_merges() {
+        // `git log` lists the newest first
+        let commits = [
+            commit("ccc", "Add c\n\nBody", false),
+            commit("mmm", "Merge branch 'topic'", true),
+            commit("aaa", "Add a", false),
+        ];
+        assert_eq!(
+            todo_from_commits(&commits),
+            [
+                TodoItem {
+                    action: TodoAction::Pick,
+                    hash: "aaa".to_string(),
+                    subject: "Add a".to_string(),
+                },
+                TodoItem {
+                    action: TodoAction::Pick,
+                    hash: "ccc".to_string(),
+                    subject: "Add c".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn a_meld_needs_a_kept_commit_above_it() {
+        use TodoAction::*;
+
+        let leading = [item(Squash, "a"), item(Pick, "b")];
+        assert_eq!(
+            check_todo(&leading).unwrap(),
+            "'Commit a' can't squash into anything, there is no kept commit above it"
+        );
+
+        let after_drops = [item(Drop, "a"), item(Fixup, "b"), item(Pick, "c")];
+        assert_eq!(
+            check_todo(&after_drops).unwrap(),
+            "'Commit b' can't fixup into anything, there is no kept commit above it"
+        );
+
+        for todo in [
+            vec![item(Drop, "a"), item(Reword, "b"), item(Fixup, "c")],
+            vec![item(Pick, "a"), item(Drop, "b"), item(Squash, "c")],
+            vec![item(Drop, "a")],
+        ] {
+            assert_eq!(check_todo(&todo), None);
+        }
+    }
+
+    #[test]
+    fn renders_the_todo_file_with_full_hashes() {
+        let todo = [
+            item(TodoAction::Pick, "1234567890abcdef"),
+            item(TodoAction::Fixup, "fedcba0987654321"),
+        ];
+        assert_eq!(
+            render_todo(&todo),
+            "pick 1234567890abcdef Commit 1234567890abcdef\n\
+             fixup fedcba0987654321 Commit fedcba0987654321\n"
+        );
+        assert_eq!(todo[0].to_string(), "pick 1234567 Commit 1234567890abcdef");
+    }
+}
@@ -0,0 +1,501 @@
+use crate::changelog::Changelog;
+use crate::error::GustError;
+use crate::settings::ConventionSettings;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Which part of the version a release increments, ordered from smallest to largest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// The bumps `feat` and `fix` call for under Conventional Commits
+pub fn default_version_bumps() -> BTreeMap<String, Bump> {
+    BTreeMap::from([
+        ("feat".to_string(), Bump::Minor),
+        ("fix".to_string(), Bump::Patch),
+    ])
+}
+
+/// A semantic version, build metadata is dropped when parsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+impl Version {
+    /// Parse `1.2.3`, `v1.2.3` or `1.2.3-rc.1`
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.strip_prefix('v').unwrap_or(text);
+        let text = text.split('+').next()?;
+        let (numbers, pre) = match text.split_once('-') {
+            Some((numbers, pre)) if valid_pre_release(pre) => (numbers, Some(pre.to_string())),
+            Some(_) => return None,
+            None => (text, None),
+        };
+
+        let mut parts = numbers.split('.').map(|p| p.parse::<u64>().ok());
+        let version = Version {
+            major: parts.next()??,
+            minor: parts.next()??,
+            patch: parts.next()??,
+            pre,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(version)
+    }
+
+    /// The next version, a pre-release becomes the release it leads up to when that is enough
+    pub fn bump(&self, bump: Bump) -> Self {
+        let pre_release = self.pre.is_some();
+        let (major, minor, patch) = match bump {
+            Bump::Major if pre_release && self.minor == 0 && self.patch == 0 => (self.major, 0, 0),
+            Bump::Major => (self.major + 1, 0, 0),
+            Bump::Minor if pre_release && self.patch == 0 => (self.major, self.minor, 0),
+            Bump::Minor => (self.major, self.minor + 1, 0),
+            Bump::Patch if pre_release => (self.major, self.minor, self.patch),
+            Bump::Patch => (self.major, self.minor, self.patch + 1),
+        };
+        Version {
+            major,
+            minor,
+            patch,
+            pre: None,
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            // A pre-release comes before the release it leads up to
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => cmp_pre_release(a, b),
+            })
+    }
+}
+
+/// Dot separated identifiers of ASCII letters, digits and `-`, numbers without leading zeros
+fn valid_pre_release(pre: &str) -> bool {
+    pre.split('.').all(|id| {
+        !id.is_empty()
+            && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !(id.len() > 1 && id.starts_with('0') && is_numeric(id))
+    })
+}
+
+fn is_numeric(id: &str) -> bool {
+    id.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Compare pre-releases as SemVer §11 does, identifier by identifier: numbers by their value
+/// and before anything with letters, which compare as text. More identifiers come last.
+fn cmp_pre_release(a: &str, b: &str) -> Ordering {
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match (is_numeric(x), is_numeric(y)) {
+                // Without leading zeros the longer number is the larger one, however long
+                (true, true) => x.len().cmp(&y.len()).then_with(|| x.cmp(y)),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => x.cmp(y),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+/// The tag holding the highest semantic version, tags that aren't versions are skipped
+pub fn latest_version_tag(tags: &[String]) -> Option<(String, Version)> {
+    tags.iter()
+        .filter_map(|tag| Some((tag.clone(), Version::parse(tag)?)))
+        .max_by(|(_, a), (_, b)| a.cmp(b))
+}
+
+/// The largest bump the changes call for: major for breaking changes, otherwise the one
+/// `ConventionSettings::version_bumps` gives each type. `None` if no change calls for one.
+pub fn next_bump(changelog: &Changelog, conventions: &ConventionSettings) -> Option<Bump> {
+    changelog
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            if entry.breaking.is_some() {
+                Some(Bump::Major)
+            } else {
+                conventions.version_bumps.get(&entry.kind).copied()
+            }
+        })
+        .max()
+}
+
+/// The new contents of a `Cargo.toml` or `package.json` with its version set, and of every
+/// other file that has to change with it: the package's entry in a `Cargo.lock` next to
+/// `Cargo.toml`. Nothing is written, so the release can still be called off.
+pub fn version_updates(
+    path: &Path,
+    version: &Version,
+) -> Result<Vec<(PathBuf, String)>, GustError> {
+    let content = std::fs::read_to_string(path)?;
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+
+    match file_name {
+        "Cargo.toml" => {
+            let (updated, package) = set_cargo_version(&content, version)
+                .map_err(|e| format!("{} {}", path.display(), e))?;
+            let mut updates = vec![(path.to_path_buf(), updated)];
+
+            let lock = path.with_file_name("Cargo.lock");
+            if let (Some((name, old)), true) = (package, lock.exists()) {
+                let lock_content = std::fs::read_to_string(&lock)?;
+                if let Some(updated) = set_cargo_lock_version(&lock_content, &name, &old, version) {
+                    updates.push((lock, updated));
+                }
+            }
+            Ok(updates)
+        }
+        "package.json" => {
+            let pattern = Regex::new(r#"("version"\s*:\s*")[^"]*(")"#).unwrap();
+            if !pattern.is_match(&content) {
+                return Err(format!("{} has no version", path.display()).into());
+            }
+            let updated = pattern.replace(&content, format!("${{1}}{}${{2}}", version));
+            Ok(vec![(path.to_path_buf(), updated.to_string())])
+        }
+        _ => Err(format!("Gust can't set the version in {}", path.display()).into()),
+    }
+}
+
+/// A package's name and version
+type Package = (String, String);
+
+/// Replace the version in the `[package]` table, or in `[workspace.package]` when the package
+/// inherits it with `version.workspace = true`, keeping the rest of the file as it was.
+/// Also returns the package's name and old version, if both were found.
+fn set_cargo_version(
+    content: &str,
+    version: &Version,
+) -> Result<(String, Option<Package>), &'static str> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+
+    let mut table = "";
+    let mut name = None;
+    let mut inherited = false;
+    // Where the `version = "..."` lines of each table are, and what they hold
+    let mut package_version = None;
+    let mut workspace_version = None;
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            table = trimmed;
+            continue;
+        }
+        let (key, value) = match trimmed.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+        let literal = value
+            .strip_prefix('"')
+            .and_then(|v| v.split('"').next())
+            .map(|v| (index, v.to_string()));
+        match (table, key) {
+            ("[package]", "name") => name = literal.map(|(_, name)| name),
+            ("[package]", "version") if value.starts_with('{') => inherited = true,
+            ("[package]", "version.workspace") => inherited = true,
+            ("[package]", "version") => package_version = package_version.or(literal),
+            ("[workspace.package]", "version") => workspace_version = workspace_version.or(literal),
+            _ => {}
+        }
+    }
+
+    let (index, old) = match (package_version, workspace_version) {
+        (Some(found), _) => found,
+        (None, Some(found)) if inherited || name.is_none() => found,
+        _ if inherited => {
+            return Err(
+                "inherits its version from the workspace, set it in the workspace's Cargo.toml",
+            )
+        }
+        _ => return Err("has no [package] version"),
+    };
+
+    let line = lines[index];
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let ending = if line.ends_with('\n') { "\n" } else { "" };
+    let mut output: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    output[index] = format!("{}version = \"{}\"{}", indent, version, ending);
+
+    Ok((output.concat(), name.map(|name| (name, old))))
+}
+
+/// Replace the version of the `name` package at `old` in a `Cargo.lock`
+fn set_cargo_lock_version(
+    content: &str,
+    name: &str,
+    old: &str,
+    version: &Version,
+) -> Option<String> {
+    let entry = format!("name = \"{}\"\nversion = \"{}\"\n", name, old);
+    if !content.contains(&entry) {
+        return None;
+    }
+    Some(content.replacen(
+        &entry,
+        &format!("name = \"{}\"\nversion = \"{}\"\n", name, version),
+        1,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> Version {
+        Version::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(
+            version("v1.2.3-rc.1+build.5"),
+            Version {
+                major: 1,
+                minor: 2,
+                patch: 3,
+                pre: Some("rc.1".to_string()),
+            }
+        );
+        for text in ["1.2.3", "0.0.0", "1.2.3-alpha-1.0", "1.0.0-0.3.7"] {
+            assert_eq!(version(text).to_string(), text);
+        }
+        for text in [
+            "1.2",
+            "1.2.3.4",
+            "1.2.x",
+            "1.2.3-",
+            "1.2.3-rc..1",
+            "1.2.3-rc.01",
+            "1.2.3-rc_1",
+            "",
+        ] {
+            assert_eq!(Version::parse(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn bumps_releases() {
+        let release = version("1.2.3");
+        assert_eq!(release.bump(Bump::Patch).to_string(), "1.2.4");
+        assert_eq!(release.bump(Bump::Minor).to_string(), "1.3.0");
+        assert_eq!(release.bump(Bump::Major).to_string(), "2.0.0");
+    }
+
+    #[test]
+    fn bumps_pre_releases_to_their_release_when_enough() {
+        assert_eq!(version("1.2.3-rc.1").bump(Bump::Patch).to_string(), "1.2.3");
+        assert_eq!(version("1.2.0-rc.1").bump(Bump::Minor).to_string(), "1.2.0");
+        assert_eq!(version("1.2.3-rc.1").bump(Bump::Minor).to_string(), "1.3.0");
+        assert_eq!(version("2.0.0-rc.1").bump(Bump::Major).to_string(), "2.0.0");
+        assert_eq!(version("1.2.0-rc.1").bump(Bump::Major).to_string(), "2.0.0");
+    }
+
+    #[test]
+    fn orders_versions_by_semver() {
+        // The example from SemVer §11, then the parts before the pre-release
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1",
+            "1.1.0-rc.9",
+            "1.1.0-rc.10",
+            "1.1.0",
+            "2.0.0",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(
+                version(pair[0]) < version(pair[1]),
+                "{} < {}",
+                pair[0],
+                pair[1]
+            );
+        }
+        assert_eq!(
+            version("1.0.0-rc.99999999999999999999")
+                .cmp(&version("1.0.0-rc.100000000000000000000")),
+            Ordering::Less
+        );
+        assert_eq!(
+            version("v1.0.0+a").cmp(&version("1.0.0+b")),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn finds_the_latest_version_tag() {
+        let tags: Vec<String> = ["v1.0.0-rc.9", "v1.0.0-rc.10", "latest", "v0.9.0"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(
+            latest_version_tag(&tags),
+            Some(("v1.0.0-rc.10".to_string(), version("1.0.0-rc.10")))
+        );
+        assert_eq!(latest_version_tag(&["latest".to_string()]), None);
+    }
+
+    const MANIFEST: &str = "[package]\n\
+        name = \"gust\"\n\
+        \x20 version = \"0.1.0\"\n\
+        edition = \"2021\"\n\
+        \n\
+        [dependencies]\n\
+        serde = { version = \"1.0\" }\n\
+        \n\
+        [dependencies.clap]\n\
+        version = \"3.2.16\"\n";
+
+    const LOCK: &str = "[[package]]\n\
+        name = \"clap\"\n\
+        version = \"0.1.0\"\n\
+        \n\
+        [[package]]\n\
+        name = \"gust\"\n\
+        version = \"0.1.0\"\n\
+        dependencies = [\n \"clap\",\n]\n";
+
+    #[test]
+    fn sets_only_the_package_version() {
+        let (updated, package) = set_cargo_version(MANIFEST, &version("0.2.0")).unwrap();
+        assert_eq!(
+            updated,
+            MANIFEST.replace("  version = \"0.1.0\"", "  version = \"0.2.0\"")
+        );
+        assert_eq!(package, Some(("gust".to_string(), "0.1.0".to_string())));
+
+        assert_eq!(
+            set_cargo_version("[dependencies]\nversion = \"1\"\n", &version("0.2.0")),
+            Err("has no [package] version")
+        );
+    }
+
+    #[test]
+    fn sets_the_workspace_version_a_package_inherits() {
+        for inherit in ["version.workspace = true", "version = { workspace = true }"] {
+            let manifest = format!(
+                "[workspace.package]\nversion = \"1.0.0\"\n\n[package]\nname = \"gust\"\n{}\n",
+                inherit
+            );
+            let (updated, package) = set_cargo_version(&manifest, &version("1.1.0")).unwrap();
+            assert_eq!(updated, manifest.replace("1.0.0", "1.1.0"));
+            assert_eq!(package, Some(("gust".to_string(), "1.0.0".to_string())));
+
+            let member = format!("[package]\nname = \"member\"\n{}\n", inherit);
+            assert!(set_cargo_version(&member, &version("1.1.0"))
+                .unwrap_err()
+                .starts_with("inherits its version from the workspace"));
+        }
+    }
+
+    #[test]
+    fn sets_the_version_of_a_virtual_workspace() {
+        let manifest = "[workspace]\nmembers = [\"a\"]\n\n[workspace.package]\nversion = \"1.0.0\"";
+        let (updated, package) = set_cargo_version(manifest, &version("2.0.0")).unwrap();
+        assert_eq!(updated, manifest.replace("1.0.0", "2.0.0"));
+        assert_eq!(package, None);
+    }
+
+    #[test]
+    fn sets_only_the_packages_lock_entry() {
+        let updated = set_cargo_lock_version(LOCK, "gust", "0.1.0", &version("0.2.0")).unwrap();
+        assert_eq!(
+            updated,
+            LOCK.replace(
+                "name = \"gust\"\nversion = \"0.1.0\"",
+                "name = \"gust\"\nversion = \"0.2.0\""
+            )
+        );
+        assert!(updated.contains("name = \"clap\"\nversion = \"0.1.0\""));
+
+        assert_eq!(
+            set_cargo_lock_version(LOCK, "gust", "0.0.9", &version("0.2.0")),
+            None
+        );
+    }
+
+    #[test]
+    fn updates_the_manifest_with_its_lock_file_or_package_json() {
+        let dir = std::env::temp_dir().join(format!("gust-release-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), MANIFEST).unwrap();
+        std::fs::write(dir.join("Cargo.lock"), LOCK).unwrap();
+        std::fs::write(
+            dir.join("package.json"),
+            "{\n  \"name\": \"gust\",\n  \"version\" : \"0.1.0\"\n}\n",
+        )
+        .unwrap();
+
+        let cargo = version_updates(&dir.join("Cargo.toml"), &version("0.2.0"));
+        let npm = version_updates(&dir.join("package.json"), &version("0.2.0"));
+        let unknown = version_updates(&dir.join("Cargo.lock"), &version("0.2.0"));
+        // Nothing is written until the updates are applied
+        let untouched = std::fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let files: Vec<PathBuf> = cargo.unwrap().into_iter().map(|(path, _)| path).collect();
+        assert_eq!(files, [dir.join("Cargo.toml"), dir.join("Cargo.lock")]);
+        assert_eq!(
+            npm.unwrap()[0].1,
+            "{\n  \"name\": \"gust\",\n  \"version\" : \"0.2.0\"\n}\n"
+        );
+        assert!(unknown.is_err());
+        assert_eq!(untouched, MANIFEST);
+    }
+}
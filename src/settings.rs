@@ -1,26 +1,30 @@
 use colored::Colorize;
-use confy;
-use dirs_next;
 use serde::{Deserialize, Serialize};
-use serde_json;
+use std::collections::BTreeMap;
 
 use crate::cli;
+use crate::error::GustError;
+use crate::release::{self, Bump};
 
 const APP_NAME: &str = "gust";
 pub const DEFAULT_COMMIT_CONV: &str = "ConventionalCommits";
 
-pub fn get_config(file_name: &str) -> std::path::PathBuf {
-    let mut dir = dirs_next::config_dir().unwrap();
+pub fn get_config(file_name: &str) -> Result<std::path::PathBuf, GustError> {
+    let mut dir = dirs_next::config_dir()
+        .ok_or_else(|| GustError::Config("No config directory was found".to_string()))?;
     dir.push(APP_NAME);
     dir.push(file_name);
-    return dir;
+    Ok(dir)
 }
 
+// Fields missing from an older config file take their default value
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub show_welcome: bool,
     pub show_all_in_add_menu: bool,
     pub enable_basic_menu: bool,
+    pub use_tui: bool,
 
     pub verbose_commit: bool,
     pub use_git_commit_message_dialog: bool,
@@ -42,6 +46,7 @@ impl std::default::Default for Config {
             show_welcome: false,
             show_all_in_add_menu: true,
             enable_basic_menu: true,
+            use_tui: false,
 
             verbose_commit: false,
             use_git_commit_message_dialog: false,
@@ -59,16 +64,17 @@ impl std::default::Default for Config {
     }
 }
 
-pub fn load_config() -> Result<Config, confy::ConfyError> {
-    println!(
+pub fn load_config() -> Result<Config, GustError> {
+    // On stderr, so commands printing JSON or a changelog can be piped
+    eprintln!(
         "loading config at {:?}",
         confy::get_configuration_file_path(APP_NAME, None)
     );
-    return confy::load(APP_NAME, None);
+    confy::load(APP_NAME, None).map_err(|e| GustError::Config(e.to_string()))
 }
 
-pub fn save_config(config: Config) -> Result<(), confy::ConfyError> {
-    return confy::store(APP_NAME, None, config);
+pub fn save_config(config: Config) -> Result<(), GustError> {
+    confy::store(APP_NAME, None, config).map_err(|e| GustError::Config(e.to_string()))
 }
 
 #[derive(Serialize, Deserialize)]
@@ -85,18 +91,28 @@ pub struct ConventionSettings {
     pub important_symbol: String,
     pub separator: String,
     pub footers: Vec<String>,
+    /// The longest subject line the linter accepts
+    #[serde(default = "default_subject_length")]
+    pub subject_length: usize,
+    /// The version part each commit type bumps when releasing, breaking changes always bump major
+    #[serde(default = "release::default_version_bumps")]
+    pub version_bumps: BTreeMap<String, Bump>,
 }
 
-pub fn load_convention(convention_name: String) -> ConventionSettings {
-    let json: ConventionSettings = serde_json::from_reader(
-        std::fs::File::open(get_config((convention_name + ".json").as_str())).unwrap(),
-    )
-    .unwrap();
-    return json;
+fn default_subject_length() -> usize {
+    72
 }
 
-pub fn check_for_convention_file(config: &Config) -> Result<(), String> {
-    let expected_path = get_config((DEFAULT_COMMIT_CONV.to_string() + ".json").as_str());
+pub fn load_convention(convention_name: String) -> Result<ConventionSettings, GustError> {
+    let path = get_config((convention_name + ".json").as_str())?;
+    let file = std::fs::File::open(&path)
+        .map_err(|e| GustError::Convention(format!("{}: {}", path.display(), e)))?;
+    serde_json::from_reader(file)
+        .map_err(|e| GustError::Convention(format!("{}: {}", path.display(), e)))
+}
+
+pub fn check_for_convention_file(config: &Config) -> Result<(), GustError> {
+    let expected_path = get_config((DEFAULT_COMMIT_CONV.to_string() + ".json").as_str())?;
 
     if !expected_path.exists() {
         println!("{}", "Convention file not found!".bright_red());
@@ -105,28 +121,28 @@ pub fn check_for_convention_file(config: &Config) -> Result<(), String> {
             if cli::ask_choice_cli(
                 "Would you like to copy the default convention file?".to_string(),
             )? {
-                let mut cwd_path = std::env::current_dir().map_err(|e| e.to_string())?;
+                let mut cwd_path = std::env::current_dir()?;
                 cwd_path.push(DEFAULT_COMMIT_CONV.to_string() + ".json");
 
-                let mut exe_path = std::env::current_exe().map_err(|e| e.to_string())?;
+                let mut exe_path = std::env::current_exe()?;
                 exe_path.push("..");
                 exe_path.push(DEFAULT_COMMIT_CONV.to_string() + ".json");
 
                 if cwd_path.exists() {
-                    std::fs::copy(cwd_path, expected_path).map_err(|e| e.to_string())?;
+                    std::fs::copy(cwd_path, expected_path)?;
                 } else if exe_path.exists() {
-                    std::fs::copy(exe_path, expected_path).map_err(|e| e.to_string())?;
+                    std::fs::copy(exe_path, expected_path)?;
                 } else {
-                    return Err("Default convention file found! Make sure that the default convention file is next to the executable, then create an issue".to_string());
+                    return Err(GustError::Convention("Default convention file not found! Make sure that the default convention file is next to the executable, then create an issue".to_string()));
                 }
             }
         } else {
-            return Err(format!(
+            return Err(GustError::Convention(format!(
                 "{}: '{}'",
                 "Please put the convention file at".bright_yellow(),
-                expected_path.to_str().unwrap(),
-            ));
+                expected_path.display(),
+            )));
         }
     }
-    return Ok(());
+    Ok(())
 }
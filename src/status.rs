@@ -0,0 +1,605 @@
+use crate::error::GustError;
+use crate::git::GitCommand;
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// The state of a file on one side (index or worktree) of `git status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileState {
+    Unmodified,
+    Modified,
+    TypeChanged,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+    Unmerged,
+    Untracked,
+    Ignored,
+}
+
+impl FileState {
+    fn from_char(c: char) -> Result<Self, GustError> {
+        match c {
+            '.' => Ok(FileState::Unmodified),
+            'M' => Ok(FileState::Modified),
+            'T' => Ok(FileState::TypeChanged),
+            'A' => Ok(FileState::Added),
+            'D' => Ok(FileState::Deleted),
+            'R' => Ok(FileState::Renamed),
+            'C' => Ok(FileState::Copied),
+            'U' => Ok(FileState::Unmerged),
+            _ => Err(format!("Unknown file state in git status: '{}'", c).into()),
+        }
+    }
+
+    /// The letter `git status --short` shows for the state
+    pub fn code(&self) -> char {
+        match self {
+            FileState::Unmodified => ' ',
+            FileState::Modified => 'M',
+            FileState::TypeChanged => 'T',
+            FileState::Added => 'A',
+            FileState::Deleted => 'D',
+            FileState::Renamed => 'R',
+            FileState::Copied => 'C',
+            FileState::Unmerged => 'U',
+            FileState::Untracked => '?',
+            FileState::Ignored => '!',
+        }
+    }
+}
+
+/// Which sides of a merge changed an unmerged file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    BothDeleted,
+    AddedByUs,
+    DeletedByThem,
+    AddedByThem,
+    DeletedByUs,
+    BothAdded,
+    BothModified,
+}
+
+impl ConflictKind {
+    fn from_xy(xy: &str) -> Result<Self, GustError> {
+        match xy {
+            "DD" => Ok(ConflictKind::BothDeleted),
+            "AU" => Ok(ConflictKind::AddedByUs),
+            "UD" => Ok(ConflictKind::DeletedByThem),
+            "UA" => Ok(ConflictKind::AddedByThem),
+            "DU" => Ok(ConflictKind::DeletedByUs),
+            "AA" => Ok(ConflictKind::BothAdded),
+            "UU" => Ok(ConflictKind::BothModified),
+            _ => Err(format!("Unknown conflict state in git status: '{}'", xy).into()),
+        }
+    }
+
+    /// Whether our side of the merge still has the file
+    pub fn ours_exists(&self) -> bool {
+        !matches!(self, ConflictKind::BothDeleted | ConflictKind::DeletedByUs)
+    }
+
+    /// Whether their side of the merge still has the file
+    pub fn theirs_exists(&self) -> bool {
+        !matches!(
+            self,
+            ConflictKind::BothDeleted | ConflictKind::DeletedByThem
+        )
+    }
+}
+
+impl std::fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ConflictKind::BothDeleted => "both deleted",
+            ConflictKind::AddedByUs => "added by us",
+            ConflictKind::DeletedByThem => "deleted by them",
+            ConflictKind::AddedByThem => "added by them",
+            ConflictKind::DeletedByUs => "deleted by us",
+            ConflictKind::BothAdded => "both added",
+            ConflictKind::BothModified => "both modified",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Flags git reports for an entry that is a submodule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubmoduleState {
+    pub commit_changed: bool,
+    pub has_tracked_changes: bool,
+    pub has_untracked_changes: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileEntry {
+    pub index: FileState,
+    pub worktree: FileState,
+    /// Relative to the top of the work tree, whatever directory Gust was started in
+    pub path: PathBuf,
+    /// The path the file had before being renamed or copied
+    pub orig_path: Option<PathBuf>,
+    pub submodule: Option<SubmoduleState>,
+    /// Set for unmerged entries
+    pub conflict: Option<ConflictKind>,
+}
+
+impl FileEntry {
+    pub fn is_untracked(&self) -> bool {
+        self.worktree == FileState::Untracked
+    }
+
+    pub fn is_unmerged(&self) -> bool {
+        self.conflict.is_some()
+    }
+
+    /// Has changes in the index that `git commit` would record
+    pub fn is_staged(&self) -> bool {
+        !self.is_unmerged()
+            && !matches!(
+                self.index,
+                FileState::Unmodified | FileState::Untracked | FileState::Ignored
+            )
+    }
+
+    /// Has changes in the worktree that `git add` would stage
+    pub fn is_unstaged(&self) -> bool {
+        self.is_unmerged() || self.is_untracked() || self.worktree != FileState::Unmodified
+    }
+
+    /// The path as it should be shown to the user, renames shown as `old -> new`
+    pub fn label(&self) -> String {
+        let mut label = match &self.orig_path {
+            Some(orig) => format!("{} -> {}", orig.display(), self.path.display()),
+            None => self.path.display().to_string(),
+        };
+
+        if let Some(sub) = &self.submodule {
+            let mut notes = Vec::<&str>::new();
+            if sub.commit_changed {
+                notes.push("new commits");
+            }
+            if sub.has_tracked_changes {
+                notes.push("modified content");
+            }
+            if sub.has_untracked_changes {
+                notes.push("untracked content");
+            }
+            if notes.is_empty() {
+                label.push_str(" (submodule)");
+            } else {
+                label.push_str(&format!(" (submodule: {})", notes.join(", ")));
+            }
+        }
+        label
+    }
+
+    /// Every path git needs to be given to act on this entry, joined onto `toplevel`
+    /// so they work from any directory of the repository
+    pub fn paths(&self, toplevel: &Path) -> Vec<PathBuf> {
+        let mut paths = vec![toplevel.join(&self.path)];
+        if let Some(orig) = &self.orig_path {
+            paths.push(toplevel.join(orig));
+        }
+        paths
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BranchStatus {
+    /// `None` for a repository without any commits
+    pub oid: Option<String>,
+    /// `None` when HEAD is detached
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+impl BranchStatus {
+    pub fn summary(&self) -> String {
+        let mut output = match &self.head {
+            Some(head) => format!("On branch {}", head),
+            None => match &self.oid {
+                Some(oid) => format!("HEAD detached at {}", &oid[..oid.len().min(7)]),
+                None => "HEAD detached".to_string(),
+            },
+        };
+
+        if let Some(upstream) = &self.upstream {
+            output.push_str(&format!(
+                " tracking {} (ahead {}, behind {})",
+                upstream, self.ahead, self.behind
+            ));
+        }
+        output
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RepoStatus {
+    pub branch: BranchStatus,
+    pub entries: Vec<FileEntry>,
+}
+
+impl RepoStatus {
+    pub fn staged(&self) -> Vec<&FileEntry> {
+        self.entries.iter().filter(|e| e.is_staged()).collect()
+    }
+
+    pub fn unstaged(&self) -> Vec<&FileEntry> {
+        self.entries.iter().filter(|e| e.is_unstaged()).collect()
+    }
+}
+
+/// Map labels picked by the user back to the entries they were made from
+pub fn entries_from_labels<'a>(entries: &[&'a FileEntry], labels: &[String]) -> Vec<&'a FileEntry> {
+    entries
+        .iter()
+        .filter(|e| labels.contains(&e.label()))
+        .copied()
+        .collect()
+}
+
+/// A multi-step git command that stopped part way through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+}
+
+impl Operation {
+    /// The git subcommand that drives this operation
+    pub fn command(&self) -> &'static str {
+        match self {
+            Operation::Merge => "merge",
+            Operation::Rebase => "rebase",
+            Operation::CherryPick => "cherry-pick",
+            Operation::Revert => "revert",
+        }
+    }
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.command())
+    }
+}
+
+/// Find out if a merge, rebase, cherry-pick or revert is waiting to be continued
+pub fn operation_in_progress(git_dir: &Path) -> Option<Operation> {
+    if git_dir.join("rebase-merge").exists() || git_dir.join("rebase-apply").exists() {
+        Some(Operation::Rebase)
+    } else if git_dir.join("MERGE_HEAD").exists() {
+        Some(Operation::Merge)
+    } else if git_dir.join("CHERRY_PICK_HEAD").exists() {
+        Some(Operation::CherryPick)
+    } else if git_dir.join("REVERT_HEAD").exists() {
+        Some(Operation::Revert)
+    } else {
+        None
+    }
+}
+
+pub fn git_status() -> Result<RepoStatus, GustError> {
+    let output = GitCommand::new("status")
+        .arg("--porcelain=v2")
+        .arg("-z")
+        .arg("--branch")
+        .capture()
+        .read_only()
+        .run()?;
+
+    if output.success() {
+        parse_porcelain_v2(&output.stdout)
+    } else {
+        Err(output.into_error())
+    }
+}
+
+/// Parse the output of `git status --porcelain=v2 -z --branch`
+pub fn parse_porcelain_v2(output: &[u8]) -> Result<RepoStatus, GustError> {
+    let mut status = RepoStatus::default();
+    let mut records = output.split(|b| *b == 0).filter(|r| !r.is_empty());
+
+    while let Some(record) = records.next() {
+        // Everything up to the path is ascii, so it is safe to read as a string
+        let (kind, rest) = match record.split_first() {
+            Some((kind, rest)) => (*kind as char, rest),
+            None => continue,
+        };
+
+        match kind {
+            '#' => parse_header(&String::from_utf8_lossy(rest), &mut status.branch)?,
+            '1' => {
+                let (fields, path) = split_fields(rest, 7)?;
+                status.entries.push(FileEntry {
+                    index: FileState::from_char(state_char(&fields[0], 0)?)?,
+                    worktree: FileState::from_char(state_char(&fields[0], 1)?)?,
+                    path: path_from_bytes(path),
+                    orig_path: None,
+                    submodule: parse_submodule(&fields[1])?,
+                    conflict: None,
+                });
+            }
+            '2' => {
+                let (fields, path) = split_fields(rest, 8)?;
+                // With `-z` the original path is the next NUL separated record
+                let orig = records.next().ok_or_else(|| {
+                    "Rename entry in git status was missing its origin".to_string()
+                })?;
+                status.entries.push(FileEntry {
+                    index: FileState::from_char(state_char(&fields[0], 0)?)?,
+                    worktree: FileState::from_char(state_char(&fields[0], 1)?)?,
+                    path: path_from_bytes(path),
+                    orig_path: Some(path_from_bytes(orig)),
+                    submodule: parse_submodule(&fields[1])?,
+                    conflict: None,
+                });
+            }
+            'u' => {
+                let (fields, path) = split_fields(rest, 9)?;
+                status.entries.push(FileEntry {
+                    index: FileState::Unmerged,
+                    worktree: FileState::Unmerged,
+                    path: path_from_bytes(path),
+                    orig_path: None,
+                    submodule: parse_submodule(&fields[1])?,
+                    conflict: Some(ConflictKind::from_xy(&fields[0])?),
+                });
+            }
+            '?' | '!' => {
+                let state = if kind == '?' {
+                    FileState::Untracked
+                } else {
+                    FileState::Ignored
+                };
+                status.entries.push(FileEntry {
+                    index: state,
+                    worktree: state,
+                    path: path_from_bytes(rest.strip_prefix(b" ").unwrap_or(rest)),
+                    orig_path: None,
+                    submodule: None,
+                    conflict: None,
+                });
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown entry in git status: {}",
+                    String::from_utf8_lossy(record)
+                )
+                .into())
+            }
+        }
+    }
+
+    Ok(status)
+}
+
+fn parse_header(line: &str, branch: &mut BranchStatus) -> Result<(), GustError> {
+    let line = line.trim_start();
+    let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+
+    match key {
+        "branch.oid" => {
+            branch.oid = if value == "(initial)" {
+                None
+            } else {
+                Some(value.to_string())
+            }
+        }
+        "branch.head" => {
+            branch.head = if value == "(detached)" {
+                None
+            } else {
+                Some(value.to_string())
+            }
+        }
+        "branch.upstream" => branch.upstream = Some(value.to_string()),
+        "branch.ab" => {
+            for count in value.split_whitespace() {
+                let parse = |n: &str| {
+                    n.parse::<usize>()
+                        .map_err(|_| format!("Invalid ahead/behind count in git status: {}", value))
+                };
+                if let Some(ahead) = count.strip_prefix('+') {
+                    branch.ahead = parse(ahead)?;
+                } else if let Some(behind) = count.strip_prefix('-') {
+                    branch.behind = parse(behind)?;
+                }
+            }
+        }
+        // Other headers (like stash counts) aren't used
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Split `count` space separated ascii fields off the front of a record,
+/// returning them along with the remaining path bytes
+fn split_fields(record: &[u8], count: usize) -> Result<(Vec<String>, &[u8]), GustError> {
+    // Skip the space following the entry kind
+    let mut rest = record.strip_prefix(b" ").unwrap_or(record);
+    let mut fields = Vec::<String>::with_capacity(count);
+
+    for _ in 0..count {
+        let end = rest.iter().position(|b| *b == b' ').ok_or_else(|| {
+            format!(
+                "Malformed entry in git status: {}",
+                String::from_utf8_lossy(record)
+            )
+        })?;
+        fields.push(String::from_utf8_lossy(&rest[..end]).to_string());
+        rest = &rest[end + 1..];
+    }
+
+    Ok((fields, rest))
+}
+
+fn state_char(xy: &str, index: usize) -> Result<char, GustError> {
+    xy.chars()
+        .nth(index)
+        .ok_or_else(|| format!("Malformed file state in git status: '{}'", xy).into())
+}
+
+fn parse_submodule(field: &str) -> Result<Option<SubmoduleState>, GustError> {
+    let mut chars = field.chars();
+    match chars.next() {
+        Some('N') => Ok(None),
+        Some('S') => {
+            let flags: Vec<char> = chars.collect();
+            if flags.len() != 3 {
+                return Err(format!("Malformed submodule state in git status: {}", field).into());
+            }
+            Ok(Some(SubmoduleState {
+                commit_changed: flags[0] == 'C',
+                has_tracked_changes: flags[1] == 'M',
+                has_untracked_changes: flags[2] == 'U',
+            }))
+        }
+        _ => Err(format!("Malformed submodule state in git status: {}", field).into()),
+    }
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    // git always writes utf-8 paths on windows
+    PathBuf::from(OsStr::new(String::from_utf8_lossy(bytes).as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OID: &str = "61780798228d17af2d34fce4cfbdf35556832472";
+
+    #[test]
+    fn parses_branch_headers() {
+        let output = format!(
+            "# branch.oid {}\0# branch.head main\0# branch.upstream origin/main\0# branch.ab +2 -1\0",
+            OID
+        );
+        let status = parse_porcelain_v2(output.as_bytes()).unwrap();
+
+        assert_eq!(status.branch.oid.as_deref(), Some(OID));
+        assert_eq!(status.branch.head.as_deref(), Some("main"));
+        assert_eq!(status.branch.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.branch.ahead, status.branch.behind), (2, 1));
+        assert!(status.entries.is_empty());
+    }
+
+    #[test]
+    fn parses_a_new_repository_with_detached_head() {
+        let status =
+            parse_porcelain_v2(b"# branch.oid (initial)\0# branch.head (detached)\0").unwrap();
+
+        assert_eq!(status.branch.oid, None);
+        assert_eq!(status.branch.head, None);
+        assert_eq!(status.branch.summary(), "HEAD detached");
+    }
+
+    #[test]
+    fn parses_ordinary_and_untracked_entries() {
+        let output = format!(
+            "1 .M N... 100644 100644 100644 {0} {0} sub/a file\0? sub/new\0! target/\0",
+            OID
+        );
+        let status = parse_porcelain_v2(output.as_bytes()).unwrap();
+
+        let modified = &status.entries[0];
+        assert_eq!(modified.index, FileState::Unmodified);
+        assert_eq!(modified.worktree, FileState::Modified);
+        // Paths may contain spaces, everything after the fields is the path
+        assert_eq!(modified.path, PathBuf::from("sub/a file"));
+        assert!(modified.is_unstaged() && !modified.is_staged());
+
+        assert!(status.entries[1].is_untracked());
+        assert_eq!(status.entries[1].path, PathBuf::from("sub/new"));
+        assert_eq!(status.entries[2].worktree, FileState::Ignored);
+    }
+
+    #[test]
+    fn parses_renames_with_the_origin_in_the_next_record() {
+        let output = format!(
+            "2 R. N... 100644 100644 100644 {0} {0} R100 new name\0old name\0? after\0",
+            OID
+        );
+        let status = parse_porcelain_v2(output.as_bytes()).unwrap();
+
+        assert_eq!(status.entries.len(), 2);
+        let renamed = &status.entries[0];
+        assert_eq!(renamed.index, FileState::Renamed);
+        assert_eq!(renamed.path, PathBuf::from("new name"));
+        assert_eq!(renamed.orig_path, Some(PathBuf::from("old name")));
+        assert_eq!(renamed.label(), "old name -> new name");
+        assert_eq!(
+            renamed.paths(Path::new("/repo")),
+            vec![
+                PathBuf::from("/repo/new name"),
+                PathBuf::from("/repo/old name")
+            ]
+        );
+        assert_eq!(status.entries[1].path, PathBuf::from("after"));
+    }
+
+    #[test]
+    fn rename_without_origin_is_an_error() {
+        let output = format!("2 R. N... 100644 100644 100644 {0} {0} R100 new\0", OID);
+        assert!(parse_porcelain_v2(output.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn parses_unmerged_entries() {
+        let output = format!(
+            "u UU N... 100644 100644 100644 100644 {0} {0} {0} conflicted.txt\0u DU N... 100644 000000 100644 100644 {0} {0} {0} gone.txt\0",
+            OID
+        );
+        let status = parse_porcelain_v2(output.as_bytes()).unwrap();
+
+        let both = &status.entries[0];
+        assert_eq!(both.conflict, Some(ConflictKind::BothModified));
+        assert_eq!(both.path, PathBuf::from("conflicted.txt"));
+        assert!(both.is_unmerged() && both.is_unstaged() && !both.is_staged());
+
+        let deleted = status.entries[1].conflict.unwrap();
+        assert_eq!(deleted, ConflictKind::DeletedByUs);
+        assert!(!deleted.ours_exists() && deleted.theirs_exists());
+    }
+
+    #[test]
+    fn parses_submodule_flags() {
+        let output = format!("1 .M SCM. 160000 160000 160000 {0} {0} lib\0", OID);
+        let status = parse_porcelain_v2(output.as_bytes()).unwrap();
+
+        assert_eq!(
+            status.entries[0].submodule,
+            Some(SubmoduleState {
+                commit_changed: true,
+                has_tracked_changes: true,
+                has_untracked_changes: false,
+            })
+        );
+        assert_eq!(
+            status.entries[0].label(),
+            "lib (submodule: new commits, modified content)"
+        );
+    }
+
+    #[test]
+    fn unknown_entries_are_errors() {
+        assert!(parse_porcelain_v2(b"x something\0").is_err());
+        let output = format!("1 .Z N... 100644 100644 100644 {0} {0} a\0", OID);
+        assert!(parse_porcelain_v2(output.as_bytes()).is_err());
+    }
+}
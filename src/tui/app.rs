@@ -0,0 +1,310 @@
+use crate::backend::GitBackend;
+use crate::error::GustError;
+use crate::git;
+use crate::status::{FileEntry, Operation, RepoStatus};
+use crate::utils;
+
+use ratatui::widgets::ListState;
+
+/// The panes that can hold the selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Unstaged,
+    Staged,
+    Log,
+}
+
+impl Pane {
+    fn next(self) -> Self {
+        match self {
+            Pane::Unstaged => Pane::Staged,
+            Pane::Staged => Pane::Log,
+            Pane::Log => Pane::Unstaged,
+        }
+    }
+
+    fn previous(self) -> Self {
+        match self {
+            Pane::Unstaged => Pane::Log,
+            Pane::Staged => Pane::Unstaged,
+            Pane::Log => Pane::Staged,
+        }
+    }
+}
+
+/// The line shown under the branch summary after an action
+pub enum Message {
+    Info(String),
+    Error(String),
+}
+
+/// Everything the full-screen mode shows, reloaded from git after every action
+pub struct App {
+    pub status: RepoStatus,
+    pub operation: Option<Operation>,
+    pub log: Vec<String>,
+    pub focus: Pane,
+    pub unstaged: ListState,
+    pub staged: ListState,
+    pub log_state: ListState,
+    pub preview: String,
+    pub preview_scroll: u16,
+    pub message: Option<Message>,
+    pub dry_run: bool,
+}
+
+impl App {
+    pub fn new(backend: &impl GitBackend) -> Result<Self, GustError> {
+        let mut app = App {
+            status: RepoStatus::default(),
+            operation: None,
+            log: vec![],
+            focus: Pane::Unstaged,
+            unstaged: ListState::default(),
+            staged: ListState::default(),
+            log_state: ListState::default(),
+            preview: String::new(),
+            preview_scroll: 0,
+            message: None,
+            dry_run: git::is_dry_run(),
+        };
+        app.refresh(backend)?;
+        Ok(app)
+    }
+
+    /// Reload the status and log, keeping the selections in range
+    pub fn refresh(&mut self, backend: &impl GitBackend) -> Result<(), GustError> {
+        self.status = backend.status()?;
+        self.operation = backend.operation_in_progress()?;
+        self.log = match backend.log(None) {
+            Ok(Some(log)) => log
+                .lines()
+                .map(|l| utils::strip_colors(l.to_string()))
+                .collect(),
+            // A repository without commits has no log yet
+            Ok(None) | Err(GustError::GitFailed { .. }) => vec![],
+            Err(e) => return Err(e),
+        };
+
+        let lengths = [
+            self.status.unstaged().len(),
+            self.status.staged().len(),
+            self.log.len(),
+        ];
+        for (state, length) in [&mut self.unstaged, &mut self.staged, &mut self.log_state]
+            .into_iter()
+            .zip(lengths)
+        {
+            state.select(match (state.selected(), length) {
+                (_, 0) => None,
+                (Some(index), _) => Some(index.min(length - 1)),
+                (None, _) => Some(0),
+            });
+        }
+        self.update_preview(backend)
+    }
+
+    pub fn unstaged_entries(&self) -> Vec<&FileEntry> {
+        self.status.unstaged()
+    }
+
+    pub fn staged_entries(&self) -> Vec<&FileEntry> {
+        self.status.staged()
+    }
+
+    fn focused_state(&mut self) -> &mut ListState {
+        match self.focus {
+            Pane::Unstaged => &mut self.unstaged,
+            Pane::Staged => &mut self.staged,
+            Pane::Log => &mut self.log_state,
+        }
+    }
+
+    fn focused_len(&self) -> usize {
+        match self.focus {
+            Pane::Unstaged => self.unstaged_entries().len(),
+            Pane::Staged => self.staged_entries().len(),
+            Pane::Log => self.log.len(),
+        }
+    }
+
+    /// The file selected in the focused list, `None` when the log is focused
+    fn selected_entry(&self) -> Option<&FileEntry> {
+        match self.focus {
+            Pane::Unstaged => self
+                .unstaged
+                .selected()
+                .and_then(|i| self.unstaged_entries().get(i).copied()),
+            Pane::Staged => self
+                .staged
+                .selected()
+                .and_then(|i| self.staged_entries().get(i).copied()),
+            Pane::Log => None,
+        }
+    }
+
+    pub fn move_selection(
+        &mut self,
+        backend: &impl GitBackend,
+        delta: isize,
+    ) -> Result<(), GustError> {
+        let length = self.focused_len();
+        if length == 0 {
+            return Ok(());
+        }
+        let state = self.focused_state();
+        let current = state.selected().unwrap_or(0) as isize;
+        state.select(Some(
+            (current + delta).clamp(0, length as isize - 1) as usize
+        ));
+        self.update_preview(backend)
+    }
+
+    pub fn next_pane(&mut self, backend: &impl GitBackend) -> Result<(), GustError> {
+        self.focus = self.focus.next();
+        self.update_preview(backend)
+    }
+
+    pub fn previous_pane(&mut self, backend: &impl GitBackend) -> Result<(), GustError> {
+        self.focus = self.focus.previous();
+        self.update_preview(backend)
+    }
+
+    pub fn scroll_preview(&mut self, delta: i32) {
+        let lines = self.preview.lines().count() as i32;
+        self.preview_scroll =
+            (self.preview_scroll as i32 + delta).clamp(0, lines.max(1) - 1) as u16;
+    }
+
+    /// Show the diff of the selected file, or the selected commit when the log is focused
+    fn update_preview(&mut self, backend: &impl GitBackend) -> Result<(), GustError> {
+        self.preview_scroll = 0;
+        let preview = match self.focus {
+            Pane::Log => match self.log_state.selected().and_then(|i| self.log.get(i)) {
+                Some(line) => {
+                    let hash = line.split_whitespace().next().unwrap_or_default();
+                    backend.show(hash.to_string())
+                }
+                None => Ok(String::new()),
+            },
+            Pane::Unstaged | Pane::Staged => match self.selected_entry() {
+                Some(entry) if entry.is_untracked() => untracked_preview(backend, entry),
+                Some(entry) => {
+                    let options = if self.focus == Pane::Staged {
+                        vec!["--cached".to_string()]
+                    } else {
+                        vec![]
+                    };
+                    backend
+                        .diff(options, entry.paths(&backend.toplevel()?))
+                        .map(|diff| diff.unwrap_or_default())
+                }
+                None => Ok(String::new()),
+            },
+        };
+
+        self.preview = match preview {
+            Ok(preview) => utils::strip_colors(preview).replace('\t', "    "),
+            Err(e) if e.is_recoverable() => utils::strip_colors(e.to_string()),
+            Err(e) => return Err(e),
+        };
+        Ok(())
+    }
+
+    /// Stage the selected unstaged file, or unstage the selected staged one
+    pub fn toggle_selected(&mut self, backend: &impl GitBackend) -> Result<(), GustError> {
+        let (paths, label) = match self.selected_entry() {
+            Some(entry) => (entry.paths(&backend.toplevel()?), entry.label()),
+            None => return Ok(()),
+        };
+
+        let result = match self.focus {
+            Pane::Unstaged => backend.add(paths).map(|_| format!("Staged {}", label)),
+            Pane::Staged => backend.reset(paths).map(|_| format!("Unstaged {}", label)),
+            Pane::Log => return Ok(()),
+        };
+        self.finish(backend, result)
+    }
+
+    pub fn stage_all(&mut self, backend: &impl GitBackend) -> Result<(), GustError> {
+        let toplevel = backend.toplevel()?;
+        let paths: Vec<_> = self
+            .unstaged_entries()
+            .iter()
+            .map(|e| toplevel.join(&e.path))
+            .collect();
+        if paths.is_empty() {
+            self.message = Some(Message::Info("Nothing to stage".to_string()));
+            return Ok(());
+        }
+        let result = backend.add(paths).map(|_| "Staged every file".to_string());
+        self.finish(backend, result)
+    }
+
+    pub fn unstage_all(&mut self, backend: &impl GitBackend) -> Result<(), GustError> {
+        let toplevel = backend.toplevel()?;
+        let paths: Vec<_> = self
+            .staged_entries()
+            .iter()
+            .flat_map(|e| e.paths(&toplevel))
+            .collect();
+        if paths.is_empty() {
+            self.message = Some(Message::Info("Nothing to unstage".to_string()));
+            return Ok(());
+        }
+        let result = backend
+            .reset(paths)
+            .map(|_| "Unstaged every file".to_string());
+        self.finish(backend, result)
+    }
+
+    /// Show how an action went and reload, only errors Gust can't carry on from are returned
+    pub fn finish(
+        &mut self,
+        backend: &impl GitBackend,
+        result: Result<String, GustError>,
+    ) -> Result<(), GustError> {
+        self.message = Some(match result {
+            Ok(done) => Message::Info(done),
+            Err(GustError::Cancelled) => Message::Info("Cancelled".to_string()),
+            Err(e) if e.is_recoverable() => Message::Error(utils::strip_colors(e.to_string())),
+            Err(e) => return Err(e),
+        });
+        self.refresh(backend)
+    }
+}
+
+/// Untracked files have no diff, so show their content as if every line was added
+fn untracked_preview(backend: &impl GitBackend, entry: &FileEntry) -> Result<String, GustError> {
+    let path = backend.toplevel()?.join(&entry.path);
+    if path.is_dir() {
+        return Ok(format!("{} is an untracked directory", entry.label()));
+    }
+    Ok(match std::fs::read_to_string(&path) {
+        Ok(content) => content.lines().map(|l| format!("+{}\n", l)).collect(),
+        Err(e) => format!("{}: {}", entry.label(), e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_repo::TestRepo;
+    use crate::backend::GitCli;
+
+    #[test]
+    fn staging_reports_in_the_status_bar() {
+        let repo = TestRepo::new();
+        repo.commit_file("a.txt", "a\n", "Add a.txt");
+        repo.write("a.txt", "changed\n");
+
+        git::set_full_screen(true);
+        let mut app = App::new(&GitCli).unwrap();
+        let staged = app.toggle_selected(&GitCli);
+        git::set_full_screen(false);
+
+        staged.unwrap();
+        assert!(matches!(&app.message, Some(Message::Info(m)) if m == "Staged a.txt"));
+        assert_eq!(repo.git(&["diff", "--cached", "--name-only"]), "a.txt");
+    }
+}
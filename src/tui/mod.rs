@@ -0,0 +1,109 @@
+mod app;
+mod ui;
+
+use crate::backend::GitBackend;
+use crate::error::GustError;
+use crate::git;
+use crate::menus;
+use crate::settings::Config;
+use app::App;
+
+use colored::Colorize;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::Terminal;
+use std::io::{self, Stdout};
+
+type FullScreen = Terminal<CrosstermBackend<Stdout>>;
+
+/// Run the full-screen mode until the user quits, the prompt menus are used for
+/// anything that needs more than a key press
+pub fn run(backend: &impl GitBackend, config: &Config) -> Result<(), GustError> {
+    let mut app = App::new(backend)?;
+    let mut terminal = enter()?;
+    let result = event_loop(&mut terminal, &mut app, backend, config);
+    leave(&mut terminal)?;
+    result
+}
+
+fn enter() -> Result<FullScreen, GustError> {
+    terminal::enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    git::set_full_screen(true);
+    Ok(Terminal::new(CrosstermBackend::new(io::stdout()))?)
+}
+
+fn leave(terminal: &mut FullScreen) -> Result<(), GustError> {
+    git::set_full_screen(false);
+    terminal::disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+fn event_loop(
+    terminal: &mut FullScreen,
+    app: &mut App,
+    backend: &impl GitBackend,
+    config: &Config,
+) -> Result<(), GustError> {
+    loop {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Tab => app.next_pane(backend)?,
+            KeyCode::BackTab => app.previous_pane(backend)?,
+            KeyCode::Up | KeyCode::Char('k') => app.move_selection(backend, -1)?,
+            KeyCode::Down | KeyCode::Char('j') => app.move_selection(backend, 1)?,
+            KeyCode::PageUp => app.scroll_preview(-10),
+            KeyCode::PageDown => app.scroll_preview(10),
+            KeyCode::Char(' ') | KeyCode::Enter => app.toggle_selected(backend)?,
+            KeyCode::Char('a') => app.stage_all(backend)?,
+            KeyCode::Char('u') => app.unstage_all(backend)?,
+            KeyCode::Char('r') => {
+                app.message = None;
+                app.refresh(backend)?;
+            }
+            KeyCode::Char('c') => {
+                let result = suspend(terminal, || menus::git_commit_cli(backend, config))?;
+                app.finish(backend, result.map(|_| "Commit menu closed".to_string()))?;
+            }
+            KeyCode::Char('p') => {
+                let result = suspend(terminal, || menus::git_push_cli(backend))?;
+                app.finish(backend, result.map(|_| "Push finished".to_string()))?;
+            }
+            KeyCode::Char('P') => {
+                let result = suspend(terminal, || menus::git_pull_cli(backend))?;
+                app.finish(backend, result.map(|_| "Pull finished".to_string()))?;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Leave the full screen to run a prompt menu, then wait for a key so its output can be read
+fn suspend(
+    terminal: &mut FullScreen,
+    menu: impl FnOnce() -> Result<(), GustError>,
+) -> Result<Result<(), GustError>, GustError> {
+    leave(terminal)?;
+    let result = menu();
+    if let Err(e) = &result {
+        eprintln!("{}", e.to_string().bright_red());
+    }
+    println!("{}", "Press any key to return".bright_black());
+    console::Term::stdout().read_key()?;
+
+    *terminal = enter()?;
+    terminal.clear()?;
+    Ok(result)
+}
@@ -0,0 +1,160 @@
+use super::app::{App, Message, Pane};
+use crate::status::FileEntry;
+
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
+
+const KEYS: &[(&str, &str)] = &[
+    ("tab", "pane"),
+    ("↑↓", "move"),
+    ("space", "stage/unstage"),
+    ("a/u", "stage/unstage all"),
+    ("c", "commit"),
+    ("p/P", "push/pull"),
+    ("PgUp/PgDn", "scroll"),
+    ("r", "refresh"),
+    ("q", "quit"),
+];
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Min(8),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(rows[1]);
+
+    let lists = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(columns[0]);
+
+    draw_status(frame, app, rows[0]);
+
+    let unstaged = file_list(&app.unstaged_entries(), true);
+    let staged = file_list(&app.staged_entries(), false);
+    frame.render_stateful_widget(
+        list(unstaged, "Unstaged", app.focus == Pane::Unstaged),
+        lists[0],
+        &mut app.unstaged,
+    );
+    frame.render_stateful_widget(
+        list(staged, "Staged", app.focus == Pane::Staged),
+        lists[1],
+        &mut app.staged,
+    );
+
+    let preview = Paragraph::new(diff_text(&app.preview))
+        .block(pane_block("Preview", false))
+        .scroll((app.preview_scroll, 0));
+    frame.render_widget(preview, columns[1]);
+
+    let log = app.log.iter().map(|l| ListItem::new(l.as_str())).collect();
+    frame.render_stateful_widget(
+        list(log, "Log", app.focus == Pane::Log),
+        rows[2],
+        &mut app.log_state,
+    );
+
+    draw_footer(frame, rows[3]);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let mut summary = vec![Span::raw(app.status.branch.summary()).bold()];
+    if let Some(operation) = app.operation {
+        summary.push(Span::raw(format!("  {} in progress", operation)).red());
+    }
+    if app.dry_run {
+        summary.push(Span::raw("  dry run").yellow());
+    }
+
+    let message = match &app.message {
+        Some(Message::Info(text)) => Line::from(text.as_str()).green(),
+        Some(Message::Error(text)) => Line::from(text.lines().next().unwrap_or_default()).red(),
+        None => Line::default(),
+    };
+
+    let status =
+        Paragraph::new(vec![Line::from(summary), message]).block(pane_block("Gust", false));
+    frame.render_widget(status, area);
+}
+
+fn draw_footer(frame: &mut Frame, area: Rect) {
+    let spans: Vec<Span> = KEYS
+        .iter()
+        .flat_map(|(key, action)| {
+            [
+                Span::raw(*key).bold().cyan(),
+                Span::raw(format!(" {}  ", action)),
+            ]
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn file_list(entries: &[&FileEntry], unstaged: bool) -> Vec<ListItem<'static>> {
+    entries
+        .iter()
+        .map(|entry| {
+            let (state, color) = if entry.is_unmerged() {
+                ('U', Color::Red)
+            } else if unstaged {
+                (entry.worktree.code(), Color::Red)
+            } else {
+                (entry.index.code(), Color::Green)
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", state), Style::default().fg(color)),
+                Span::raw(entry.label()),
+            ]))
+        })
+        .collect()
+}
+
+fn list<'a>(items: Vec<ListItem<'a>>, title: &'a str, focused: bool) -> List<'a> {
+    List::new(items)
+        .block(pane_block(title, focused))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+}
+
+fn pane_block(title: &str, focused: bool) -> Block<'_> {
+    let style = if focused {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(style)
+        .title(format!(" {} ", title))
+}
+
+/// Color a diff the way `git diff --color` would
+fn diff_text(diff: &str) -> Text<'_> {
+    diff.lines()
+        .map(|line| {
+            let style = if line.starts_with("+++") || line.starts_with("---") {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else if line.starts_with('+') {
+                Style::default().fg(Color::Green)
+            } else if line.starts_with('-') {
+                Style::default().fg(Color::Red)
+            } else if line.starts_with("@@") {
+                Style::default().fg(Color::Cyan)
+            } else if line.starts_with("diff ") || line.starts_with("commit ") {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            Line::styled(line, style)
+        })
+        .collect()
+}
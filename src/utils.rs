@@ -1,7 +1,5 @@
-use console;
-
 pub fn strip_colors(input: String) -> String {
-    return console::strip_ansi_codes(input.as_str()).to_string();
+    console::strip_ansi_codes(input.as_str()).to_string()
 }
 
 pub fn strip_vec_colors(mut input: Vec<String>) -> Vec<String> {
@@ -12,5 +10,5 @@ pub fn strip_vec_colors(mut input: Vec<String>) -> Vec<String> {
     for i in &mut input {
         *i = strip_colors((*i).to_string());
     }
-    return input;
+    input
 }